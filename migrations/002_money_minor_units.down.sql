@@ -0,0 +1,39 @@
+-- Restore the REAL price column. The old schema has no currency, so the
+-- currency column is dropped and every amount is read as cents.
+--
+-- Amounts in currencies without exactly 2 decimals (JPY, KWD, ...) cannot be
+-- read as cents, so the rollback is refused while any such product exists:
+-- the CHECK below fails and the migration's transaction is rolled back.
+CREATE TEMP TABLE money_rollback_guard (
+    products_not_in_cents INTEGER NOT NULL
+        CONSTRAINT only_two_decimal_currencies_can_be_rolled_back CHECK (products_not_in_cents = 0)
+);
+
+INSERT INTO money_rollback_guard (products_not_in_cents)
+SELECT COUNT(*) FROM products
+WHERE currency IN (
+    'BIF', 'CLP', 'DJF', 'GNF', 'ISK', 'JPY', 'KMF', 'KRW', 'PYG', 'RWF',
+    'UGX', 'UYI', 'VND', 'VUV', 'XAF', 'XOF', 'XPF',
+    'BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND',
+    'CLF', 'UYW'
+);
+
+DROP TABLE money_rollback_guard;
+
+CREATE TABLE products_old (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    description TEXT,
+    price REAL NOT NULL,
+    stock INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+INSERT INTO products_old (id, name, description, price, stock, created_at, updated_at)
+SELECT id, name, description, price_minor / 100.0, stock, created_at, updated_at
+FROM products;
+
+DROP TABLE products;
+
+ALTER TABLE products_old RENAME TO products;
@@ -0,0 +1,21 @@
+-- Store prices as integer minor units with an explicit ISO-4217 currency.
+-- Existing REAL prices were always rounded to 2 decimals, so ROUND(price * 100)
+-- recovers the exact cent amount.
+CREATE TABLE products_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    description TEXT,
+    price_minor INTEGER NOT NULL CHECK (price_minor >= 0),
+    currency TEXT NOT NULL DEFAULT 'USD',
+    stock INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+INSERT INTO products_new (id, name, description, price_minor, currency, stock, created_at, updated_at)
+SELECT id, name, description, CAST(ROUND(price * 100) AS INTEGER), 'USD', stock, created_at, updated_at
+FROM products;
+
+DROP TABLE products;
+
+ALTER TABLE products_new RENAME TO products;
@@ -0,0 +1,2 @@
+-- Row version for optimistic concurrency control; bumped on every update.
+ALTER TABLE products ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,16 @@
+-- Units of stock held by active reservations. The CHECK guarantees that
+-- reservations can never exceed the stock on hand, whatever the writer does.
+ALTER TABLE products ADD COLUMN reserved INTEGER NOT NULL DEFAULT 0 CHECK (reserved >= 0 AND reserved <= stock);
+
+CREATE TABLE stock_reservations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    product_id INTEGER NOT NULL REFERENCES products (id) ON DELETE CASCADE,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    status TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'committed', 'released', 'expired')),
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_stock_reservations_active ON stock_reservations (status, expires_at);
+CREATE INDEX idx_stock_reservations_product ON stock_reservations (product_id);
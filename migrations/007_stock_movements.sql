@@ -0,0 +1,20 @@
+-- Ledger of every change to products.stock. The sum of a product's deltas always
+-- equals its stock on hand. Rows outlive their product so history is never lost.
+CREATE TABLE stock_movements (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    product_id INTEGER NOT NULL,
+    delta INTEGER NOT NULL CHECK (delta != 0),
+    reason TEXT NOT NULL CHECK (reason IN ('sale', 'restock', 'correction', 'return')),
+    actor TEXT NOT NULL,
+    note TEXT,
+    stock_after INTEGER NOT NULL CHECK (stock_after >= 0),
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_stock_movements_product ON stock_movements (product_id, id);
+
+-- Opening balance for stock that predates the ledger
+INSERT INTO stock_movements (product_id, delta, reason, actor, note, stock_after, created_at)
+SELECT id, stock, 'correction', 'system', 'Opening balance', stock, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+FROM products
+WHERE stock != 0;
@@ -0,0 +1,19 @@
+-- Session-backed shopping carts. Lines keep the price captured when the product was added.
+CREATE TABLE carts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    token TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE cart_items (
+    cart_id INTEGER NOT NULL REFERENCES carts (id) ON DELETE CASCADE,
+    product_id INTEGER NOT NULL REFERENCES products (id) ON DELETE CASCADE,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    unit_price_minor INTEGER NOT NULL CHECK (unit_price_minor >= 0),
+    currency TEXT NOT NULL,
+    added_at TEXT NOT NULL,
+    PRIMARY KEY (cart_id, product_id)
+);
+
+CREATE INDEX idx_cart_items_product ON cart_items (product_id);
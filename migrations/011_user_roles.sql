@@ -0,0 +1,3 @@
+-- Roles for catalog authorization. Every existing and new account starts as a viewer.
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'viewer'
+    CHECK (role IN ('admin', 'catalog_editor', 'viewer'));
@@ -0,0 +1,13 @@
+-- API keys for machine clients of /api. Keys are stored by a SHA-256 hash of the secret;
+-- `prefix` holds its first characters so admins can tell keys apart.
+CREATE TABLE api_keys (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    scopes TEXT NOT NULL,
+    rate_limit_per_minute INTEGER CHECK (rate_limit_per_minute > 0),
+    created_at TEXT NOT NULL,
+    last_used_at TEXT,
+    revoked_at TEXT
+);
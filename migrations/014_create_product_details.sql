@@ -0,0 +1,18 @@
+-- Free-form tags and key/value attributes on products, one row each. Both go when the
+-- product is deleted. The value indexes serve facet filters and counts.
+CREATE TABLE product_tags (
+    product_id INTEGER NOT NULL REFERENCES products (id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (product_id, tag)
+);
+
+CREATE INDEX idx_product_tags_tag ON product_tags (tag);
+
+CREATE TABLE product_attributes (
+    product_id INTEGER NOT NULL REFERENCES products (id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (product_id, key)
+);
+
+CREATE INDEX idx_product_attributes_key_value ON product_attributes (key, value);
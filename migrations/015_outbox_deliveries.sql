@@ -0,0 +1,8 @@
+-- Which event handlers have taken each outbox event, so a retry only goes to the
+-- handlers that failed.
+CREATE TABLE outbox_deliveries (
+    event_id INTEGER NOT NULL REFERENCES outbox (id) ON DELETE CASCADE,
+    handler TEXT NOT NULL,
+    delivered_at TEXT NOT NULL,
+    PRIMARY KEY (event_id, handler)
+);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_orders_user;
+ALTER TABLE orders DROP COLUMN user_id;
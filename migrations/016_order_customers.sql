@@ -0,0 +1,5 @@
+-- The customer who was signed in when an order was placed, so their orders follow them
+-- to a new cart session. No foreign key, like order items, so orders outlive the account.
+ALTER TABLE orders ADD COLUMN user_id INTEGER;
+
+CREATE INDEX idx_orders_user ON orders (user_id, id);
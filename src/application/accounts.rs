@@ -0,0 +1,313 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::application::dtos::{LoginRequest, RegisterRequest, SetRoleRequest, UserResponse};
+use crate::application::policy::{Actor, Permission};
+use crate::application::services::ApplicationError;
+use crate::domain::{
+    validate_password, Email, PasswordHasher, RepositoryError, Role, SessionRepository, User,
+    UserId, UserRepository,
+};
+
+/// How long a sign-in lasts
+pub const SESSION_TTL_DAYS: i64 = 14;
+
+/// Argon2id hash, with the default cost parameters, that no password matches. Checked
+/// when an email is unknown so that the answer takes as long as for a wrong password.
+const DUMMY_PASSWORD_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$yXX7aw/TDA/DT9i6c9K9Rw$T8AeedoZeCCOm6XKtKpk/GWjtoq2O9HYmnEc3xfEjw8";
+
+/// A new sign-in: the account and the token to hand to the browser
+#[derive(Debug)]
+pub struct SignedIn {
+    pub user: UserResponse,
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Application service for customer accounts and their sessions.
+///
+/// Session tokens are random and only their SHA-256 hash is stored.
+pub struct AccountService {
+    users: Arc<dyn UserRepository>,
+    sessions: Arc<dyn SessionRepository>,
+    hasher: Arc<dyn PasswordHasher>,
+}
+
+impl AccountService {
+    pub fn new(
+        users: Arc<dyn UserRepository>,
+        sessions: Arc<dyn SessionRepository>,
+        hasher: Arc<dyn PasswordHasher>,
+    ) -> Self {
+        Self { users, sessions, hasher }
+    }
+
+    /// Create an account and sign it in
+    pub async fn register(&self, request: RegisterRequest) -> Result<SignedIn, ApplicationError> {
+        let email = Email::new(&request.email).map_err(ApplicationError::DomainError)?;
+        validate_password(&request.password).map_err(ApplicationError::DomainError)?;
+
+        let taken = || ApplicationError::validation("An account with this email already exists");
+        if self.users.find_by_email(&email).await
+            .map_err(ApplicationError::RepositoryError)?
+            .is_some()
+        {
+            return Err(taken());
+        }
+
+        let password_hash = self.hasher.hash(&request.password)
+            .map_err(ApplicationError::DomainError)?;
+        let id = self.users.next_id().await
+            .map_err(ApplicationError::RepositoryError)?;
+        let user = User::register(id, email, &request.name, password_hash)
+            .map_err(ApplicationError::DomainError)?;
+
+        let user = match self.users.create(user).await {
+            Ok(user) => user,
+            // Lost a race with another registration for the same email
+            Err(RepositoryError::ConstraintViolation(_)) => return Err(taken()),
+            Err(err) => return Err(ApplicationError::RepositoryError(err)),
+        };
+        tracing::info!(user_id = user.id().value(), "account registered");
+
+        self.start_session(user).await
+    }
+
+    /// Sign in with email and password. Unknown emails and wrong passwords are
+    /// reported the same way, and both pay for one password hash check.
+    pub async fn login(&self, request: LoginRequest) -> Result<SignedIn, ApplicationError> {
+        let email = Email::new(&request.email).map_err(|_| ApplicationError::InvalidCredentials)?;
+        let user = self.users.find_by_email(&email).await
+            .map_err(ApplicationError::RepositoryError)?;
+        let password_hash = user.as_ref().map_or(DUMMY_PASSWORD_HASH, |user| user.password_hash());
+        let verified = self.hasher.verify(&request.password, password_hash);
+        let user = user
+            .filter(|_| verified)
+            .ok_or(ApplicationError::InvalidCredentials)?;
+
+        self.sessions.delete_expired_sessions(Utc::now()).await
+            .map_err(ApplicationError::RepositoryError)?;
+        self.start_session(user).await
+    }
+
+    pub async fn logout(&self, token: &str) -> Result<(), ApplicationError> {
+        self.sessions.delete_session(&hash_token(token)).await
+            .map_err(ApplicationError::RepositoryError)
+    }
+
+    /// The account signed in with `token`, if the session is still valid
+    pub async fn current_user(&self, token: &str) -> Result<Option<UserResponse>, ApplicationError> {
+        let user = self.sessions.find_session_user(&hash_token(token), Utc::now()).await
+            .map_err(ApplicationError::RepositoryError)?;
+        Ok(user.map(UserResponse::from))
+    }
+
+    /// Change the role of account `id`; only admins may do this
+    pub async fn set_role(
+        &self,
+        id: i64,
+        request: SetRoleRequest,
+        actor: &Actor,
+    ) -> Result<UserResponse, ApplicationError> {
+        actor.authorize(Permission::ManageRoles)?;
+        let role = Role::try_from(request.role.as_str()).map_err(ApplicationError::DomainError)?;
+        let user_id = UserId::new(id).map_err(ApplicationError::DomainError)?;
+
+        let user = self.users.find_by_id(&user_id).await
+            .map_err(ApplicationError::RepositoryError)?
+            .ok_or(ApplicationError::UserNotFound)?;
+        self.change_role(user, role, actor).await
+    }
+
+    /// Change the role of the account registered with `email`, e.g. to promote the first admin
+    pub async fn grant_role(&self, email: &str, role: &str, actor: &Actor) -> Result<UserResponse, ApplicationError> {
+        actor.authorize(Permission::ManageRoles)?;
+        let role = Role::try_from(role).map_err(ApplicationError::DomainError)?;
+        let email = Email::new(email).map_err(ApplicationError::DomainError)?;
+
+        let user = self.users.find_by_email(&email).await
+            .map_err(ApplicationError::RepositoryError)?
+            .ok_or(ApplicationError::UserNotFound)?;
+        self.change_role(user, role, actor).await
+    }
+
+    async fn change_role(&self, mut user: User, role: Role, actor: &Actor) -> Result<UserResponse, ApplicationError> {
+        user.set_role(role);
+        let user = self.users.update(user).await
+            .map_err(ApplicationError::RepositoryError)?;
+        tracing::info!(user_id = user.id().value(), role = role.as_str(), actor = actor.name(), "role changed");
+
+        Ok(UserResponse::from(user))
+    }
+
+    async fn start_session(&self, user: User) -> Result<SignedIn, ApplicationError> {
+        let token = format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let expires_at = Utc::now() + Duration::days(SESSION_TTL_DAYS);
+        self.sessions.create_session(&hash_token(&token), user.id(), expires_at).await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        Ok(SignedIn { user: UserResponse::from(user), token, expires_at })
+    }
+}
+
+/// SHA-256 hex digest of a bearer secret, the form in which sessions and API keys are stored
+pub(crate) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::SqlitePool;
+
+    use crate::domain::DomainError;
+    use crate::infrastructure::{migrated_pool, Argon2PasswordHasher, SqliteUserRepository};
+
+    async fn service() -> (AccountService, SqlitePool) {
+        let pool = migrated_pool().await;
+
+        let users = Arc::new(SqliteUserRepository::new(pool.clone()));
+        let service = AccountService::new(users.clone(), users, Arc::new(Argon2PasswordHasher::new()));
+        (service, pool)
+    }
+
+    fn register(email: &str, password: &str) -> RegisterRequest {
+        RegisterRequest {
+            email: email.to_string(),
+            name: "Alice".to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    fn login(email: &str, password: &str) -> LoginRequest {
+        LoginRequest { email: email.to_string(), password: password.to_string() }
+    }
+
+    #[tokio::test]
+    async fn registered_customers_can_sign_in_and_out() {
+        let (accounts, pool) = service().await;
+        let registered = accounts.register(register(" Alice@Example.com ", "correct horse")).await.unwrap();
+        assert_eq!(registered.user.email, "alice@example.com");
+
+        let stored: String = sqlx::query_scalar("SELECT password_hash FROM users").fetch_one(&pool).await.unwrap();
+        assert!(stored.starts_with("$argon2id$"));
+        let session: String = sqlx::query_scalar("SELECT token_hash FROM sessions").fetch_one(&pool).await.unwrap();
+        assert_ne!(session, registered.token);
+
+        let signed_in = accounts.login(login("ALICE@example.com", "correct horse")).await.unwrap();
+        assert_ne!(signed_in.token, registered.token);
+        let current = accounts.current_user(&signed_in.token).await.unwrap().unwrap();
+        assert_eq!((current.id, current.name.as_str()), (registered.user.id, "Alice"));
+
+        accounts.logout(&signed_in.token).await.unwrap();
+        assert!(accounts.current_user(&signed_in.token).await.unwrap().is_none());
+        assert!(accounts.current_user(&registered.token).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn wrong_passwords_and_unknown_emails_are_refused_alike() {
+        let (accounts, _) = service().await;
+        accounts.register(register("alice@example.com", "correct horse")).await.unwrap();
+
+        assert!(matches!(
+            accounts.login(login("alice@example.com", "wrong horse")).await,
+            Err(ApplicationError::InvalidCredentials)
+        ));
+        assert!(matches!(
+            accounts.login(login("bob@example.com", "correct horse")).await,
+            Err(ApplicationError::InvalidCredentials)
+        ));
+        assert!(matches!(
+            accounts.register(register("ALICE@example.com", "another password")).await,
+            Err(ApplicationError::ValidationError(_))
+        ));
+        assert!(matches!(
+            accounts.register(register("carol@example.com", "short")).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidUser(_)))
+        ));
+        assert!(matches!(
+            accounts.register(register("not-an-email", "long enough")).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidUser(_)))
+        ));
+    }
+
+    /// Argon2, remembering every hash a password was checked against
+    #[derive(Default)]
+    struct RecordingHasher {
+        argon2: Argon2PasswordHasher,
+        verified: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl PasswordHasher for RecordingHasher {
+        fn hash(&self, password: &str) -> Result<String, DomainError> {
+            self.argon2.hash(password)
+        }
+
+        fn verify(&self, password: &str, password_hash: &str) -> bool {
+            self.verified.lock().unwrap().push(password_hash.to_string());
+            self.argon2.verify(password, password_hash)
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_emails_still_pay_for_a_password_check() {
+        let users = Arc::new(SqliteUserRepository::new(migrated_pool().await));
+        let hasher = Arc::new(RecordingHasher::default());
+        let accounts = AccountService::new(users.clone(), users, hasher.clone());
+
+        assert!(matches!(
+            accounts.login(login("bob@example.com", "not a password")).await,
+            Err(ApplicationError::InvalidCredentials)
+        ));
+        assert_eq!(*hasher.verified.lock().unwrap(), vec![DUMMY_PASSWORD_HASH.to_string()]);
+        // Same cost parameters as a freshly hashed password
+        let real = hasher.hash("correct horse").unwrap();
+        assert_eq!(real.split('$').nth(3), DUMMY_PASSWORD_HASH.split('$').nth(3));
+    }
+
+    #[tokio::test]
+    async fn expired_sessions_are_not_accepted() {
+        let (accounts, pool) = service().await;
+        let signed_in = accounts.register(register("alice@example.com", "correct horse")).await.unwrap();
+        sqlx::query("UPDATE sessions SET expires_at = '2000-01-01T00:00:00Z'").execute(&pool).await.unwrap();
+
+        assert!(accounts.current_user(&signed_in.token).await.unwrap().is_none());
+        accounts.login(login("alice@example.com", "correct horse")).await.unwrap();
+        let sessions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions").fetch_one(&pool).await.unwrap();
+        assert_eq!(sessions, 1);
+    }
+
+    #[tokio::test]
+    async fn only_admins_can_change_roles() {
+        let (accounts, _) = service().await;
+        let alice = accounts.register(register("alice@example.com", "correct horse")).await.unwrap().user;
+        let bob = accounts.register(register("bob@example.com", "correct horse")).await.unwrap().user;
+        assert_eq!(alice.role, "viewer");
+
+        let promote = || SetRoleRequest { role: "catalog_editor".to_string() };
+        assert!(matches!(
+            accounts.set_role(bob.id, promote(), &Actor::user(&alice)).await,
+            Err(ApplicationError::AuthorizationError(_))
+        ));
+
+        let alice = accounts.grant_role("ALICE@example.com", "admin", &Actor::system("test")).await.unwrap();
+        assert_eq!(alice.role, "admin");
+        let bob = accounts.set_role(bob.id, promote(), &Actor::user(&alice)).await.unwrap();
+        assert_eq!(bob.role, "catalog_editor");
+        assert!(matches!(
+            accounts.set_role(bob.id, SetRoleRequest { role: "owner".to_string() }, &Actor::user(&alice)).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidUser(_)))
+        ));
+        assert!(matches!(
+            accounts.set_role(99, promote(), &Actor::user(&alice)).await,
+            Err(ApplicationError::UserNotFound)
+        ));
+    }
+}
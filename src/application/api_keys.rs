@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::application::accounts::hash_token;
+use crate::application::dtos::{ApiKeyResponse, CreateApiKeyRequest};
+use crate::application::policy::{Actor, Permission};
+use crate::application::services::ApplicationError;
+use crate::domain::{ApiKey, ApiKeyId, ApiKeyRepository, ApiScope};
+
+/// Marks our keys in logs and secret scanners
+pub const API_KEY_PREFIX: &str = "ek_";
+/// Characters of a key kept in clear so admins can tell keys apart
+const VISIBLE_KEY_LENGTH: usize = 11;
+/// Length of the fixed window the per-key rate limit counts requests in
+const RATE_LIMIT_WINDOW_SECS: i64 = 60;
+
+/// Requests a key made in its current rate-limit window
+struct RateWindow {
+    started_at: DateTime<Utc>,
+    requests: u32,
+}
+
+/// Application service for API keys: issuing and revoking them, and authenticating the
+/// machine clients that present them.
+///
+/// Rate-limit counters live in memory, so they are per process and reset on restart.
+pub struct ApiKeyService {
+    repository: Arc<dyn ApiKeyRepository>,
+    windows: Mutex<HashMap<i64, RateWindow>>,
+}
+
+impl ApiKeyService {
+    pub fn new(repository: Arc<dyn ApiKeyRepository>) -> Self {
+        Self { repository, windows: Mutex::new(HashMap::new()) }
+    }
+
+    /// Issue a key. The secret is returned only in this response.
+    pub async fn issue_key(
+        &self,
+        request: CreateApiKeyRequest,
+        actor: &Actor,
+    ) -> Result<ApiKeyResponse, ApplicationError> {
+        actor.authorize(Permission::ManageApiKeys)?;
+        let scopes = request.scopes.iter()
+            .map(|scope| ApiScope::try_from(scope.as_str()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ApplicationError::DomainError)?;
+
+        let secret = format!(
+            "{}{}{}",
+            API_KEY_PREFIX,
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let id = self.repository.next_id().await
+            .map_err(ApplicationError::RepositoryError)?;
+        let key = ApiKey::issue(
+            id,
+            &request.name,
+            secret[..VISIBLE_KEY_LENGTH].to_string(),
+            hash_token(&secret),
+            scopes,
+            request.rate_limit_per_minute,
+        )
+        .map_err(ApplicationError::DomainError)?;
+
+        let saved = self.repository.save(key).await
+            .map_err(ApplicationError::RepositoryError)?;
+        tracing::info!(api_key_id = saved.id().value(), actor = actor.name(), "API key issued");
+
+        let mut response = ApiKeyResponse::from(saved);
+        response.key = Some(secret);
+        Ok(response)
+    }
+
+    pub async fn list_keys(&self, actor: &Actor) -> Result<Vec<ApiKeyResponse>, ApplicationError> {
+        actor.authorize(Permission::ManageApiKeys)?;
+        let keys = self.repository.find_all().await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        Ok(keys.into_iter().map(ApiKeyResponse::from).collect())
+    }
+
+    /// Revoke a key; revoking it again is harmless
+    pub async fn revoke_key(&self, id: i64, actor: &Actor) -> Result<ApiKeyResponse, ApplicationError> {
+        actor.authorize(Permission::ManageApiKeys)?;
+        let key_id = ApiKeyId::new(id).map_err(ApplicationError::DomainError)?;
+
+        if self.repository.revoke(&key_id, Utc::now()).await
+            .map_err(ApplicationError::RepositoryError)?
+        {
+            tracing::info!(api_key_id = id, actor = actor.name(), "API key revoked");
+            self.windows.lock().unwrap().remove(&id);
+        }
+
+        let key = self.repository.find_by_id(&key_id).await
+            .map_err(ApplicationError::RepositoryError)?
+            .ok_or(ApplicationError::ApiKeyNotFound)?;
+        Ok(ApiKeyResponse::from(key))
+    }
+
+    /// Check a presented key and count the request against its rate limit. Unknown and
+    /// revoked keys are reported the same way; scopes are checked by the services.
+    pub async fn authenticate(&self, secret: &str) -> Result<ApiKeyResponse, ApplicationError> {
+        let key = self.repository.find_by_hash(&hash_token(secret)).await
+            .map_err(ApplicationError::RepositoryError)?
+            .filter(ApiKey::is_active)
+            .ok_or(ApplicationError::InvalidApiKey)?;
+
+        let now = Utc::now();
+        self.count_request(&key, now)?;
+        self.repository.record_use(key.id(), now).await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        let mut response = ApiKeyResponse::from(key);
+        response.last_used_at = Some(now);
+        Ok(response)
+    }
+
+    fn count_request(&self, key: &ApiKey, now: DateTime<Utc>) -> Result<(), ApplicationError> {
+        let Some(limit) = key.rate_limit_per_minute() else {
+            return Ok(());
+        };
+        let window_length = Duration::seconds(RATE_LIMIT_WINDOW_SECS);
+
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows
+            .entry(key.id().value())
+            .or_insert(RateWindow { started_at: now, requests: 0 });
+        if now - window.started_at >= window_length {
+            *window = RateWindow { started_at: now, requests: 0 };
+        }
+        if window.requests >= limit {
+            let retry_after = (window.started_at + window_length - now).num_seconds().max(1);
+            return Err(ApplicationError::RateLimited { retry_after_secs: retry_after as u64 });
+        }
+        window.requests += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::SqlitePool;
+
+    use crate::application::UserResponse;
+    use crate::domain::DomainError;
+    use crate::infrastructure::{migrated_pool, SqliteApiKeyRepository};
+
+    async fn service() -> (ApiKeyService, SqlitePool) {
+        let pool = migrated_pool().await;
+
+        let service = ApiKeyService::new(Arc::new(SqliteApiKeyRepository::new(pool.clone())));
+        (service, pool)
+    }
+
+    fn request(scopes: &[&str], rate_limit_per_minute: Option<u32>) -> CreateApiKeyRequest {
+        CreateApiKeyRequest {
+            name: "Inventory sync".to_string(),
+            scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+            rate_limit_per_minute,
+        }
+    }
+
+    #[tokio::test]
+    async fn issued_keys_are_stored_hashed_and_can_be_revoked() {
+        let (keys, pool) = service().await;
+        let admin = Actor::system("test");
+
+        let issued = keys.issue_key(request(&["products:read", "stock:manage"], None), &admin).await.unwrap();
+        let secret = issued.key.clone().unwrap();
+        assert!(secret.starts_with("ek_") && secret.starts_with(&issued.prefix));
+        let stored: String = sqlx::query_scalar("SELECT key_hash FROM api_keys").fetch_one(&pool).await.unwrap();
+        assert!(!stored.contains(&secret[3..]));
+        assert!(keys.list_keys(&admin).await.unwrap().iter().all(|key| key.key.is_none()));
+
+        let used = keys.authenticate(&secret).await.unwrap();
+        assert_eq!(used.id, issued.id);
+        assert!(keys.list_keys(&admin).await.unwrap()[0].last_used_at.is_some());
+        let actor = Actor::api_key(&used);
+        assert!(actor.can(Permission::ViewStock) && actor.can(Permission::AdjustStock));
+        assert!(matches!(
+            actor.authorize(Permission::EditProduct),
+            Err(ApplicationError::AuthorizationError(_))
+        ));
+        assert!(matches!(
+            keys.authenticate("ek_not-a-key").await,
+            Err(ApplicationError::InvalidApiKey)
+        ));
+
+        let revoked = keys.revoke_key(issued.id, &admin).await.unwrap();
+        assert!(!revoked.active && revoked.revoked_at.is_some());
+        assert!(matches!(
+            keys.authenticate(&secret).await,
+            Err(ApplicationError::InvalidApiKey)
+        ));
+        assert!(matches!(keys.revoke_key(99, &admin).await, Err(ApplicationError::ApiKeyNotFound)));
+    }
+
+    #[tokio::test]
+    async fn only_admins_issue_keys_with_known_scopes() {
+        let (keys, _) = service().await;
+        let editor = Actor::user(&UserResponse {
+            id: 1,
+            email: "editor@example.com".to_string(),
+            name: "Editor".to_string(),
+            role: "catalog_editor".to_string(),
+            created_at: Utc::now(),
+        });
+
+        assert!(matches!(
+            keys.issue_key(request(&["products:read"], None), &editor).await,
+            Err(ApplicationError::AuthorizationError(_))
+        ));
+        assert!(matches!(
+            keys.issue_key(request(&["products:delete"], None), &Actor::system("test")).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidApiKey(_)))
+        ));
+        assert!(matches!(
+            keys.issue_key(request(&[], None), &Actor::system("test")).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidApiKey(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn each_key_has_its_own_rate_limit() {
+        let (keys, _) = service().await;
+        let admin = Actor::system("test");
+        let limited = keys.issue_key(request(&["products:read"], Some(2)), &admin).await.unwrap().key.unwrap();
+        let unlimited = keys.issue_key(request(&["products:read"], None), &admin).await.unwrap().key.unwrap();
+
+        for _ in 0..2 {
+            keys.authenticate(&limited).await.unwrap();
+        }
+        assert!(matches!(
+            keys.authenticate(&limited).await,
+            Err(ApplicationError::RateLimited { retry_after_secs }) if (1..=60).contains(&retry_after_secs)
+        ));
+        for _ in 0..5 {
+            keys.authenticate(&unlimited).await.unwrap();
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use crate::application::dtos::{
+    AddCartItemRequest, CartItemResponse, CartResponse, UpdateCartItemRequest,
+};
+use crate::application::services::ApplicationError;
+use crate::domain::{Cart, CartRepository, Product, ProductId, ProductRepository};
+
+/// Application service for session-backed shopping carts.
+///
+/// Carts are found by the opaque token kept in the visitor's session; the first write
+/// for an unknown token creates the cart.
+pub struct CartService {
+    carts: Arc<dyn CartRepository>,
+    products: Arc<dyn ProductRepository>,
+}
+
+impl CartService {
+    pub fn new(carts: Arc<dyn CartRepository>, products: Arc<dyn ProductRepository>) -> Self {
+        Self { carts, products }
+    }
+
+    /// The cart for `token`; visitors without a stored cart get an empty one
+    pub async fn get_cart(&self, token: &str) -> Result<CartResponse, ApplicationError> {
+        let cart = self.load(token).await?;
+        self.respond(&cart).await
+    }
+
+    /// Add units of a product. The cart line may not exceed the product's available stock.
+    pub async fn add_item(
+        &self,
+        token: &str,
+        request: AddCartItemRequest,
+    ) -> Result<CartResponse, ApplicationError> {
+        let product = self.find_product(request.product_id).await?;
+        let mut cart = self.load(token).await?;
+
+        cart.add_item(&product, request.quantity.unwrap_or(1))
+            .map_err(ApplicationError::DomainError)?;
+
+        self.save(cart).await
+    }
+
+    /// Change the quantity of a product already in the cart; zero removes it
+    pub async fn update_item(
+        &self,
+        token: &str,
+        product_id: i64,
+        request: UpdateCartItemRequest,
+    ) -> Result<CartResponse, ApplicationError> {
+        let product = self.find_product(product_id).await?;
+        let mut cart = self.load(token).await?;
+
+        let found = cart.set_quantity(&product, request.quantity)
+            .map_err(ApplicationError::DomainError)?;
+        if !found {
+            return Err(ApplicationError::CartItemNotFound);
+        }
+
+        self.save(cart).await
+    }
+
+    pub async fn remove_item(&self, token: &str, product_id: i64) -> Result<CartResponse, ApplicationError> {
+        let product_id = ProductId::new(product_id).map_err(ApplicationError::DomainError)?;
+        let mut cart = self.load(token).await?;
+
+        if !cart.remove_item(&product_id) {
+            return Err(ApplicationError::CartItemNotFound);
+        }
+
+        self.save(cart).await
+    }
+
+    pub async fn clear_cart(&self, token: &str) -> Result<CartResponse, ApplicationError> {
+        let mut cart = self.load(token).await?;
+        if cart.lines().is_empty() {
+            return self.respond(&cart).await;
+        }
+
+        cart.clear();
+        self.save(cart).await
+    }
+
+    /// Stored cart for `token`, or a new unsaved one
+    async fn load(&self, token: &str) -> Result<Cart, ApplicationError> {
+        if let Some(cart) = self.carts.find_by_token(token).await
+            .map_err(ApplicationError::RepositoryError)?
+        {
+            return Ok(cart);
+        }
+
+        let id = self.carts.next_id().await
+            .map_err(ApplicationError::RepositoryError)?;
+        Cart::new(id, token.to_string()).map_err(ApplicationError::DomainError)
+    }
+
+    async fn save(&self, cart: Cart) -> Result<CartResponse, ApplicationError> {
+        let saved = self.carts.save(cart).await
+            .map_err(ApplicationError::RepositoryError)?;
+        self.respond(&saved).await
+    }
+
+    async fn find_product(&self, id: i64) -> Result<Product, ApplicationError> {
+        let product_id = ProductId::new(id).map_err(ApplicationError::DomainError)?;
+
+        self.products.find_by_id(&product_id).await
+            .map_err(ApplicationError::RepositoryError)?
+            .ok_or(ApplicationError::ProductNotFound)
+    }
+
+    /// Describe the cart with each product's current name and availability
+    async fn respond(&self, cart: &Cart) -> Result<CartResponse, ApplicationError> {
+        let mut items = Vec::with_capacity(cart.lines().len());
+        for line in cart.lines() {
+            let product = self.products.find_by_id(line.product_id()).await
+                .map_err(ApplicationError::RepositoryError)?;
+            let subtotal = line.subtotal().map_err(ApplicationError::DomainError)?;
+
+            items.push(CartItemResponse {
+                product_id: line.product_id().value(),
+                name: product.as_ref()
+                    .map_or_else(|| "Unavailable product".to_string(), |p| p.name().value().to_string()),
+                quantity: line.quantity(),
+                unit_price: line.unit_price().to_decimal(),
+                unit_price_minor: line.unit_price().amount_minor(),
+                subtotal: subtotal.to_decimal(),
+                subtotal_minor: subtotal.amount_minor(),
+                currency: line.unit_price().currency().code().to_string(),
+                available: product.as_ref().map_or(0, Product::available_stock),
+            });
+        }
+
+        let total = cart.total().map_err(ApplicationError::DomainError)?;
+        Ok(CartResponse {
+            items,
+            item_count: cart.item_count(),
+            total: total.to_decimal(),
+            total_minor: total.amount_minor(),
+            currency: total.currency().code().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::SqlitePool;
+
+    use crate::application::{Actor, ProductService, UpdateProductRequest};
+    use crate::domain::DomainError;
+    use crate::infrastructure::{migrated_pool, SqliteCartRepository, SqliteProductRepository};
+
+    async fn services() -> (CartService, ProductService, SqlitePool) {
+        let pool = migrated_pool().await;
+        let products = Arc::new(SqliteProductRepository::new(pool.clone()));
+        products.initialize().await.expect("seed data");
+
+        let carts = CartService::new(Arc::new(SqliteCartRepository::new(pool.clone())), products.clone());
+        (carts, ProductService::new(products), pool)
+    }
+
+    fn add(product_id: i64, quantity: i32) -> AddCartItemRequest {
+        AddCartItemRequest { product_id, quantity: Some(quantity) }
+    }
+
+    #[tokio::test]
+    async fn lines_keep_the_price_captured_when_added() {
+        let (carts, products, _) = services().await;
+
+        carts.add_item("alice", add(5, 2)).await.unwrap();
+        products.update_product(5, UpdateProductRequest {
+            name: None,
+            description: None,
+            price: Some(10.0),
+            currency: None,
+            stock: None,
+            version: None,
+            tags: None,
+            attributes: None,
+        }, &Actor::system("test")).await.unwrap();
+        let cart = carts.add_item("alice", add(5, 1)).await.unwrap();
+
+        assert_eq!(cart.items.len(), 1);
+        assert_eq!((cart.items[0].quantity, cart.items[0].unit_price_minor), (3, 79_999));
+        assert_eq!((cart.item_count, cart.total_minor, cart.currency.as_str()), (3, 239_997, "USD"));
+
+        // Another session has its own cart
+        assert!(carts.get_cart("bob").await.unwrap().items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn quantities_are_limited_to_available_stock() {
+        let (carts, _, pool) = services().await;
+        // Seeded "Apple Watch Ultra" has 8 units, 3 of them held by a reservation
+        sqlx::query("UPDATE products SET reserved = 3 WHERE id = 5").execute(&pool).await.unwrap();
+
+        carts.add_item("alice", add(5, 4)).await.unwrap();
+        assert!(matches!(
+            carts.add_item("alice", add(5, 2)).await,
+            Err(ApplicationError::DomainError(DomainError::InsufficientStock))
+        ));
+        assert!(matches!(
+            carts.update_item("alice", 5, UpdateCartItemRequest { quantity: 6 }).await,
+            Err(ApplicationError::DomainError(DomainError::InsufficientStock))
+        ));
+        assert!(matches!(
+            carts.add_item("alice", add(5, 0)).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidCart(_)))
+        ));
+
+        let cart = carts.update_item("alice", 5, UpdateCartItemRequest { quantity: 5 }).await.unwrap();
+        assert_eq!(cart.items[0].quantity, 5);
+        assert_eq!(cart.items[0].available, 5);
+    }
+
+    #[tokio::test]
+    async fn lines_can_be_removed_and_disappear_with_their_product() {
+        let (carts, products, pool) = services().await;
+        carts.add_item("alice", add(1, 1)).await.unwrap();
+        carts.add_item("alice", add(2, 1)).await.unwrap();
+        carts.add_item("alice", add(3, 1)).await.unwrap();
+
+        let cart = carts.update_item("alice", 1, UpdateCartItemRequest { quantity: 0 }).await.unwrap();
+        assert_eq!(cart.items.iter().map(|item| item.product_id).collect::<Vec<_>>(), vec![2, 3]);
+        assert!(matches!(carts.remove_item("alice", 1).await, Err(ApplicationError::CartItemNotFound)));
+
+        products.delete_product(2, None, &Actor::system("test")).await.unwrap();
+        let cart = carts.get_cart("alice").await.unwrap();
+        assert_eq!(cart.items.iter().map(|item| item.product_id).collect::<Vec<_>>(), vec![3]);
+
+        assert!(carts.clear_cart("alice").await.unwrap().items.is_empty());
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM cart_items").fetch_one(&pool).await.unwrap();
+        assert_eq!(stored, 0);
+    }
+}
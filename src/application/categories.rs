@@ -0,0 +1,389 @@
+use std::sync::Arc;
+
+use crate::application::dtos::{
+    AssignCategoriesRequest, CategoryResponse, CreateCategoryRequest, UpdateCategoryRequest,
+};
+use crate::application::policy::{Actor, Permission};
+use crate::application::services::ApplicationError;
+use crate::application::validation::FieldErrors;
+use crate::domain::{
+    Category, CategoryId, CategoryRepository, CategoryTree, ProductId, ProductRepository, RepositoryError,
+    Slug,
+};
+
+/// Application service for the category tree and the categories products belong to.
+///
+/// Reads are open to everyone; changing the tree needs `ManageCategories` and changing
+/// a product's categories needs `EditProduct`.
+pub struct CategoryService {
+    categories: Arc<dyn CategoryRepository>,
+    products: Arc<dyn ProductRepository>,
+}
+
+impl CategoryService {
+    pub fn new(categories: Arc<dyn CategoryRepository>, products: Arc<dyn ProductRepository>) -> Self {
+        Self { categories, products }
+    }
+
+    /// Every category, parents before their children and siblings by name
+    pub async fn list_categories(&self) -> Result<Vec<CategoryResponse>, ApplicationError> {
+        let tree = self.tree().await?;
+        Ok(tree.walk().into_iter().map(|(category, depth)| CategoryResponse::new(category, depth)).collect())
+    }
+
+    pub async fn get_category(&self, id: i64) -> Result<CategoryResponse, ApplicationError> {
+        let category = self.find(id).await?;
+        let tree = self.tree().await?;
+        Ok(describe(&tree, &category))
+    }
+
+    pub async fn create_category(
+        &self,
+        request: CreateCategoryRequest,
+        actor: &Actor,
+    ) -> Result<CategoryResponse, ApplicationError> {
+        actor.authorize(Permission::ManageCategories)?;
+        let slug = match request.slug.as_deref() {
+            Some(slug) => Slug::new(slug),
+            None => Slug::from_name(&request.name),
+        }
+        .map_err(ApplicationError::DomainError)?;
+        self.ensure_slug_free(&slug, None).await?;
+
+        let tree = self.tree().await?;
+        let parent_id = request.parent_id.map(CategoryId::new).transpose()
+            .map_err(ApplicationError::DomainError)?;
+        if let Some(parent_id) = &parent_id {
+            tree.check_parent(None, parent_id).map_err(ApplicationError::DomainError)?;
+        }
+
+        let id = self.categories.next_id().await
+            .map_err(ApplicationError::RepositoryError)?;
+        let category = Category::new(id, &request.name, slug.clone(), parent_id)
+            .map_err(ApplicationError::DomainError)?;
+
+        let saved = match self.categories.save(category).await {
+            Ok(saved) => saved,
+            // Lost a race with another category taking the same slug
+            Err(RepositoryError::ConstraintViolation(_)) => return Err(slug_taken(&slug)),
+            Err(err) => return Err(ApplicationError::RepositoryError(err)),
+        };
+        tracing::info!(category_id = saved.id().value(), slug = saved.slug().value(), "category created");
+        Ok(describe(&self.tree().await?, &saved))
+    }
+
+    pub async fn update_category(
+        &self,
+        id: i64,
+        request: UpdateCategoryRequest,
+        actor: &Actor,
+    ) -> Result<CategoryResponse, ApplicationError> {
+        actor.authorize(Permission::ManageCategories)?;
+        let mut category = self.find(id).await?;
+
+        let slug = request.slug.as_deref().map(Slug::new).transpose()
+            .map_err(ApplicationError::DomainError)?;
+        if let Some(slug) = &slug {
+            self.ensure_slug_free(slug, Some(category.id())).await?;
+        }
+        let parent_id = match request.parent_id {
+            Some(Some(parent)) => {
+                let parent = CategoryId::new(parent).map_err(ApplicationError::DomainError)?;
+                self.tree().await?
+                    .check_parent(Some(category.id()), &parent)
+                    .map_err(ApplicationError::DomainError)?;
+                Some(Some(parent))
+            }
+            Some(None) => Some(None),
+            None => None,
+        };
+
+        category.update(request.name.as_deref(), slug.clone(), parent_id)
+            .map_err(ApplicationError::DomainError)?;
+        let updated = self.categories.update(category).await.map_err(|err| match (err, &slug) {
+            // Lost a race with another category taking the same slug
+            (RepositoryError::ConstraintViolation(_), Some(slug)) => slug_taken(slug),
+            (RepositoryError::NotFound, _) => ApplicationError::CategoryNotFound,
+            (err, _) => ApplicationError::RepositoryError(err),
+        })?;
+        Ok(describe(&self.tree().await?, &updated))
+    }
+
+    /// Delete a category. Categories that still have products or subcategories are kept,
+    /// and the error says how many of each are in the way.
+    pub async fn delete_category(&self, id: i64, actor: &Actor) -> Result<(), ApplicationError> {
+        actor.authorize(Permission::ManageCategories)?;
+        let category = self.find(id).await?;
+
+        match self.categories.delete(category.id()).await {
+            Ok(true) => {
+                tracing::info!(category_id = id, "category deleted");
+                Ok(())
+            }
+            Ok(false) => Err(ApplicationError::CategoryNotFound),
+            Err(RepositoryError::DomainError(err)) => Err(ApplicationError::DomainError(err)),
+            Err(err) => Err(ApplicationError::RepositoryError(err)),
+        }
+    }
+
+    /// The categories a product belongs to
+    pub async fn product_categories(&self, product_id: i64) -> Result<Vec<CategoryResponse>, ApplicationError> {
+        let product_id = self.existing_product(product_id).await?;
+        let assigned = self.categories.find_for_product(&product_id).await
+            .map_err(ApplicationError::RepositoryError)?;
+        let tree = self.tree().await?;
+        Ok(assigned.iter().map(|category| describe(&tree, category)).collect())
+    }
+
+    /// Replace the categories a product belongs to
+    pub async fn assign_categories(
+        &self,
+        product_id: i64,
+        request: AssignCategoriesRequest,
+        actor: &Actor,
+    ) -> Result<Vec<CategoryResponse>, ApplicationError> {
+        actor.authorize(Permission::EditProduct)?;
+        let product = self.existing_product(product_id).await?;
+
+        let tree = self.tree().await?;
+        let mut ids = Vec::with_capacity(request.category_ids.len());
+        for id in request.category_ids {
+            let id = CategoryId::new(id).map_err(ApplicationError::DomainError)?;
+            if tree.get(&id).is_none() {
+                return Err(FieldErrors::only(
+                    "category_ids",
+                    "unknown",
+                    format!("Category {} does not exist", id.value()),
+                ));
+            }
+            ids.push(id);
+        }
+
+        self.categories.assign(&product, &ids).await
+            .map_err(ApplicationError::RepositoryError)?;
+        self.product_categories(product_id).await
+    }
+
+    /// One trail from the top of the tree per category the product belongs to
+    pub async fn breadcrumbs(&self, product_id: i64) -> Result<Vec<Vec<CategoryResponse>>, ApplicationError> {
+        let product_id = self.existing_product(product_id).await?;
+        let assigned = self.categories.find_for_product(&product_id).await
+            .map_err(ApplicationError::RepositoryError)?;
+        let tree = self.tree().await?;
+        Ok(assigned
+            .iter()
+            .map(|category| {
+                tree.path(category.id())
+                    .into_iter()
+                    .enumerate()
+                    .map(|(depth, category)| CategoryResponse::new(category, depth))
+                    .collect()
+            })
+            .collect())
+    }
+
+    async fn find(&self, id: i64) -> Result<Category, ApplicationError> {
+        let category_id = CategoryId::new(id).map_err(|_| ApplicationError::CategoryNotFound)?;
+
+        self.categories.find_by_id(&category_id).await
+            .map_err(ApplicationError::RepositoryError)?
+            .ok_or(ApplicationError::CategoryNotFound)
+    }
+
+    async fn all(&self) -> Result<Vec<Category>, ApplicationError> {
+        self.categories.find_all().await.map_err(ApplicationError::RepositoryError)
+    }
+
+    async fn tree(&self) -> Result<CategoryTree, ApplicationError> {
+        Ok(CategoryTree::new(self.all().await?))
+    }
+
+    async fn existing_product(&self, id: i64) -> Result<ProductId, ApplicationError> {
+        let product_id = ProductId::new(id).map_err(|_| ApplicationError::ProductNotFound)?;
+        let exists = self.products.exists(&product_id).await
+            .map_err(ApplicationError::RepositoryError)?;
+        if !exists {
+            return Err(ApplicationError::ProductNotFound);
+        }
+        Ok(product_id)
+    }
+
+    /// Fails unless `slug` is unused, or used by `owner` itself
+    async fn ensure_slug_free(&self, slug: &Slug, owner: Option<&CategoryId>) -> Result<(), ApplicationError> {
+        let existing = self.categories.find_by_slug(slug).await
+            .map_err(ApplicationError::RepositoryError)?;
+        match existing {
+            Some(category) if Some(category.id()) != owner => Err(slug_taken(slug)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn describe(tree: &CategoryTree, category: &Category) -> CategoryResponse {
+    CategoryResponse::new(category, tree.path(category.id()).len().saturating_sub(1))
+}
+
+fn slug_taken(slug: &Slug) -> ApplicationError {
+    FieldErrors::only("slug", "taken", format!("A category with slug '{}' already exists", slug.value()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    use crate::application::{ProductService, SearchProductsQuery, UserResponse};
+    use crate::domain::DomainError;
+    use crate::infrastructure::{migrated_pool, SqliteCategoryRepository, SqliteProductRepository};
+
+    async fn services() -> (CategoryService, ProductService) {
+        let pool = migrated_pool().await;
+        let products = Arc::new(SqliteProductRepository::new(pool.clone()));
+        products.initialize().await.expect("seed data");
+
+        let categories = CategoryService::new(Arc::new(SqliteCategoryRepository::new(pool)), products.clone());
+        (categories, ProductService::new(products))
+    }
+
+    fn create(name: &str, parent_id: Option<i64>) -> CreateCategoryRequest {
+        CreateCategoryRequest { name: name.to_string(), slug: None, parent_id }
+    }
+
+    async fn names_in(products: &ProductService, category: &str) -> Vec<String> {
+        let query = SearchProductsQuery {
+            category: Some(category.to_string()),
+            sort: Some("name".to_string()),
+            order: Some("asc".to_string()),
+            ..SearchProductsQuery::default()
+        };
+        products.search_products(query).await.unwrap().items.into_iter().map(|product| product.name).collect()
+    }
+
+    #[tokio::test]
+    async fn categories_form_a_tree_with_unique_slugs() {
+        let (categories, _) = services().await;
+        let admin = Actor::system("test");
+
+        let electronics = categories.create_category(create("Electronics", None), &admin).await.unwrap();
+        let phones = categories.create_category(create("Phones & Tablets", Some(electronics.id)), &admin).await.unwrap();
+        let audio = categories.create_category(create("Audio", Some(electronics.id)), &admin).await.unwrap();
+        assert_eq!(phones.slug, "phones-tablets");
+        assert_eq!((phones.parent_id, phones.depth), (Some(electronics.id), 1));
+
+        let walked: Vec<(String, usize)> = categories
+            .list_categories()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|category| (category.name, category.depth))
+            .collect();
+        assert_eq!(
+            walked,
+            vec![("Electronics".to_string(), 0), ("Audio".to_string(), 1), ("Phones & Tablets".to_string(), 1)]
+        );
+
+        match categories.create_category(create("Phones: Tablets", None), &admin).await {
+            Err(ApplicationError::InvalidFields(errors)) => {
+                assert_eq!((errors[0].field.as_str(), errors[0].code), ("slug", "taken"))
+            }
+            other => panic!("expected a taken slug, got {:?}", other.map(|category| category.slug)),
+        }
+        assert!(matches!(
+            categories.create_category(create("Orphan", Some(99)), &admin).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidCategory(_)))
+        ));
+
+        // Moving a category under its own subcategory would make a loop
+        let into_child = UpdateCategoryRequest { name: None, slug: None, parent_id: Some(Some(phones.id)) };
+        assert!(matches!(
+            categories.update_category(electronics.id, into_child, &admin).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidCategory(_)))
+        ));
+        let to_top = UpdateCategoryRequest { name: Some("Sound".to_string()), slug: None, parent_id: Some(None) };
+        let moved = categories.update_category(audio.id, to_top, &admin).await.unwrap();
+        assert_eq!((moved.name.as_str(), moved.slug.as_str(), moved.parent_id, moved.depth), ("Sound", "audio", None, 0));
+        assert!(matches!(categories.get_category(99).await, Err(ApplicationError::CategoryNotFound)));
+    }
+
+    #[tokio::test]
+    async fn products_are_filtered_by_category_and_its_subcategories() {
+        let (categories, products) = services().await;
+        let admin = Actor::system("test");
+        let electronics = categories.create_category(create("Electronics", None), &admin).await.unwrap();
+        let phones = categories.create_category(create("Phones", Some(electronics.id)), &admin).await.unwrap();
+        let audio = categories.create_category(create("Audio", Some(electronics.id)), &admin).await.unwrap();
+
+        let catalog = products.search_products(SearchProductsQuery::default()).await.unwrap().items;
+        let id_of = |name: &str| catalog.iter().find(|product| product.name == name).unwrap().id;
+        let assign = |ids: Vec<i64>| AssignCategoriesRequest { category_ids: ids };
+        categories.assign_categories(id_of("iPhone 15 Pro"), assign(vec![phones.id]), &admin).await.unwrap();
+        categories.assign_categories(id_of("AirPods Pro"), assign(vec![audio.id, phones.id]), &admin).await.unwrap();
+        // Assigning again replaces the earlier categories
+        let assigned = categories.assign_categories(id_of("AirPods Pro"), assign(vec![audio.id]), &admin).await.unwrap();
+        assert_eq!(assigned.iter().map(|category| category.slug.as_str()).collect::<Vec<_>>(), vec!["audio"]);
+
+        assert_eq!(names_in(&products, "phones").await, vec!["iPhone 15 Pro"]);
+        assert_eq!(names_in(&products, "electronics").await, vec!["AirPods Pro", "iPhone 15 Pro"]);
+        assert!(names_in(&products, "no-such-category").await.is_empty());
+
+        let trails = categories.breadcrumbs(id_of("iPhone 15 Pro")).await.unwrap();
+        let trail: Vec<&str> = trails[0].iter().map(|category| category.name.as_str()).collect();
+        assert_eq!(trail, vec!["Electronics", "Phones"]);
+
+        match categories.assign_categories(id_of("iPad Air"), assign(vec![99]), &admin).await {
+            Err(ApplicationError::InvalidFields(errors)) => assert_eq!(errors[0].field, "category_ids"),
+            other => panic!("expected an unknown category, got {:?}", other.map(|assigned| assigned.len())),
+        }
+        assert!(matches!(
+            categories.assign_categories(999, assign(vec![phones.id]), &admin).await,
+            Err(ApplicationError::ProductNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn categories_in_use_cannot_be_deleted() {
+        let (categories, products) = services().await;
+        let admin = Actor::system("test");
+        let electronics = categories.create_category(create("Electronics", None), &admin).await.unwrap();
+        let phones = categories.create_category(create("Phones", Some(electronics.id)), &admin).await.unwrap();
+        let product_id = products.search_products(SearchProductsQuery::default()).await.unwrap().items[0].id;
+        let request = AssignCategoriesRequest { category_ids: vec![phones.id] };
+        categories.assign_categories(product_id, request, &admin).await.unwrap();
+
+        assert!(matches!(
+            categories.delete_category(electronics.id, &admin).await,
+            Err(ApplicationError::DomainError(DomainError::CategoryNotEmpty { products: 0, subcategories: 1 }))
+        ));
+        assert!(matches!(
+            categories.delete_category(phones.id, &admin).await,
+            Err(ApplicationError::DomainError(DomainError::CategoryNotEmpty { products: 1, subcategories: 0 }))
+        ));
+
+        let request = AssignCategoriesRequest { category_ids: Vec::new() };
+        categories.assign_categories(product_id, request, &admin).await.unwrap();
+        categories.delete_category(phones.id, &admin).await.unwrap();
+        categories.delete_category(electronics.id, &admin).await.unwrap();
+        assert!(categories.list_categories().await.unwrap().is_empty());
+        assert!(matches!(categories.delete_category(phones.id, &admin).await, Err(ApplicationError::CategoryNotFound)));
+    }
+
+    #[tokio::test]
+    async fn only_catalog_editors_change_the_tree() {
+        let (categories, _) = services().await;
+        let user = |role: &str| {
+            Actor::user(&UserResponse {
+                id: 1,
+                email: "someone@example.com".to_string(),
+                name: "Someone".to_string(),
+                role: role.to_string(),
+                created_at: Utc::now(),
+            })
+        };
+
+        assert!(matches!(
+            categories.create_category(create("Electronics", None), &user("viewer")).await,
+            Err(ApplicationError::AuthorizationError(_))
+        ));
+        categories.create_category(create("Electronics", None), &user("catalog_editor")).await.unwrap();
+    }
+}
@@ -1,6 +1,12 @@
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer, Serialize};
 use chrono::{DateTime, Utc};
-use crate::domain::Product;
+use crate::application::validation::FieldError;
+use crate::domain::{
+    ApiKey, Category, DomainError, FacetCount, Facets, Order, Product, SearchHighlight, StockDiscrepancy, StockMovement, StockReservation, User,
+    WebhookSubscription, WebhookDelivery, HIGHLIGHT_END, HIGHLIGHT_START,
+};
 
 /// Request DTO for creating a new product
 #[derive(Debug, Deserialize)]
@@ -8,16 +14,322 @@ pub struct CreateProductRequest {
     pub name: String,
     pub description: Option<String>,
     pub price: f64,
+    /// ISO-4217 code, defaults to USD when omitted
+    pub currency: Option<String>,
     pub stock: i32,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Key/value attributes such as `{"brand": "Apple", "color": "Silver"}`
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
 }
 
 /// Request DTO for updating a product
 #[derive(Debug, Deserialize)]
 pub struct UpdateProductRequest {
     pub name: Option<String>,
-    pub description: Option<String>,
+    /// Left unchanged when omitted; `null` clears it
+    #[serde(default, deserialize_with = "present")]
+    pub description: Option<Option<String>>,
     pub price: Option<f64>,
+    pub currency: Option<String>,
     pub stock: Option<i32>,
+    /// Replaces every tag when given
+    pub tags: Option<Vec<String>>,
+    /// Replaces every attribute when given
+    pub attributes: Option<BTreeMap<String, String>>,
+    /// Version the client last saw; the update is rejected if the product changed since
+    pub version: Option<i64>,
+}
+
+/// Optional body for product deletion, as an alternative to `If-Match`
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteProductRequest {
+    pub version: Option<i64>,
+}
+
+/// Request DTO for adjusting stock on hand
+#[derive(Debug, Deserialize)]
+pub struct AdjustStockRequest {
+    /// Units to add (positive) or remove (negative)
+    pub delta: i32,
+    /// One of `sale`, `restock`, `correction`, `return`
+    pub reason: String,
+    pub note: Option<String>,
+}
+
+/// Query DTO for stock history
+#[derive(Debug, Default, Deserialize)]
+pub struct StockHistoryQuery {
+    pub limit: Option<usize>,
+}
+
+/// Response DTO for stock ledger entries
+#[derive(Debug, Serialize)]
+pub struct StockMovementResponse {
+    pub delta: i32,
+    pub reason: String,
+    pub actor: String,
+    pub note: Option<String>,
+    pub stock_after: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<StockMovement> for StockMovementResponse {
+    fn from(movement: StockMovement) -> Self {
+        Self {
+            delta: movement.delta,
+            reason: movement.reason.as_str().to_string(),
+            actor: movement.actor,
+            note: movement.note,
+            stock_after: movement.stock_after,
+            created_at: movement.created_at,
+        }
+    }
+}
+
+/// Response DTO for products whose stock disagrees with the ledger
+#[derive(Debug, Serialize)]
+pub struct StockDiscrepancyResponse {
+    pub product_id: i64,
+    pub stock: i32,
+    pub ledger_total: i64,
+}
+
+impl From<StockDiscrepancy> for StockDiscrepancyResponse {
+    fn from(discrepancy: StockDiscrepancy) -> Self {
+        Self {
+            product_id: discrepancy.product_id.value(),
+            stock: discrepancy.stock,
+            ledger_total: discrepancy.ledger_total,
+        }
+    }
+}
+
+/// Request DTO for reserving stock
+#[derive(Debug, Deserialize)]
+pub struct CreateReservationRequest {
+    pub quantity: i32,
+    /// How long the units are held; defaults to 15 minutes
+    pub ttl_seconds: Option<i64>,
+}
+
+/// Response DTO for stock reservations
+#[derive(Debug, Serialize)]
+pub struct ReservationResponse {
+    pub id: i64,
+    pub product_id: i64,
+    pub quantity: i32,
+    pub status: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<StockReservation> for ReservationResponse {
+    fn from(reservation: StockReservation) -> Self {
+        Self {
+            id: reservation.id().value(),
+            product_id: reservation.product_id().value(),
+            quantity: reservation.quantity(),
+            status: reservation.status().as_str().to_string(),
+            expires_at: reservation.expires_at(),
+            created_at: reservation.created_at(),
+            updated_at: reservation.updated_at(),
+        }
+    }
+}
+
+/// Request DTO for adding a product to the cart
+#[derive(Debug, Deserialize)]
+pub struct AddCartItemRequest {
+    pub product_id: i64,
+    /// Defaults to 1
+    pub quantity: Option<i32>,
+}
+
+/// Request DTO for changing a cart line; zero removes it
+#[derive(Debug, Deserialize)]
+pub struct UpdateCartItemRequest {
+    pub quantity: i32,
+}
+
+/// Response DTO for a cart line
+#[derive(Debug, Serialize)]
+pub struct CartItemResponse {
+    pub product_id: i64,
+    pub name: String,
+    pub quantity: i32,
+    /// Price captured when the product was added
+    pub unit_price: f64,
+    pub unit_price_minor: i64,
+    pub subtotal: f64,
+    pub subtotal_minor: i64,
+    pub currency: String,
+    /// Units of the product that can currently be sold
+    pub available: i32,
+}
+
+/// Response DTO for the visitor's cart
+#[derive(Debug, Serialize)]
+pub struct CartResponse {
+    pub items: Vec<CartItemResponse>,
+    pub item_count: i32,
+    pub total: f64,
+    pub total_minor: i64,
+    pub currency: String,
+}
+
+/// Request DTO for creating a customer account
+#[derive(Debug, Deserialize)]
+pub struct RegisterRequest {
+    pub email: String,
+    pub name: String,
+    pub password: String,
+}
+
+/// Request DTO for signing in
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+/// Request DTO for changing an account's role
+#[derive(Debug, Deserialize)]
+pub struct SetRoleRequest {
+    pub role: String,
+}
+
+/// Response DTO for a customer account
+#[derive(Debug, Clone, Serialize)]
+pub struct UserResponse {
+    pub id: i64,
+    pub email: String,
+    pub name: String,
+    /// `admin`, `catalog_editor` or `viewer`
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id().value(),
+            email: user.email().value().to_string(),
+            name: user.name().to_string(),
+            role: user.role().as_str().to_string(),
+            created_at: user.created_at(),
+        }
+    }
+}
+
+/// Request DTO for issuing an API key
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    /// `products:read`, `products:write` and/or `stock:manage`
+    pub scopes: Vec<String>,
+    /// Requests allowed per minute; unlimited when omitted
+    pub rate_limit_per_minute: Option<u32>,
+}
+
+/// Response DTO for an API key
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKeyResponse {
+    pub id: i64,
+    pub name: String,
+    /// First characters of the key, to tell keys apart
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub rate_limit_per_minute: Option<u32>,
+    pub active: bool,
+    /// The full key; only returned when the key is issued
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id().value(),
+            name: key.name().to_string(),
+            prefix: key.prefix().to_string(),
+            scopes: key.scopes().iter().map(|scope| scope.as_str().to_string()).collect(),
+            rate_limit_per_minute: key.rate_limit_per_minute(),
+            active: key.is_active(),
+            key: None,
+            created_at: key.created_at(),
+            last_used_at: key.last_used_at(),
+            revoked_at: key.revoked_at(),
+        }
+    }
+}
+
+/// Response DTO for an order line
+#[derive(Debug, Serialize)]
+pub struct OrderItemResponse {
+    pub product_id: i64,
+    /// Product name when the order was placed
+    pub name: String,
+    pub quantity: i32,
+    pub unit_price: f64,
+    pub unit_price_minor: i64,
+    pub subtotal: f64,
+    pub subtotal_minor: i64,
+}
+
+/// Response DTO for orders
+#[derive(Debug, Serialize)]
+pub struct OrderResponse {
+    pub id: i64,
+    pub status: String,
+    pub items: Vec<OrderItemResponse>,
+    pub item_count: i32,
+    pub total: f64,
+    pub total_minor: i64,
+    pub currency: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<Order> for OrderResponse {
+    type Error = DomainError;
+
+    fn try_from(order: Order) -> Result<Self, Self::Error> {
+        let items = order
+            .lines()
+            .iter()
+            .map(|line| {
+                let subtotal = line.subtotal()?;
+                Ok(OrderItemResponse {
+                    product_id: line.product_id().value(),
+                    name: line.product_name().to_string(),
+                    quantity: line.quantity(),
+                    unit_price: line.unit_price().to_decimal(),
+                    unit_price_minor: line.unit_price().amount_minor(),
+                    subtotal: subtotal.to_decimal(),
+                    subtotal_minor: subtotal.amount_minor(),
+                })
+            })
+            .collect::<Result<Vec<_>, DomainError>>()?;
+        let total = order.total()?;
+
+        Ok(Self {
+            id: order.id().value(),
+            status: order.status().as_str().to_string(),
+            items,
+            item_count: order.item_count(),
+            total: total.to_decimal(),
+            total_minor: total.amount_minor(),
+            currency: total.currency().code().to_string(),
+            created_at: order.created_at(),
+            updated_at: order.updated_at(),
+        })
+    }
 }
 
 /// Response DTO for product data
@@ -26,10 +338,58 @@ pub struct ProductResponse {
     pub id: i64,
     pub name: String,
     pub description: Option<String>,
+    /// Decimal price, kept for display and backwards compatibility
     pub price: f64,
+    /// Exact price in the currency's minor unit (e.g. cents)
+    pub price_minor: i64,
+    pub currency: String,
     pub stock: i32,
+    /// Units held by active reservations
+    pub reserved: i32,
+    /// Units that can still be sold: `stock - reserved`
+    pub available: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
+    pub tags: Vec<String>,
+    pub attributes: BTreeMap<String, String>,
+    /// Where the product matched a full-text search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<HighlightResponse>,
+}
+
+/// Matched search terms in a product's name and description
+#[derive(Debug, Clone, Serialize)]
+pub struct HighlightResponse {
+    pub name: Vec<TextSegment>,
+    /// A few words of the description around the best match
+    pub snippet: Vec<TextSegment>,
+}
+
+/// A run of text that either matched the search or did not
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TextSegment {
+    pub text: String,
+    pub matched: bool,
+}
+
+impl From<SearchHighlight> for HighlightResponse {
+    fn from(highlight: SearchHighlight) -> Self {
+        Self { name: segments(&highlight.name), snippet: segments(&highlight.snippet) }
+    }
+}
+
+/// Split text marked with `HIGHLIGHT_START`/`HIGHLIGHT_END` into matched and unmatched runs
+fn segments(marked: &str) -> Vec<TextSegment> {
+    let mut segments = Vec::new();
+    let mut matched = false;
+    for part in marked.split([HIGHLIGHT_START, HIGHLIGHT_END]) {
+        if !part.is_empty() {
+            segments.push(TextSegment { text: part.to_string(), matched });
+        }
+        matched = !matched;
+    }
+    segments
 }
 
 impl From<Product> for ProductResponse {
@@ -38,22 +398,249 @@ impl From<Product> for ProductResponse {
             id: product.id().value(),
             name: product.name().value().to_string(),
             description: product.description().clone(),
-            price: product.price().value(),
+            price: product.price().to_decimal(),
+            price_minor: product.price().amount_minor(),
+            currency: product.price().currency().code().to_string(),
             stock: product.stock().value(),
+            reserved: product.reserved().value(),
+            available: product.available_stock(),
             created_at: *product.created_at(),
             updated_at: *product.updated_at(),
+            version: product.version(),
+            tags: product.tags().iter().map(|tag| tag.value().to_string()).collect(),
+            attributes: product
+                .attributes()
+                .iter()
+                .map(|attribute| (attribute.key().to_string(), attribute.value().to_string()))
+                .collect(),
+            highlight: None,
         }
     }
 }
 
-/// Search query DTO
+/// Request DTO for creating a category
+#[derive(Debug, Deserialize)]
+pub struct CreateCategoryRequest {
+    pub name: String,
+    /// Derived from the name when omitted
+    pub slug: Option<String>,
+    /// Parent category; a top-level category when omitted
+    pub parent_id: Option<i64>,
+}
+
+/// Request DTO for renaming or moving a category
+#[derive(Debug, Deserialize)]
+pub struct UpdateCategoryRequest {
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    /// New parent; `null` moves the category to the top level
+    #[serde(default, deserialize_with = "present")]
+    pub parent_id: Option<Option<i64>>,
+}
+
+/// Request DTO for the categories a product belongs to
+#[derive(Debug, Deserialize)]
+pub struct AssignCategoriesRequest {
+    pub category_ids: Vec<i64>,
+}
+
+/// Response DTO for a category
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryResponse {
+    pub id: i64,
+    pub name: String,
+    pub slug: String,
+    pub parent_id: Option<i64>,
+    /// Levels below the top of the tree
+    pub depth: usize,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CategoryResponse {
+    pub fn new(category: &Category, depth: usize) -> Self {
+        Self {
+            id: category.id().value(),
+            name: category.name().to_string(),
+            slug: category.slug().value().to_string(),
+            parent_id: category.parent_id().map(|parent| parent.value()),
+            depth,
+            created_at: category.created_at(),
+            updated_at: category.updated_at(),
+        }
+    }
+}
+
+/// Tells a field sent as `null` (`Some(None)`) from one left out (`None`)
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Request DTO for creating a webhook subscription
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    /// Event types to deliver, e.g. `product.created`
+    pub events: Vec<String>,
+    /// Shared signing secret; one is generated when omitted
+    pub secret: Option<String>,
+}
+
+/// Request DTO for updating a webhook subscription
 #[derive(Debug, Deserialize)]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub secret: Option<String>,
+    pub active: Option<bool>,
+}
+
+/// Response DTO for webhook subscriptions
+#[derive(Debug, Serialize)]
+pub struct WebhookResponse {
+    pub id: i64,
+    pub url: String,
+    pub events: Vec<String>,
+    pub active: bool,
+    /// Only returned when the subscription is created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<WebhookSubscription> for WebhookResponse {
+    fn from(webhook: WebhookSubscription) -> Self {
+        Self {
+            id: webhook.id().value(),
+            url: webhook.url().to_string(),
+            events: webhook.events().iter().map(|event| event.as_str().to_string()).collect(),
+            active: webhook.active(),
+            secret: None,
+            created_at: webhook.created_at(),
+            updated_at: webhook.updated_at(),
+        }
+    }
+}
+
+/// Response DTO for one entry of a webhook's delivery log
+#[derive(Debug, Serialize)]
+pub struct WebhookDeliveryResponse {
+    pub id: i64,
+    pub event_type: String,
+    pub status: String,
+    pub attempts: i64,
+    pub response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl From<WebhookDelivery> for WebhookDeliveryResponse {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id,
+            event_type: delivery.event_type,
+            status: delivery.status,
+            attempts: delivery.attempts,
+            response_status: delivery.response_status,
+            last_error: delivery.last_error,
+            created_at: delivery.created_at,
+            delivered_at: delivery.delivered_at,
+        }
+    }
+}
+
+/// Search query DTO
+///
+/// Paging is either `page`/`per_page` or `offset`/`limit`; `offset` wins when both are given.
+/// `min_price`/`max_price` are decimal amounts in `currency` (USD by default).
+#[derive(Debug, Default, Deserialize)]
 pub struct SearchProductsQuery {
+    #[serde(alias = "search")]
     pub query: Option<String>,
-    #[allow(dead_code)]
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
     pub limit: Option<usize>,
-    #[allow(dead_code)]
     pub offset: Option<usize>,
+    /// One of `relevance`, `created_at`, `name`, `price`, `stock`. Searches default to
+    /// `relevance`, other listings to `created_at`.
+    pub sort: Option<String>,
+    /// `asc` or `desc`
+    pub order: Option<String>,
+    /// Category slug; products in its subcategories match too
+    pub category: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub currency: Option<String>,
+    pub in_stock: Option<bool>,
+    pub created_after: Option<DateTime<Utc>>,
+    /// Products carrying any of these tags, from repeated `tag` parameters
+    #[serde(skip)]
+    pub tags: Vec<String>,
+    /// Attribute filters from repeated `attr.<key>` parameters; see `with_facet_filters`
+    #[serde(skip)]
+    pub attributes: BTreeMap<String, Vec<String>>,
+}
+
+/// Prefix of the query parameters that filter on an attribute, as in `attr.color=Silver`
+pub const ATTRIBUTE_PARAM_PREFIX: &str = "attr.";
+
+impl SearchProductsQuery {
+    /// Take the facet filters from the query string's raw pairs: each `tag=` and
+    /// `attr.<key>=`. They may repeat, which the derived deserializer rejects, so they
+    /// are read separately.
+    pub fn with_facet_filters(mut self, pairs: Vec<(String, String)>) -> Self {
+        for (name, value) in pairs {
+            if value.trim().is_empty() {
+                continue;
+            }
+            if name == "tag" {
+                self.tags.push(value);
+            } else if let Some(key) = name.strip_prefix(ATTRIBUTE_PARAM_PREFIX) {
+                self.attributes.entry(key.to_string()).or_default().push(value);
+            }
+        }
+        self
+    }
+}
+
+/// Query parameters for search suggestions
+#[derive(Debug, Deserialize)]
+pub struct SuggestQuery {
+    /// At most 100 characters and 8 words
+    #[serde(alias = "search")]
+    pub q: Option<String>,
+    /// Suggestions of each kind, 8 by default
+    pub limit: Option<usize>,
+}
+
+/// Completions and corrections for what has been typed so far
+#[derive(Debug, Serialize)]
+pub struct SuggestionsResponse {
+    pub query: String,
+    /// Products with name words starting with the typed words
+    pub completions: Vec<ProductSuggestion>,
+    /// The query with misspelt words replaced, when any word matched nothing
+    pub did_you_mean: Option<String>,
+    /// Products found by `did_you_mean`
+    pub corrections: Vec<ProductSuggestion>,
+}
+
+impl SuggestionsResponse {
+    pub fn is_empty(&self) -> bool {
+        self.completions.is_empty() && self.did_you_mean.is_none()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductSuggestion {
+    pub id: i64,
+    pub name: String,
 }
 
 /// Generic API response wrapper
@@ -63,6 +650,9 @@ pub struct ApiResponse<T> {
     pub data: Option<T>,
     pub message: Option<String>,
     pub errors: Option<Vec<String>>,
+    /// Structured counterpart of `errors` when individual request fields were invalid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldError>>,
 }
 
 impl<T> ApiResponse<T> {
@@ -72,6 +662,7 @@ impl<T> ApiResponse<T> {
             data: Some(data),
             message: None,
             errors: None,
+            fields: None,
         }
     }
 
@@ -81,6 +672,7 @@ impl<T> ApiResponse<T> {
             data: None,
             message: Some(message),
             errors: None,
+            fields: None,
         }
     }
 
@@ -90,6 +682,16 @@ impl<T> ApiResponse<T> {
             data: None,
             message: Some("Validation failed".to_string()),
             errors: Some(errors),
+            fields: None,
+        }
+    }
+
+    /// Every invalid field, both as "field: message" lines and structured
+    pub fn invalid_fields(fields: Vec<FieldError>) -> Self {
+        Self {
+            errors: Some(fields.iter().map(|error| format!("{}: {}", error.field, error.message)).collect()),
+            fields: Some(fields),
+            ..Self::validation_error(Vec::new())
         }
     }
 }
@@ -101,28 +703,101 @@ pub struct PaginatedResponse<T> {
     pub total: usize,
     pub page: usize,
     pub per_page: usize,
+    pub total_pages: usize,
     pub has_next: bool,
     pub has_prev: bool,
+    /// Value counts across every page, for listings that offer facet filters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<FacetsResponse>,
 }
 
 impl<T> PaginatedResponse<T> {
-    #[allow(dead_code)]
+    /// `items` starts `offset` rows into the `total` matches
     pub fn new(
         items: Vec<T>,
         total: usize,
-        page: usize,
+        offset: usize,
         per_page: usize,
     ) -> Self {
-        let has_next = (page * per_page) < total;
-        let has_prev = page > 1;
+        // Offsets need not fall on a page boundary, so what follows is judged from the rows themselves
+        let has_next = offset + items.len() < total;
+        let has_prev = offset > 0;
+        let page = offset / per_page.max(1) + 1;
+        let total_pages = total.div_ceil(per_page.max(1));
 
         Self {
             items,
             total,
             page,
             per_page,
+            total_pages,
             has_next,
             has_prev,
+            facets: None,
+        }
+    }
+
+    pub fn with_facets(self, facets: FacetsResponse) -> Self {
+        Self { facets: Some(facets), ..self }
+    }
+}
+
+/// Tag and attribute value counts for a product listing, marking the values its filters chose
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FacetsResponse {
+    pub tags: Vec<FacetValueResponse>,
+    pub attributes: Vec<AttributeFacetResponse>,
+}
+
+/// The values of one attribute key
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributeFacetResponse {
+    pub key: String,
+    pub values: Vec<FacetValueResponse>,
+}
+
+/// How many matching products carry a value, and whether the listing filters on it
+#[derive(Debug, Clone, Serialize)]
+pub struct FacetValueResponse {
+    pub value: String,
+    pub count: usize,
+    pub selected: bool,
+}
+
+impl FacetsResponse {
+    /// `tags` and `attributes` are the filters the listing used. A chosen value that no
+    /// longer matches anything is kept with a count of zero, so it can still be unchosen.
+    pub fn new(facets: Facets, tags: &[String], attributes: &BTreeMap<String, Vec<String>>) -> Self {
+        let values = |counts: Vec<FacetCount>, chosen: &[String]| {
+            let mut values: Vec<FacetValueResponse> = counts
+                .into_iter()
+                .map(|count| FacetValueResponse {
+                    selected: chosen.contains(&count.value),
+                    value: count.value,
+                    count: count.count,
+                })
+                .collect();
+            for value in chosen {
+                if !values.iter().any(|facet| facet.value == *value) {
+                    values.push(FacetValueResponse { value: value.clone(), count: 0, selected: true });
+                }
+            }
+            values
+        };
+
+        let mut counted: BTreeMap<String, Vec<FacetCount>> = facets.attributes.into_iter().collect();
+        for key in attributes.keys() {
+            counted.entry(key.clone()).or_default();
+        }
+        Self {
+            tags: values(facets.tags, tags),
+            attributes: counted
+                .into_iter()
+                .map(|(key, counts)| {
+                    let chosen = attributes.get(&key).map(Vec::as_slice).unwrap_or_default();
+                    AttributeFacetResponse { values: values(counts, chosen), key }
+                })
+                .collect(),
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,133 @@
+use std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+
+use crate::domain::ProductEvent;
+use crate::application::services::ApplicationError;
+
+/// Subscriber for domain events published on the [`EventBus`]
+#[async_trait]
+pub trait EventHandler: Send + Sync {
+    /// Name used when logging handler failures
+    fn name(&self) -> &'static str;
+
+    async fn handle(&self, event: &ProductEvent) -> Result<(), ApplicationError>;
+}
+
+/// In-process event bus.
+///
+/// Events are delivered to every subscriber in subscription order by the outbox
+/// relay, after the change that raised them has been committed. A failing handler
+/// does not stop delivery to the remaining handlers; the failure is reported back
+/// so the relay can retry the event later for that handler alone.
+#[derive(Default)]
+pub struct EventBus {
+    handlers: RwLock<Vec<Arc<dyn EventHandler>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self, handler: Arc<dyn EventHandler>) {
+        self.handlers
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(handler);
+    }
+
+    /// Hand `event` to every subscriber except those named in `delivered`, which
+    /// already took it on an earlier attempt
+    pub async fn publish(&self, event: &ProductEvent, delivered: &[String]) -> Published {
+        // Snapshot the subscribers so no lock is held across an await
+        let handlers = self
+            .handlers
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+
+        let mut published = Published::default();
+        for handler in handlers {
+            if delivered.iter().any(|name| name == handler.name()) {
+                continue;
+            }
+            match handler.handle(event).await {
+                Ok(()) => published.handled.push(handler.name()),
+                Err(err) => {
+                    tracing::error!(
+                        handler = handler.name(),
+                        event = event.event_type(),
+                        product_id = event.product_id().value(),
+                        "event handler failed: {}",
+                        err
+                    );
+                    published.failures.push(format!("{}: {}", handler.name(), err));
+                }
+            }
+        }
+        published
+    }
+}
+
+/// Outcome of one [`EventBus::publish`]
+#[derive(Debug, Default)]
+pub struct Published {
+    /// Subscribers that took the event
+    pub handled: Vec<&'static str>,
+    /// One `"handler: error"` per subscriber that failed
+    pub failures: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::domain::ProductId;
+
+    struct Recorder {
+        name: &'static str,
+        fail: bool,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl EventHandler for Recorder {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn handle(&self, event: &ProductEvent) -> Result<(), ApplicationError> {
+            self.log.lock().unwrap().push(format!("{}:{}", self.name, event.event_type()));
+            if self.fail {
+                return Err(ApplicationError::internal("boom"));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn delivers_to_every_subscriber_even_after_a_failure() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let bus = EventBus::new();
+        bus.subscribe(Arc::new(Recorder { name: "first", fail: true, log: log.clone() }));
+        bus.subscribe(Arc::new(Recorder { name: "second", fail: false, log: log.clone() }));
+
+        let deleted = ProductEvent::ProductDeleted { product_id: ProductId::from(1) };
+        let updated = ProductEvent::ProductUpdated { product_id: ProductId::from(2), changes: vec!["name".to_string()] };
+
+        let published = bus.publish(&deleted, &[]).await;
+        assert_eq!((published.handled, published.failures), (vec!["second"], vec!["first: Internal error: boom".to_string()]));
+        // A retry skips the handlers that already took the event
+        let published = bus.publish(&updated, &["second".to_string()]).await;
+        assert!(published.handled.is_empty() && published.failures.len() == 1);
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "first:product.deleted",
+                "second:product.deleted",
+                "first:product.updated",
+            ]
+        );
+    }
+}
@@ -0,0 +1,539 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::task::JoinHandle;
+
+use crate::application::dtos::{
+    AdjustStockRequest, CreateReservationRequest, ProductResponse, ReservationResponse,
+    StockDiscrepancyResponse, StockMovementResponse,
+};
+use crate::application::policy::{Actor, Permission};
+use crate::application::services::ApplicationError;
+use crate::domain::{
+    InventoryRepository, Product, ProductEvent, ProductId, ProductRepository, RepositoryError,
+    ReservationId, StockMovementReason, StockReservation,
+};
+
+/// Reservation lifetime used when the client does not ask for one
+pub const DEFAULT_RESERVATION_TTL_SECS: i64 = 15 * 60;
+/// Longest a reservation may hold stock
+pub const MAX_RESERVATION_TTL_SECS: i64 = 24 * 60 * 60;
+/// Stock writes that lose a version race are re-read and retried this many times in total
+pub const MAX_STOCK_WRITE_ATTEMPTS: usize = 5;
+/// Expired reservations released per sweep
+const EXPIRY_BATCH_SIZE: usize = 100;
+/// Ledger entries returned when the client does not ask for a number
+pub const DEFAULT_STOCK_HISTORY_LIMIT: usize = 50;
+/// Most ledger entries returned at once
+pub const MAX_STOCK_HISTORY_LIMIT: usize = 500;
+
+/// How a reservation leaves the active state
+#[derive(Debug, Clone, Copy)]
+enum Settlement<'a> {
+    Commit { actor: &'a str },
+    Release,
+    Expire,
+}
+
+/// Application service for stock adjustments and reservations.
+///
+/// Every write is a read-modify-write of the product guarded by its version, so two
+/// concurrent writers can never both spend the same units; the loser re-reads and retries.
+pub struct InventoryService {
+    products: Arc<dyn ProductRepository>,
+    inventory: Arc<dyn InventoryRepository>,
+}
+
+impl InventoryService {
+    pub fn new(products: Arc<dyn ProductRepository>, inventory: Arc<dyn InventoryRepository>) -> Self {
+        Self { products, inventory }
+    }
+
+    /// Add or remove stock on hand on behalf of `actor`. Removing units held by
+    /// reservations is refused.
+    pub async fn adjust_stock(
+        &self,
+        id: i64,
+        request: AdjustStockRequest,
+        actor: &Actor,
+    ) -> Result<ProductResponse, ApplicationError> {
+        actor.authorize(Permission::AdjustStock)?;
+        let product_id = ProductId::new(id).map_err(ApplicationError::DomainError)?;
+        if request.reason.trim().is_empty() {
+            return Err(ApplicationError::validation("A reason is required for stock adjustments"));
+        }
+        let reason = StockMovementReason::try_from(request.reason.as_str())
+            .map_err(ApplicationError::DomainError)?;
+        let note = request.note
+            .map(|note| note.trim().to_string())
+            .filter(|note| !note.is_empty());
+        if request.delta < 0 {
+            self.expire_due(Some(&product_id)).await?;
+        }
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut product = self.find_product(&product_id).await?;
+            let old_stock = product.adjust_stock(request.delta, reason, actor.name(), note.clone())
+                .map_err(ApplicationError::DomainError)?;
+            let event = ProductEvent::StockChanged {
+                product_id: product_id.clone(),
+                old_stock,
+                new_stock: product.stock().clone(),
+            };
+
+            match self.products.update(product, &[event]).await {
+                Ok(product) => {
+                    tracing::info!(
+                        product_id = id,
+                        delta = request.delta,
+                        reason = reason.as_str(),
+                        actor = actor.name(),
+                        stock = product.stock().value(),
+                        "stock adjusted"
+                    );
+                    return Ok(ProductResponse::from(product));
+                }
+                Err(RepositoryError::ConcurrentModification) if attempt < MAX_STOCK_WRITE_ATTEMPTS => continue,
+                Err(err) => return Err(ApplicationError::RepositoryError(err)),
+            }
+        }
+    }
+
+    /// Hold available units until the reservation is committed, released or expires
+    pub async fn reserve_stock(
+        &self,
+        id: i64,
+        request: CreateReservationRequest,
+        actor: &Actor,
+    ) -> Result<ReservationResponse, ApplicationError> {
+        actor.authorize(Permission::ReserveStock)?;
+        let product_id = ProductId::new(id).map_err(ApplicationError::DomainError)?;
+        let ttl_seconds = request.ttl_seconds.unwrap_or(DEFAULT_RESERVATION_TTL_SECS);
+        if !(1..=MAX_RESERVATION_TTL_SECS).contains(&ttl_seconds) {
+            return Err(ApplicationError::validation(format!(
+                "ttl_seconds must be between 1 and {}",
+                MAX_RESERVATION_TTL_SECS
+            )));
+        }
+
+        // Units held by reservations that already lapsed should not block this one
+        self.expire_due(Some(&product_id)).await?;
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut product = self.find_product(&product_id).await?;
+            product.reserve_stock(request.quantity)
+                .map_err(ApplicationError::DomainError)?;
+
+            let reservation_id = self.inventory.next_reservation_id().await
+                .map_err(ApplicationError::RepositoryError)?;
+            let reservation = StockReservation::new(
+                reservation_id,
+                product_id.clone(),
+                request.quantity,
+                chrono::Duration::seconds(ttl_seconds),
+            )
+            .map_err(ApplicationError::DomainError)?;
+
+            match self.inventory.create_reservation(product, reservation).await {
+                Ok((_, reservation)) => {
+                    tracing::info!(
+                        reservation_id = reservation.id().value(),
+                        product_id = id,
+                        quantity = reservation.quantity(),
+                        actor = actor.name(),
+                        "stock reserved"
+                    );
+                    return Ok(ReservationResponse::from(reservation));
+                }
+                Err(RepositoryError::ConcurrentModification) if attempt < MAX_STOCK_WRITE_ATTEMPTS => continue,
+                Err(err) => return Err(ApplicationError::RepositoryError(err)),
+            }
+        }
+    }
+
+    pub async fn get_reservation(&self, id: i64, actor: &Actor) -> Result<ReservationResponse, ApplicationError> {
+        actor.authorize(Permission::ViewStock)?;
+        let reservation = self.find_reservation(&Self::reservation_id(id)?).await?;
+        Ok(ReservationResponse::from(reservation))
+    }
+
+    /// Turn the held units into a sale by `actor`, removing them from stock
+    pub async fn commit_reservation(&self, id: i64, actor: &Actor) -> Result<ReservationResponse, ApplicationError> {
+        actor.authorize(Permission::ReserveStock)?;
+        let settlement = Settlement::Commit { actor: actor.name() };
+        let reservation = self.settle(&Self::reservation_id(id)?, settlement).await?;
+        Ok(ReservationResponse::from(reservation))
+    }
+
+    /// Give the held units back without selling them
+    pub async fn release_reservation(&self, id: i64, actor: &Actor) -> Result<ReservationResponse, ApplicationError> {
+        actor.authorize(Permission::ReserveStock)?;
+        let reservation = self.settle(&Self::reservation_id(id)?, Settlement::Release).await?;
+        Ok(ReservationResponse::from(reservation))
+    }
+
+    /// Release every reservation whose expiry has passed. Returns how many were expired.
+    pub async fn expire_reservations(&self) -> Result<usize, ApplicationError> {
+        self.expire_due(None).await
+    }
+
+    /// Most recent ledger entries for a product, newest first. Entries name the staff
+    /// member behind each movement, so reading them takes `ViewStock`.
+    pub async fn stock_history(
+        &self,
+        id: i64,
+        limit: Option<usize>,
+        actor: &Actor,
+    ) -> Result<Vec<StockMovementResponse>, ApplicationError> {
+        actor.authorize(Permission::ViewStock)?;
+        let product_id = ProductId::new(id).map_err(ApplicationError::DomainError)?;
+        let product = self.find_product(&product_id).await?;
+        let limit = limit.unwrap_or(DEFAULT_STOCK_HISTORY_LIMIT).clamp(1, MAX_STOCK_HISTORY_LIMIT);
+
+        let movements = self.inventory.find_stock_movements(product.id(), limit).await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        Ok(movements.into_iter().map(StockMovementResponse::from).collect())
+    }
+
+    /// Products whose stock on hand does not equal the sum of their ledger entries.
+    /// An empty list means the ledger and the stock counters agree.
+    pub async fn reconcile_stock(&self, actor: &Actor) -> Result<Vec<StockDiscrepancyResponse>, ApplicationError> {
+        actor.authorize(Permission::ViewStock)?;
+        let discrepancies = self.inventory.reconcile_stock().await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        Ok(discrepancies.into_iter().map(StockDiscrepancyResponse::from).collect())
+    }
+
+    /// Periodically expire lapsed reservations until the task is aborted
+    pub fn spawn_expiry_sweeper(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match service.expire_reservations().await {
+                    Ok(0) => {}
+                    Ok(expired) => tracing::info!(expired, "expired stock reservations"),
+                    Err(err) => tracing::error!("reservation expiry failed: {}", err),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+
+    async fn expire_due(&self, product_id: Option<&ProductId>) -> Result<usize, ApplicationError> {
+        let due = self.inventory.find_expired_reservations(Utc::now(), product_id, EXPIRY_BATCH_SIZE).await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        let mut expired = 0;
+        for reservation in due {
+            match self.settle(reservation.id(), Settlement::Expire).await {
+                Ok(_) => expired += 1,
+                // Committed, released or deleted by someone else in the meantime
+                Err(ApplicationError::DomainError(_))
+                | Err(ApplicationError::ReservationNotFound)
+                | Err(ApplicationError::ProductNotFound) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(expired)
+    }
+
+    async fn settle(
+        &self,
+        id: &ReservationId,
+        settlement: Settlement<'_>,
+    ) -> Result<StockReservation, ApplicationError> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut reservation = self.find_reservation(id).await?;
+            let mut product = self.find_product(reservation.product_id()).await?;
+            let now = Utc::now();
+            let mut events = Vec::new();
+
+            match settlement {
+                Settlement::Commit { actor } => {
+                    reservation.commit(now).map_err(ApplicationError::DomainError)?;
+                    let old_stock = product.commit_reserved_stock(reservation.quantity(), actor)
+                        .map_err(ApplicationError::DomainError)?;
+                    events.push(ProductEvent::StockChanged {
+                        product_id: product.id().clone(),
+                        old_stock,
+                        new_stock: product.stock().clone(),
+                    });
+                }
+                Settlement::Release => {
+                    reservation.release(now).map_err(ApplicationError::DomainError)?;
+                    product.release_reserved_stock(reservation.quantity())
+                        .map_err(ApplicationError::DomainError)?;
+                }
+                Settlement::Expire => {
+                    reservation.expire(now).map_err(ApplicationError::DomainError)?;
+                    product.release_reserved_stock(reservation.quantity())
+                        .map_err(ApplicationError::DomainError)?;
+                }
+            }
+
+            match self.inventory.update_reservation(product, reservation, &events).await {
+                Ok((_, reservation)) => return Ok(reservation),
+                Err(RepositoryError::ConcurrentModification) if attempt < MAX_STOCK_WRITE_ATTEMPTS => continue,
+                Err(err) => return Err(ApplicationError::RepositoryError(err)),
+            }
+        }
+    }
+
+    async fn find_product(&self, id: &ProductId) -> Result<Product, ApplicationError> {
+        self.products.find_by_id(id).await
+            .map_err(ApplicationError::RepositoryError)?
+            .ok_or(ApplicationError::ProductNotFound)
+    }
+
+    async fn find_reservation(&self, id: &ReservationId) -> Result<StockReservation, ApplicationError> {
+        self.inventory.find_reservation(id).await
+            .map_err(ApplicationError::RepositoryError)?
+            .ok_or(ApplicationError::ReservationNotFound)
+    }
+
+    fn reservation_id(id: i64) -> Result<ReservationId, ApplicationError> {
+        ReservationId::new(id).map_err(ApplicationError::DomainError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use sqlx::SqlitePool;
+
+    use crate::application::{CreateProductRequest, ProductService, UpdateProductRequest, UserResponse};
+    use crate::domain::DomainError;
+    use crate::infrastructure::{migrated_pool, SqliteProductRepository};
+
+    async fn inventory() -> (Arc<SqliteProductRepository>, Arc<InventoryService>, SqlitePool) {
+        let pool = migrated_pool().await;
+        let repository = Arc::new(SqliteProductRepository::new(pool.clone()));
+        repository.initialize().await.expect("seed data");
+        let service = Arc::new(InventoryService::new(repository.clone(), repository.clone()));
+        (repository, service, pool)
+    }
+
+    async fn stock_of(repository: &SqliteProductRepository, id: i64) -> (i32, i32) {
+        let product = repository.find_by_id(&ProductId::new(id).unwrap()).await.unwrap().unwrap();
+        (product.stock().value(), product.reserved().value())
+    }
+
+    fn reserve(quantity: i32) -> CreateReservationRequest {
+        CreateReservationRequest { quantity, ttl_seconds: None }
+    }
+
+    #[tokio::test]
+    async fn concurrent_reservations_never_oversell() {
+        let (repository, service, pool) = inventory().await;
+        // Seeded "Apple Watch Ultra" has 8 units
+        let tasks: Vec<_> = (0..20)
+            .map(|_| {
+                let service = service.clone();
+                tokio::spawn(async move { service.reserve_stock(5, reserve(1), &Actor::system("test")).await })
+            })
+            .collect();
+
+        let mut granted = 0;
+        for task in tasks {
+            match task.await.unwrap() {
+                Ok(_) => granted += 1,
+                Err(ApplicationError::DomainError(DomainError::InsufficientStock))
+                | Err(ApplicationError::RepositoryError(RepositoryError::ConcurrentModification)) => {}
+                Err(err) => panic!("unexpected error: {}", err),
+            }
+        }
+        assert!(granted <= 8);
+        assert_eq!(stock_of(&repository, 5).await, (8, granted));
+
+        // Whatever lost a race can still be reserved, but never more than is on hand
+        while service.reserve_stock(5, reserve(1), &Actor::system("test")).await.is_ok() {}
+        let held: i64 = sqlx::query_scalar("SELECT SUM(quantity) FROM stock_reservations WHERE status = 'active'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!((stock_of(&repository, 5).await, held), ((8, 8), 8));
+    }
+
+    #[tokio::test]
+    async fn reservations_hold_stock_until_committed_released_or_expired() {
+        let (repository, service, pool) = inventory().await;
+
+        let committed = service.reserve_stock(5, reserve(3), &Actor::system("test")).await.unwrap();
+        let released = service.reserve_stock(5, reserve(2), &Actor::system("test")).await.unwrap();
+        let lapsing = service.reserve_stock(5, reserve(1), &Actor::system("test")).await.unwrap();
+        assert_eq!(stock_of(&repository, 5).await, (8, 6));
+
+        // Only the 2 unreserved units can be taken off the shelf
+        let too_many = AdjustStockRequest { delta: -3, reason: "correction".to_string(), note: Some("Damaged".to_string()) };
+        assert!(matches!(
+            service.adjust_stock(5, too_many, &Actor::system("test")).await,
+            Err(ApplicationError::DomainError(DomainError::InsufficientStock))
+        ));
+
+        assert_eq!(service.commit_reservation(committed.id, &Actor::system("test")).await.unwrap().status, "committed");
+        assert_eq!(stock_of(&repository, 5).await, (5, 3));
+        assert!(matches!(
+            service.commit_reservation(committed.id, &Actor::system("test")).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidReservation(_)))
+        ));
+
+        assert_eq!(service.release_reservation(released.id, &Actor::system("test")).await.unwrap().status, "released");
+        assert_eq!(stock_of(&repository, 5).await, (5, 1));
+
+        sqlx::query("UPDATE stock_reservations SET expires_at = '2000-01-01T00:00:00Z' WHERE id = ?")
+            .bind(lapsing.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(service.expire_reservations().await.unwrap(), 1);
+        assert_eq!(service.get_reservation(lapsing.id, &Actor::system("test")).await.unwrap().status, "expired");
+        assert_eq!(stock_of(&repository, 5).await, (5, 0));
+
+        // Only the commit changed stock on hand
+        let stock_events: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM outbox WHERE event_type = 'product.stock_changed'"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(stock_events, 1);
+    }
+
+    #[tokio::test]
+    async fn stock_adjustments_are_recorded_as_stock_changes() {
+        let (_, service, pool) = inventory().await;
+        let restock = AdjustStockRequest { delta: 12, reason: "restock".to_string(), note: None };
+
+        let product = service.adjust_stock(5, restock, &Actor::system("test")).await.unwrap();
+
+        assert_eq!((product.stock, product.version), (20, 2));
+        let payload: String = sqlx::query_scalar("SELECT payload FROM outbox WHERE event_type = 'product.stock_changed'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let payload: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!((payload["old_stock"].as_i64(), payload["new_stock"].as_i64()), (Some(8), Some(20)));
+
+        let no_reason = AdjustStockRequest { delta: 1, reason: " ".to_string(), note: None };
+        assert!(matches!(service.adjust_stock(5, no_reason, &Actor::system("test")).await, Err(ApplicationError::ValidationError(_))));
+    }
+
+    fn adjustment(delta: i32, reason: &str) -> AdjustStockRequest {
+        AdjustStockRequest { delta, reason: reason.to_string(), note: None }
+    }
+
+    #[tokio::test]
+    async fn every_stock_change_is_recorded_in_the_ledger() {
+        let (repository, service, _) = inventory().await;
+        let products = ProductService::new(repository.clone());
+        assert!(service.reconcile_stock(&Actor::system("test")).await.unwrap().is_empty(), "seed data is reconciled");
+
+        let created = products.create_product(CreateProductRequest {
+            name: "Standing Desk".to_string(),
+            description: None,
+            price: 499.0,
+            currency: None,
+            stock: 4,
+            tags: Vec::new(),
+            attributes: BTreeMap::new(),
+        }, &Actor::system("alice")).await.unwrap();
+        let id = created.id;
+
+        service.adjust_stock(id, adjustment(6, "restock"), &Actor::system("bob")).await.unwrap();
+        let reservation = service.reserve_stock(id, reserve(3), &Actor::system("test")).await.unwrap();
+        service.commit_reservation(reservation.id, &Actor::system("checkout")).await.unwrap();
+        products.update_product(id, UpdateProductRequest {
+            name: None,
+            description: None,
+            price: None,
+            currency: None,
+            stock: Some(5),
+            version: None,
+            tags: None,
+            attributes: None,
+        }, &Actor::system("carol")).await.unwrap();
+        service.adjust_stock(id, adjustment(1, "return"), &Actor::system("dave")).await.unwrap();
+
+        // A sale can only remove stock
+        assert!(matches!(
+            service.adjust_stock(id, adjustment(2, "sale"), &Actor::system("bob")).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidStock(_)))
+        ));
+
+        let history = service.stock_history(id, None, &Actor::system("test")).await.unwrap();
+        let entries: Vec<(i32, &str, &str, i32)> = history
+            .iter()
+            .map(|m| (m.delta, m.reason.as_str(), m.actor.as_str(), m.stock_after))
+            .collect();
+        assert_eq!(entries, vec![
+            (1, "return", "dave", 6),
+            (-2, "correction", "carol", 5),
+            (-3, "sale", "checkout", 7),
+            (6, "restock", "bob", 10),
+            (4, "restock", "alice", 4),
+        ]);
+        assert_eq!(stock_of(&repository, id).await, (6, 0));
+        assert!(service.reconcile_stock(&Actor::system("test")).await.unwrap().is_empty());
+        assert_eq!(service.stock_history(id, Some(2), &Actor::system("test")).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn reconciliation_reports_stock_changed_outside_the_ledger() {
+        let (_, service, pool) = inventory().await;
+
+        sqlx::query("UPDATE products SET stock = stock + 2 WHERE id = 3")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let discrepancies = service.reconcile_stock(&Actor::system("test")).await.unwrap();
+        let found: Vec<(i64, i32, i64)> = discrepancies
+            .iter()
+            .map(|d| (d.product_id, d.stock, d.ledger_total))
+            .collect();
+        assert_eq!(found, vec![(3, 52, 50)]);
+    }
+
+    #[tokio::test]
+    async fn stock_changes_follow_the_role_policy() {
+        let (repository, service, _) = inventory().await;
+        let signed_in = |role: &str| Actor::user(&UserResponse {
+            id: 1,
+            email: format!("{}@example.com", role),
+            name: role.to_string(),
+            role: role.to_string(),
+            created_at: Utc::now(),
+        });
+        let (editor, viewer) = (signed_in("catalog_editor"), signed_in("viewer"));
+        let refused = |result: Result<_, ApplicationError>| matches!(result, Err(ApplicationError::AuthorizationError(_)));
+
+        let held = service.reserve_stock(1, reserve(2), &editor).await.unwrap();
+        for actor in [&viewer, &Actor::anonymous("api")] {
+            assert!(refused(service.adjust_stock(1, adjustment(1, "restock"), actor).await.map(|_| ())));
+            assert!(refused(service.reserve_stock(1, reserve(1), actor).await.map(|_| ())));
+            assert!(refused(service.commit_reservation(held.id, actor).await.map(|_| ())));
+            assert!(refused(service.release_reservation(held.id, actor).await.map(|_| ())));
+            assert!(refused(service.get_reservation(held.id, actor).await.map(|_| ())));
+            assert!(refused(service.stock_history(1, None, actor).await.map(|_| ())));
+            assert!(refused(service.reconcile_stock(actor).await.map(|_| ())));
+        }
+        assert_eq!(service.get_reservation(held.id, &editor).await.unwrap().status, "active");
+        service.release_reservation(held.id, &editor).await.unwrap();
+        // Refused writes left nothing behind
+        assert_eq!(stock_of(&repository, 1).await, (10, 0));
+        assert_eq!(service.stock_history(1, None, &editor).await.unwrap().len(), 1);
+
+        service.adjust_stock(1, adjustment(1, "restock"), &editor).await.unwrap();
+        assert_eq!(stock_of(&repository, 1).await, (11, 0));
+    }
+}
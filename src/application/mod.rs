@@ -1,5 +1,27 @@
+pub mod accounts;
+pub mod api_keys;
+pub mod carts;
+pub mod categories;
 pub mod dtos;
+pub mod events;
+pub mod inventory;
+pub mod orders;
+pub mod policy;
 pub mod services;
+pub mod suggestions;
+pub mod validation;
+pub mod webhooks;
 
+pub use accounts::*;
+pub use api_keys::*;
+pub use carts::*;
+pub use categories::*;
 pub use dtos::*;
-pub use services::*;
\ No newline at end of file
+pub use events::*;
+pub use inventory::*;
+pub use orders::*;
+pub use policy::*;
+pub use services::*;
+pub use suggestions::*;
+pub use validation::*;
+pub use webhooks::*;
@@ -0,0 +1,463 @@
+use std::sync::Arc;
+
+use crate::application::dtos::OrderResponse;
+use crate::application::inventory::MAX_STOCK_WRITE_ATTEMPTS;
+use crate::application::policy::{Actor, Permission};
+use crate::application::services::ApplicationError;
+use crate::domain::{
+    CartRepository, Order, OrderId, OrderLine, OrderOwner, OrderRepository, OrderStatus, Product,
+    ProductEvent, ProductRepository, RepositoryError, StockMovementReason,
+};
+
+/// Orders listed per owner
+pub const ORDER_LIST_LIMIT: usize = 50;
+
+/// Application service for placing orders and moving them through their lifecycle.
+///
+/// Orders belong to the session whose cart they were placed from and to the customer signed
+/// in at the time, who may read them and cancel them while pending. Payment, shipping,
+/// delivery and cancelling a paid order are done by staff holding `FulfilOrders`, for any
+/// order. Placing an order sells its units and cancelling one puts them back, each in the
+/// same transaction as the order.
+pub struct OrderService {
+    orders: Arc<dyn OrderRepository>,
+    carts: Arc<dyn CartRepository>,
+    products: Arc<dyn ProductRepository>,
+}
+
+impl OrderService {
+    pub fn new(
+        orders: Arc<dyn OrderRepository>,
+        carts: Arc<dyn CartRepository>,
+        products: Arc<dyn ProductRepository>,
+    ) -> Self {
+        Self { orders, carts, products }
+    }
+
+    /// Turn the owner's cart into a pending order on behalf of `actor`, taking every
+    /// line out of stock and emptying the cart in one transaction. Nothing is sold if any
+    /// line is short, and a cart whose prices changed is re-priced and sent back for review.
+    pub async fn place_order(&self, owner: &OrderOwner, actor: &str) -> Result<OrderResponse, ApplicationError> {
+        let mut attempt = 0;
+        let order = loop {
+            attempt += 1;
+            let mut cart = self.carts.find_by_token(&owner.session_token).await
+                .map_err(ApplicationError::RepositoryError)?
+                .filter(|cart| !cart.lines().is_empty())
+                .ok_or_else(|| ApplicationError::validation("Your cart is empty"))?;
+
+            let mut products = Vec::with_capacity(cart.lines().len());
+            for line in cart.lines() {
+                products.push(self.products.find_by_id(line.product_id()).await
+                    .map_err(ApplicationError::RepositoryError)?
+                    .ok_or(ApplicationError::ProductNotFound)?);
+            }
+
+            let mut repriced = Vec::new();
+            for product in &products {
+                if cart.reprice(product) {
+                    repriced.push(product.name().value());
+                }
+            }
+            if !repriced.is_empty() {
+                let message = format!(
+                    "The price of {} changed; please review your cart",
+                    repriced.join(", ")
+                );
+                self.carts.save(cart).await
+                    .map_err(ApplicationError::RepositoryError)?;
+                return Err(ApplicationError::validation(message));
+            }
+
+            let mut events = Vec::with_capacity(products.len());
+            let mut lines = Vec::with_capacity(products.len());
+            for (line, product) in cart.lines().iter().zip(products.iter_mut()) {
+                let old_stock = product.adjust_stock(-line.quantity(), StockMovementReason::Sale, actor, None)
+                    .map_err(ApplicationError::DomainError)?;
+                events.push(ProductEvent::StockChanged {
+                    product_id: product.id().clone(),
+                    old_stock,
+                    new_stock: product.stock().clone(),
+                });
+                lines.push(OrderLine::new(product, line.quantity(), line.unit_price().clone())
+                    .map_err(ApplicationError::DomainError)?);
+            }
+
+            let id = self.orders.next_id().await
+                .map_err(ApplicationError::RepositoryError)?;
+            let order = Order::place(id, owner, lines)
+                .map_err(ApplicationError::DomainError)?;
+
+            match self.orders.place(order, &cart, &products, &events).await {
+                Ok(order) => break order,
+                Err(RepositoryError::ConcurrentModification) if attempt < MAX_STOCK_WRITE_ATTEMPTS => continue,
+                Err(err) => return Err(ApplicationError::RepositoryError(err)),
+            }
+        };
+        tracing::info!(order_id = order.id().value(), actor, items = order.item_count(), "order placed");
+
+        OrderResponse::try_from(order).map_err(ApplicationError::DomainError)
+    }
+
+    /// Most recent orders of the owner, newest first
+    pub async fn list_orders(&self, owner: &OrderOwner) -> Result<Vec<OrderResponse>, ApplicationError> {
+        let orders = self.orders.find_by_owner(owner, ORDER_LIST_LIMIT).await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        orders
+            .into_iter()
+            .map(|order| OrderResponse::try_from(order).map_err(ApplicationError::DomainError))
+            .collect()
+    }
+
+    pub async fn get_order(&self, owner: &OrderOwner, id: i64) -> Result<OrderResponse, ApplicationError> {
+        let order = self.find_order(Some(owner), id).await?;
+        OrderResponse::try_from(order).map_err(ApplicationError::DomainError)
+    }
+
+    pub async fn pay_order(&self, id: i64, actor: &Actor) -> Result<OrderResponse, ApplicationError> {
+        actor.authorize(Permission::FulfilOrders)?;
+        self.transition(None, id, OrderStatus::Paid, None).await
+    }
+
+    pub async fn ship_order(&self, id: i64, actor: &Actor) -> Result<OrderResponse, ApplicationError> {
+        actor.authorize(Permission::FulfilOrders)?;
+        self.transition(None, id, OrderStatus::Shipped, None).await
+    }
+
+    pub async fn deliver_order(&self, id: i64, actor: &Actor) -> Result<OrderResponse, ApplicationError> {
+        actor.authorize(Permission::FulfilOrders)?;
+        self.transition(None, id, OrderStatus::Delivered, None).await
+    }
+
+    /// Cancel an order that has not shipped yet and return its units to stock on behalf of
+    /// `actor`. Customers may cancel their own pending orders; a paid order needs
+    /// `FulfilOrders`, since staff arrange the refund. Staff may cancel any order.
+    pub async fn cancel_order(&self, owner: &OrderOwner, id: i64, actor: &Actor) -> Result<OrderResponse, ApplicationError> {
+        let owner = (!actor.can(Permission::FulfilOrders)).then_some(owner);
+        self.transition(owner, id, OrderStatus::Cancelled, Some(actor)).await
+    }
+
+    /// Move an order to `next`, looking it up among the orders of `owner` when given. With
+    /// `cancelled_by`, a paid order needs that actor to hold `FulfilOrders`, and every line
+    /// whose product still exists goes back into stock.
+    async fn transition(
+        &self,
+        owner: Option<&OrderOwner>,
+        id: i64,
+        next: OrderStatus,
+        cancelled_by: Option<&Actor>,
+    ) -> Result<OrderResponse, ApplicationError> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut order = self.find_order(owner, id).await?;
+            if cancelled_by.is_some_and(|actor| order.status() == OrderStatus::Paid && !actor.can(Permission::FulfilOrders)) {
+                return Err(ApplicationError::authorization(
+                    "Paid orders can only be cancelled by staff, who arrange the refund",
+                ));
+            }
+            let previous = order.transition_to(next)
+                .map_err(ApplicationError::DomainError)?;
+
+            let (products, events) = match cancelled_by {
+                Some(actor) => self.restock(&order, actor.name()).await?,
+                None => (Vec::new(), Vec::new()),
+            };
+
+            match self.orders.update_status(order, previous, &products, &events).await {
+                Ok(order) => {
+                    tracing::info!(order_id = id, status = next.as_str(), "order status changed");
+                    return OrderResponse::try_from(order).map_err(ApplicationError::DomainError);
+                }
+                Err(RepositoryError::ConcurrentModification) if attempt < MAX_STOCK_WRITE_ATTEMPTS => continue,
+                Err(err) => return Err(ApplicationError::RepositoryError(err)),
+            }
+        }
+    }
+
+    async fn restock(&self, order: &Order, actor: &str) -> Result<(Vec<Product>, Vec<ProductEvent>), ApplicationError> {
+        let note = format!("Order #{} cancelled", order.id().value());
+        let mut products = Vec::with_capacity(order.lines().len());
+        let mut events = Vec::with_capacity(order.lines().len());
+        for line in order.lines() {
+            let Some(mut product) = self.products.find_by_id(line.product_id()).await
+                .map_err(ApplicationError::RepositoryError)?
+            else {
+                continue;
+            };
+            let old_stock = product.adjust_stock(line.quantity(), StockMovementReason::Return, actor, Some(note.clone()))
+                .map_err(ApplicationError::DomainError)?;
+            events.push(ProductEvent::StockChanged {
+                product_id: product.id().clone(),
+                old_stock,
+                new_stock: product.stock().clone(),
+            });
+            products.push(product);
+        }
+        Ok((products, events))
+    }
+
+    /// The order, if it belongs to `owner`; any order without one
+    async fn find_order(&self, owner: Option<&OrderOwner>, id: i64) -> Result<Order, ApplicationError> {
+        let order_id = OrderId::new(id).map_err(ApplicationError::DomainError)?;
+
+        self.orders.find_by_id(&order_id).await
+            .map_err(ApplicationError::RepositoryError)?
+            .filter(|order| owner.is_none_or(|owner| order.belongs_to(owner)))
+            .ok_or(ApplicationError::OrderNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::SqlitePool;
+
+    use crate::application::{AddCartItemRequest, CartService, InventoryService, UserResponse};
+    use crate::domain::{DomainError, ProductId, UserId};
+    use crate::infrastructure::{
+        migrated_pool, SqliteCartRepository, SqliteOrderRepository, SqliteProductRepository,
+    };
+
+    struct Services {
+        orders: OrderService,
+        carts: CartService,
+        inventory: InventoryService,
+        pool: SqlitePool,
+    }
+
+    async fn services() -> Services {
+        let pool = migrated_pool().await;
+        let products = Arc::new(SqliteProductRepository::new(pool.clone()));
+        products.initialize().await.expect("seed data");
+        let carts = Arc::new(SqliteCartRepository::new(pool.clone()));
+
+        Services {
+            orders: OrderService::new(Arc::new(SqliteOrderRepository::new(pool.clone())), carts.clone(), products.clone()),
+            carts: CartService::new(carts, products.clone()),
+            inventory: InventoryService::new(products.clone(), products),
+            pool,
+        }
+    }
+
+    async fn stock(pool: &SqlitePool, product_id: i64) -> i32 {
+        sqlx::query_scalar("SELECT stock FROM products WHERE id = ?")
+            .bind(product_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    /// A visitor's orders, known by their cart session only
+    fn session(token: &str) -> OrderOwner {
+        OrderOwner::new(token, None)
+    }
+
+    fn add(product_id: i64, quantity: i32) -> AddCartItemRequest {
+        AddCartItemRequest { product_id, quantity: Some(quantity) }
+    }
+
+    #[tokio::test]
+    async fn placing_an_order_sells_the_cart() {
+        let s = services().await;
+        s.carts.add_item("alice", add(1, 2)).await.unwrap();
+        s.carts.add_item("alice", add(5, 3)).await.unwrap();
+
+        let order = s.orders.place_order(&session("alice"), "test").await.unwrap();
+        assert_eq!((order.status.as_str(), order.item_count), ("pending", 5));
+        assert_eq!(order.total_minor, 2 * 249_999 + 3 * 79_999);
+        assert_eq!((stock(&s.pool, 1).await, stock(&s.pool, 5).await), (8, 5));
+
+        let history = s.inventory.stock_history(5, None, &Actor::system("test")).await.unwrap();
+        assert_eq!((history[0].delta, history[0].reason.as_str()), (-3, "sale"));
+        assert!(s.carts.get_cart("alice").await.unwrap().items.is_empty());
+        assert!(s.inventory.reconcile_stock(&Actor::system("test")).await.unwrap().is_empty());
+
+        // Orders are only visible to the session that placed them
+        assert_eq!(s.orders.list_orders(&session("alice")).await.unwrap().len(), 1);
+        assert!(matches!(s.orders.get_order(&session("bob"), order.id).await, Err(ApplicationError::OrderNotFound)));
+    }
+
+    #[tokio::test]
+    async fn an_order_is_refused_when_any_line_is_short() {
+        let s = services().await;
+        s.carts.add_item("alice", add(1, 2)).await.unwrap();
+        s.carts.add_item("alice", add(5, 8)).await.unwrap();
+        // Someone else bought part of the watches after they were added to the cart
+        sqlx::query("UPDATE products SET stock = 6 WHERE id = 5").execute(&s.pool).await.unwrap();
+
+        assert!(matches!(
+            s.orders.place_order(&session("alice"), "test").await,
+            Err(ApplicationError::DomainError(DomainError::InsufficientStock))
+        ));
+        assert_eq!((stock(&s.pool, 1).await, stock(&s.pool, 5).await), (10, 6));
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM orders").fetch_one(&s.pool).await.unwrap();
+        assert_eq!(stored, 0);
+        assert_eq!(s.carts.get_cart("alice").await.unwrap().items.len(), 2);
+
+        assert!(matches!(
+            s.orders.place_order(&session("bob"), "test").await,
+            Err(ApplicationError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn a_cart_is_only_ordered_once() {
+        let s = services().await;
+        s.carts.add_item("alice", add(2, 1)).await.unwrap();
+        let carts = SqliteCartRepository::new(s.pool.clone());
+        let stale = carts.find_by_token("alice").await.unwrap().unwrap();
+
+        s.orders.place_order(&session("alice"), "test").await.unwrap();
+
+        // A second checkout racing the first still holds the cart as it was read
+        let product = SqliteProductRepository::new(s.pool.clone())
+            .find_by_id(&ProductId::new(2).unwrap()).await.unwrap().unwrap();
+        let line = OrderLine::new(&product, 1, product.price().clone()).unwrap();
+        let order = Order::place(OrderId::new(1).unwrap(), &session("alice"), vec![line]).unwrap();
+        assert!(matches!(
+            SqliteOrderRepository::new(s.pool.clone()).place(order, &stale, &[], &[]).await,
+            Err(RepositoryError::ConcurrentModification)
+        ));
+
+        assert!(matches!(
+            s.orders.place_order(&session("alice"), "test").await,
+            Err(ApplicationError::ValidationError(_))
+        ));
+        assert_eq!(s.orders.list_orders(&session("alice")).await.unwrap().len(), 1);
+        assert_eq!(stock(&s.pool, 2).await, 24);
+    }
+
+    #[tokio::test]
+    async fn a_changed_price_is_reviewed_before_ordering() {
+        let s = services().await;
+        s.carts.add_item("alice", add(2, 2)).await.unwrap();
+        sqlx::query("UPDATE products SET price_minor = 89999 WHERE id = 2")
+            .execute(&s.pool)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            s.orders.place_order(&session("alice"), "test").await,
+            Err(ApplicationError::ValidationError(_))
+        ));
+        assert_eq!(stock(&s.pool, 2).await, 25);
+        assert_eq!(s.carts.get_cart("alice").await.unwrap().items[0].unit_price_minor, 89_999);
+
+        let order = s.orders.place_order(&session("alice"), "test").await.unwrap();
+        assert_eq!(order.total_minor, 2 * 89_999);
+    }
+
+    #[tokio::test]
+    async fn orders_follow_the_status_machine() {
+        let s = services().await;
+        s.carts.add_item("alice", add(2, 1)).await.unwrap();
+        let order = s.orders.place_order(&session("alice"), "test").await.unwrap();
+        let staff = Actor::system("test");
+
+        assert!(matches!(
+            s.orders.ship_order(order.id, &staff).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidOrder(_)))
+        ));
+        assert_eq!(s.orders.pay_order(order.id, &staff).await.unwrap().status, "paid");
+        assert_eq!(s.orders.ship_order(order.id, &staff).await.unwrap().status, "shipped");
+        assert!(matches!(
+            s.orders.cancel_order(&session("alice"), order.id, &Actor::anonymous("web")).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidOrder(_)))
+        ));
+        assert_eq!(s.orders.deliver_order(order.id, &staff).await.unwrap().status, "delivered");
+        assert_eq!(stock(&s.pool, 2).await, 24);
+    }
+
+    #[tokio::test]
+    async fn only_staff_fulfil_orders() {
+        let s = services().await;
+        s.carts.add_item("alice", add(2, 1)).await.unwrap();
+        let order = s.orders.place_order(&session("alice"), "test").await.unwrap();
+        let editor = Actor::user(&UserResponse {
+            id: 1,
+            email: "editor@example.com".to_string(),
+            name: "editor".to_string(),
+            role: "catalog_editor".to_string(),
+            created_at: chrono::Utc::now(),
+        });
+
+        for actor in [Actor::anonymous("alice"), editor] {
+            assert!(matches!(
+                s.orders.pay_order(order.id, &actor).await,
+                Err(ApplicationError::AuthorizationError(_))
+            ));
+        }
+        assert_eq!(s.orders.get_order(&session("alice"), order.id).await.unwrap().status, "pending");
+        assert_eq!(s.orders.pay_order(order.id, &Actor::system("test")).await.unwrap().status, "paid");
+    }
+
+    #[tokio::test]
+    async fn cancelling_an_order_restocks_its_items() {
+        let s = services().await;
+        s.carts.add_item("alice", add(3, 4)).await.unwrap();
+        let order = s.orders.place_order(&session("alice"), "test").await.unwrap();
+
+        let cancelled = s.orders.cancel_order(&session("alice"), order.id, &Actor::anonymous("web")).await.unwrap();
+        assert_eq!(cancelled.status, "cancelled");
+        assert_eq!(stock(&s.pool, 3).await, 50);
+
+        let history = s.inventory.stock_history(3, None, &Actor::system("test")).await.unwrap();
+        assert_eq!((history[0].delta, history[0].reason.as_str()), (4, "return"));
+        assert_eq!(history[0].note.as_deref(), Some(format!("Order #{} cancelled", order.id).as_str()));
+        assert!(s.inventory.reconcile_stock(&Actor::system("test")).await.unwrap().is_empty());
+
+        // Lines keep the name and price the order was placed with
+        assert_eq!(cancelled.items[0].unit_price_minor, 24_999);
+        assert!(matches!(
+            s.orders.cancel_order(&session("alice"), order.id, &Actor::anonymous("web")).await,
+            Err(ApplicationError::DomainError(DomainError::InvalidOrder(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn paid_orders_are_only_cancelled_by_staff() {
+        let s = services().await;
+        s.carts.add_item("alice", add(3, 4)).await.unwrap();
+        let order = s.orders.place_order(&session("alice"), "test").await.unwrap();
+        let staff = Actor::system("support");
+        s.orders.pay_order(order.id, &staff).await.unwrap();
+
+        assert!(matches!(
+            s.orders.cancel_order(&session("alice"), order.id, &Actor::anonymous("web")).await,
+            Err(ApplicationError::AuthorizationError(_))
+        ));
+        assert_eq!(stock(&s.pool, 3).await, 46);
+
+        // Staff find the order whichever session asks, and restock it in their name
+        let cancelled = s.orders.cancel_order(&session("support-desk"), order.id, &staff).await.unwrap();
+        assert_eq!(cancelled.status, "cancelled");
+        assert_eq!(stock(&s.pool, 3).await, 50);
+        let history = s.inventory.stock_history(3, None, &staff).await.unwrap();
+        assert_eq!((history[0].delta, history[0].actor.as_str()), (4, "support"));
+    }
+
+    #[tokio::test]
+    async fn signed_in_customers_keep_their_orders_in_a_new_session() {
+        let s = services().await;
+        let alice = |token: &str| OrderOwner::new(token, Some(UserId::new(7).unwrap()));
+        s.carts.add_item("laptop", add(2, 1)).await.unwrap();
+        s.carts.add_item("visitor", add(2, 1)).await.unwrap();
+        let order = s.orders.place_order(&alice("laptop"), "test").await.unwrap();
+        s.orders.place_order(&session("visitor"), "test").await.unwrap();
+
+        let listed = s.orders.list_orders(&alice("phone")).await.unwrap();
+        assert_eq!(listed.iter().map(|order| order.id).collect::<Vec<_>>(), vec![order.id]);
+        assert_eq!(s.orders.get_order(&alice("phone"), order.id).await.unwrap().id, order.id);
+        assert!(matches!(
+            s.orders.get_order(&session("phone"), order.id).await,
+            Err(ApplicationError::OrderNotFound)
+        ));
+        let other = OrderOwner::new("phone", Some(UserId::new(8).unwrap()));
+        assert!(s.orders.list_orders(&other).await.unwrap().is_empty());
+
+        let cancelled = s.orders.cancel_order(&alice("phone"), order.id, &Actor::anonymous("web")).await.unwrap();
+        assert_eq!(cancelled.status, "cancelled");
+    }
+}
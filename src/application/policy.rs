@@ -0,0 +1,143 @@
+use crate::application::dtos::{ApiKeyResponse, UserResponse};
+use crate::application::services::ApplicationError;
+use crate::domain::{ApiScope, Role};
+
+/// Operations that need more than read access to the public catalog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    ViewStock,
+    CreateProduct,
+    EditProduct,
+    DeleteProduct,
+    AdjustStock,
+    ReserveStock,
+    ManageCategories,
+    ManageRoles,
+    ManageApiKeys,
+    ManageWebhooks,
+    FulfilOrders,
+}
+
+impl Permission {
+    fn describe(&self) -> &'static str {
+        match self {
+            Permission::ViewStock => "view stock history, reservations and reconciliation",
+            Permission::CreateProduct => "create products",
+            Permission::EditProduct => "edit products",
+            Permission::DeleteProduct => "delete products",
+            Permission::AdjustStock => "adjust stock",
+            Permission::ReserveStock => "reserve stock",
+            Permission::ManageCategories => "manage categories",
+            Permission::ManageRoles => "change account roles",
+            Permission::ManageApiKeys => "manage API keys",
+            Permission::ManageWebhooks => "manage webhooks",
+            Permission::FulfilOrders => "mark orders paid, shipped or delivered, or cancel paid orders",
+        }
+    }
+}
+
+/// The role → permission table. Admins may do everything, catalog editors may create
+/// and edit products, their stock, reservations and categories, viewers only read the
+/// public catalog.
+pub fn role_allows(role: Role, permission: Permission) -> bool {
+    match role {
+        Role::Admin => true,
+        Role::CatalogEditor => matches!(
+            permission,
+            Permission::ViewStock
+                | Permission::CreateProduct
+                | Permission::EditProduct
+                | Permission::AdjustStock
+                | Permission::ReserveStock
+                | Permission::ManageCategories
+        ),
+        Role::Viewer => false,
+    }
+}
+
+/// The scope → permission table for API keys. Any valid key reads the public catalog;
+/// `products:read` adds the stock records. Keys never manage accounts or other keys.
+pub fn scope_allows(scope: ApiScope, permission: Permission) -> bool {
+    match scope {
+        ApiScope::ReadProducts => permission == Permission::ViewStock,
+        ApiScope::WriteProducts => matches!(
+            permission,
+            Permission::CreateProduct
+                | Permission::EditProduct
+                | Permission::DeleteProduct
+                | Permission::ManageCategories
+        ),
+        ApiScope::ManageStock => matches!(
+            permission,
+            Permission::ViewStock | Permission::AdjustStock | Permission::ReserveStock
+        ),
+    }
+}
+
+/// Where an actor's permissions come from
+#[derive(Debug, Clone)]
+enum Grant {
+    /// Not signed in; nothing beyond reads
+    Anonymous,
+    Role(Role),
+    Scopes(Vec<ApiScope>),
+}
+
+/// Who performs an operation: the name recorded in the stock ledger and the role or API
+/// key scopes the policy checks. Visitors who are not signed in have neither.
+#[derive(Debug, Clone)]
+pub struct Actor {
+    name: String,
+    grant: Grant,
+}
+
+impl Actor {
+    /// A visitor who is not signed in, recorded as `name` (e.g. "web" or "api")
+    pub fn anonymous(name: &str) -> Self {
+        Self { name: name.to_string(), grant: Grant::Anonymous }
+    }
+
+    /// A signed-in customer, recorded by email
+    pub fn user(user: &UserResponse) -> Self {
+        let grant = Role::try_from(user.role.as_str()).map_or(Grant::Anonymous, Grant::Role);
+        Self { name: user.email.clone(), grant }
+    }
+
+    /// A machine client authenticated by API key, recorded by the key's name
+    pub fn api_key(key: &ApiKeyResponse) -> Self {
+        let scopes = key.scopes.iter()
+            .filter_map(|scope| ApiScope::try_from(scope.as_str()).ok())
+            .collect();
+        Self { name: format!("api-key:{}", key.name), grant: Grant::Scopes(scopes) }
+    }
+
+    /// Trusted callers such as command-line tools and tests
+    pub fn system(name: &str) -> Self {
+        Self { name: name.to_string(), grant: Grant::Role(Role::Admin) }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn can(&self, permission: Permission) -> bool {
+        match &self.grant {
+            Grant::Anonymous => false,
+            Grant::Role(role) => role_allows(*role, permission),
+            Grant::Scopes(scopes) => scopes.iter().any(|scope| scope_allows(*scope, permission)),
+        }
+    }
+
+    /// Fail with `AuthorizationError` unless the actor holds `permission`
+    pub fn authorize(&self, permission: Permission) -> Result<(), ApplicationError> {
+        if self.can(permission) {
+            return Ok(());
+        }
+        let message = match self.grant {
+            Grant::Anonymous => format!("Sign in to {}", permission.describe()),
+            Grant::Role(_) => format!("You are not allowed to {}", permission.describe()),
+            Grant::Scopes(_) => format!("This API key is not allowed to {}", permission.describe()),
+        };
+        Err(ApplicationError::authorization(message))
+    }
+}
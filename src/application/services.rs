@@ -1,13 +1,28 @@
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use crate::domain::{
-    Product, ProductId, ProductName, Money, StockQuantity, 
-    ProductRepository, DomainError, RepositoryError
+    Product, ProductId, ProductName, Money, Currency, StockQuantity, 
+    ProductRepository, DomainError, RepositoryError, ProductEvent,
+    ProductQuery, ProductSortField, SortDirection, PriceRange, SearchHighlight, Tag, ProductAttribute,
 };
 use crate::application::dtos::{
-    CreateProductRequest, UpdateProductRequest, ProductResponse, SearchProductsQuery
+    CreateProductRequest, UpdateProductRequest, ProductResponse, SearchProductsQuery,
+    PaginatedResponse, HighlightResponse, FacetsResponse,
 };
+use crate::application::policy::{Actor, Permission};
+use crate::application::validation::{
+    describe, parse_attributes, parse_tags, validate_create_product, validate_update_product, FieldError,
+    FieldErrors,
+};
+
+/// Page size used when the client does not ask for one
+pub const DEFAULT_PAGE_SIZE: usize = 12;
+/// Largest page a client may request
+pub const MAX_PAGE_SIZE: usize = 100;
 
-/// Application service for product operations
+/// Application service for product operations.
+///
+/// Catalog writes are checked against the actor's role before anything is read or changed.
 pub struct ProductService {
     repository: Arc<dyn ProductRepository>,
 }
@@ -21,30 +36,49 @@ impl ProductService {
     pub async fn create_product(
         &self,
         request: CreateProductRequest,
+        actor: &Actor,
     ) -> Result<ProductResponse, ApplicationError> {
-        // Validate input
+        actor.authorize(Permission::CreateProduct)?;
+
+        // Check every field first, so all problems are reported at once
+        validate_create_product(&request)?;
         let name = ProductName::new(request.name)
             .map_err(ApplicationError::DomainError)?;
-        let price = Money::new(request.price)
+        let currency = match request.currency.as_deref() {
+            Some(code) => Currency::new(code).map_err(ApplicationError::DomainError)?,
+            None => Currency::default(),
+        };
+        let price = Money::from_decimal(request.price, currency)
             .map_err(ApplicationError::DomainError)?;
         let stock = StockQuantity::new(request.stock)
             .map_err(ApplicationError::DomainError)?;
+        let tags = parse_tags(&request.tags).map_err(ApplicationError::DomainError)?;
+        let attributes = parse_attributes(&request.attributes).map_err(ApplicationError::DomainError)?;
 
         // Get next ID
         let id = self.repository.next_id().await
             .map_err(ApplicationError::RepositoryError)?;
 
-        // Create product entity
-        let product = Product::new(id, name, request.description, price, stock);
+        // Create product entity; initial stock becomes its first ledger entry
+        let product = Product::new(id, name, request.description, price, stock, actor.name())
+            .with_details(tags, attributes);
+
+        // The id is assigned on insert; the repository records the event against it
+        let event = ProductEvent::ProductCreated {
+            product_id: product.id().clone(),
+            name: product.name().clone(),
+            price: product.price().clone(),
+        };
 
-        // Save to repository
-        let saved_product = self.repository.save(product).await
+        // Save to repository together with its event
+        let saved_product = self.repository.save(product, &[event]).await
             .map_err(ApplicationError::RepositoryError)?;
 
         Ok(ProductResponse::from(saved_product))
     }
 
     /// Get all products
+    #[allow(dead_code)]
     pub async fn get_all_products(&self) -> Result<Vec<ProductResponse>, ApplicationError> {
         let products = self.repository.find_all().await
             .map_err(ApplicationError::RepositoryError)?;
@@ -64,12 +98,14 @@ impl ProductService {
         Ok(ProductResponse::from(product))
     }
 
-    /// Update product
+    /// Update product. `actor` is recorded in the stock ledger if the stock level changes.
     pub async fn update_product(
         &self,
         id: i64,
         request: UpdateProductRequest,
+        actor: &Actor,
     ) -> Result<ProductResponse, ApplicationError> {
+        actor.authorize(Permission::EditProduct)?;
         let product_id = ProductId::new(id)
             .map_err(ApplicationError::DomainError)?;
 
@@ -78,38 +114,96 @@ impl ProductService {
             .map_err(ApplicationError::RepositoryError)?
             .ok_or(ApplicationError::ProductNotFound)?;
 
-        // Validate and convert updates
+        // Reject edits based on a stale copy; the repository re-checks on write
+        if request.version.is_some_and(|version| version != product.version()) {
+            return Err(ApplicationError::RepositoryError(RepositoryError::ConcurrentModification));
+        }
+
+        // Check every supplied field first, so all problems are reported at once
+        validate_update_product(&request, product.price())?;
+
+        // Convert updates
         let name = if let Some(name_str) = request.name {
             Some(ProductName::new(name_str).map_err(ApplicationError::DomainError)?)
         } else {
             None
         };
 
-        let price = if let Some(price_val) = request.price {
-            Some(Money::new(price_val).map_err(ApplicationError::DomainError)?)
+        let currency = if let Some(code) = request.currency.as_deref() {
+            Some(Currency::new(code).map_err(ApplicationError::DomainError)?)
         } else {
             None
         };
 
+        let price = match (request.price, currency) {
+            (Some(price_val), currency) => {
+                let currency = currency.unwrap_or_else(|| product.price().currency());
+                Some(Money::from_decimal(price_val, currency).map_err(ApplicationError::DomainError)?)
+            }
+            // Validation refused a new currency without a price, so the price stays as it is
+            (None, _) => None,
+        };
+
         let stock = if let Some(stock_val) = request.stock {
             Some(StockQuantity::new(stock_val).map_err(ApplicationError::DomainError)?)
         } else {
             None
         };
+        let tags = request.tags.as_deref().map(parse_tags).transpose()
+            .map_err(ApplicationError::DomainError)?;
+        let attributes = request.attributes.as_ref().map(parse_attributes).transpose()
+            .map_err(ApplicationError::DomainError)?;
 
         // Update product
-        product.update(name, Some(request.description), price, stock)
-            .map_err(ApplicationError::DomainError)?;
+        let old_stock = product.stock().clone();
+        let mut changes = product.update(name, request.description, price, stock, actor.name())
+            .map_err(|err| match err {
+                // Only the reserved-units check can fail here
+                DomainError::InvalidStock(message) => FieldErrors::only("stock", "below_reserved", message),
+                other => ApplicationError::DomainError(other),
+            })?;
+        if let Some(tags) = tags {
+            if product.set_tags(tags).map_err(ApplicationError::DomainError)? {
+                changes.push("tags".to_string());
+            }
+        }
+        if let Some(attributes) = attributes {
+            if product.set_attributes(attributes).map_err(ApplicationError::DomainError)? {
+                changes.push("attributes".to_string());
+            }
+        }
+
+        let mut events = Vec::new();
+        if !changes.is_empty() {
+            let stock_changed = changes.iter().any(|field| field == "stock");
+            events.push(ProductEvent::ProductUpdated {
+                product_id: product.id().clone(),
+                changes,
+            });
+            if stock_changed {
+                events.push(ProductEvent::StockChanged {
+                    product_id: product.id().clone(),
+                    old_stock,
+                    new_stock: product.stock().clone(),
+                });
+            }
+        }
 
-        // Save updated product
-        let updated_product = self.repository.update(product).await
+        // Save updated product together with its events
+        let updated_product = self.repository.update(product, &events).await
             .map_err(ApplicationError::RepositoryError)?;
 
         Ok(ProductResponse::from(updated_product))
     }
 
-    /// Delete product
-    pub async fn delete_product(&self, id: i64) -> Result<bool, ApplicationError> {
+    /// Delete product, optionally only if it is still at `expected_version`
+    pub async fn delete_product(
+        &self,
+        id: i64,
+        expected_version: Option<i64>,
+        actor: &Actor,
+    ) -> Result<bool, ApplicationError> {
+        actor.authorize(Permission::DeleteProduct)?;
         let product_id = ProductId::new(id)
             .map_err(ApplicationError::DomainError)?;
 
@@ -121,30 +215,136 @@ impl ProductService {
             return Err(ApplicationError::ProductNotFound);
         }
 
-        // Delete product
-        let deleted = self.repository.delete(&product_id).await
-            .map_err(ApplicationError::RepositoryError)?;
-
-        Ok(deleted)
+        // Delete product together with its event
+        let event = ProductEvent::ProductDeleted { product_id: product_id.clone() };
+        self.repository.delete(&product_id, expected_version, &[event]).await
+            .map_err(ApplicationError::RepositoryError)
     }
 
-    /// Search products
+    /// Search, filter, sort and paginate products
     pub async fn search_products(
         &self,
         query: SearchProductsQuery,
-    ) -> Result<Vec<ProductResponse>, ApplicationError> {
-        let products = match query.query {
-            Some(search_term) if !search_term.trim().is_empty() => {
-                self.repository.search_by_name(&search_term).await
-                    .map_err(ApplicationError::RepositoryError)?
+    ) -> Result<PaginatedResponse<ProductResponse>, ApplicationError> {
+        let product_query = Self::build_query(&query)?;
+
+        let result = self.repository.find_page(&product_query).await
+            .map_err(ApplicationError::RepositoryError)?;
+        let facets = self.repository.find_facets(&product_query).await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        let mut highlights: HashMap<i64, SearchHighlight> =
+            result.highlights.into_iter().map(|highlight| (highlight.product_id, highlight)).collect();
+        let items = result
+            .products
+            .into_iter()
+            .map(|product| {
+                let highlight = highlights.remove(&product.id().value());
+                ProductResponse { highlight: highlight.map(HighlightResponse::from), ..product.into() }
+            })
+            .collect();
+        let facets = FacetsResponse::new(facets, &product_query.tags, &product_query.attributes);
+        Ok(PaginatedResponse::new(items, result.total, product_query.offset, product_query.limit).with_facets(facets))
+    }
+
+    /// Translate a search DTO into a validated repository query
+    fn build_query(query: &SearchProductsQuery) -> Result<ProductQuery, ApplicationError> {
+        let per_page = query.limit.or(query.per_page).unwrap_or(DEFAULT_PAGE_SIZE);
+        if per_page == 0 || per_page > MAX_PAGE_SIZE {
+            return Err(ApplicationError::validation(format!(
+                "Page size must be between 1 and {}",
+                MAX_PAGE_SIZE
+            )));
+        }
+
+        let offset = match (query.offset, query.page) {
+            (Some(offset), _) => Some(offset),
+            (None, Some(0)) => return Err(ApplicationError::validation("Page numbers start at 1")),
+            (None, Some(page)) => (page - 1).checked_mul(per_page),
+            (None, None) => Some(0),
+        };
+        // The database takes a signed 64-bit offset
+        let offset = offset
+            .filter(|offset| i64::try_from(*offset).is_ok())
+            .ok_or_else(|| ApplicationError::validation("Page is out of range"))?;
+
+        let searching = query.query.as_deref().is_some_and(|text| !text.trim().is_empty());
+        let sort_by = match query.sort.as_deref() {
+            None if searching => ProductSortField::Relevance,
+            Some("relevance") => ProductSortField::Relevance,
+            None | Some("created_at") => ProductSortField::CreatedAt,
+            Some("name") => ProductSortField::Name,
+            Some("price") => ProductSortField::Price,
+            Some("stock") => ProductSortField::Stock,
+            Some(other) => {
+                return Err(ApplicationError::validation(format!(
+                    "Unknown sort field '{}', expected relevance, created_at, name, price or stock",
+                    other
+                )))
             }
-            _ => {
-                self.repository.find_all().await
-                    .map_err(ApplicationError::RepositoryError)?
+        };
+
+        let direction = match query.order.as_deref() {
+            None | Some("desc") => SortDirection::Desc,
+            Some("asc") => SortDirection::Asc,
+            Some(other) => {
+                return Err(ApplicationError::validation(format!(
+                    "Unknown sort order '{}', expected asc or desc",
+                    other
+                )))
             }
         };
 
-        Ok(products.into_iter().map(ProductResponse::from).collect())
+        let price = if query.min_price.is_some() || query.max_price.is_some() {
+            let currency = match query.currency.as_deref() {
+                Some(code) => Currency::new(code).map_err(ApplicationError::DomainError)?,
+                None => Currency::default(),
+            };
+            let scale = 10f64.powi(currency.minor_units() as i32);
+            let to_minor = |value: f64| {
+                if !value.is_finite() || value < 0.0 {
+                    return Err(ApplicationError::validation("Price bounds must be non-negative numbers"));
+                }
+                Ok((value * scale).round() as i64)
+            };
+            Some(PriceRange {
+                currency,
+                min_minor: query.min_price.map(to_minor).transpose()?,
+                max_minor: query.max_price.map(to_minor).transpose()?,
+            })
+        } else {
+            None
+        };
+
+        // Facet values are compared in the form they are stored in
+        let tags = query
+            .tags
+            .iter()
+            .map(|tag| Tag::new(tag).map(|tag| tag.value().to_string()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| ApplicationError::validation(err.to_string()))?;
+        let mut attributes: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (key, values) in &query.attributes {
+            for value in values {
+                let attribute = ProductAttribute::new(key, value)
+                    .map_err(|err| ApplicationError::validation(err.to_string()))?;
+                attributes.entry(attribute.key().to_string()).or_default().push(attribute.value().to_string());
+            }
+        }
+
+        Ok(ProductQuery {
+            search: query.query.clone().filter(|s| !s.trim().is_empty()),
+            category: query.category.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string),
+            tags,
+            attributes,
+            price,
+            in_stock_only: query.in_stock.unwrap_or(false),
+            created_after: query.created_after,
+            sort_by,
+            direction,
+            offset,
+            limit: per_page,
+        })
     }
 
     /// Check if product exists
@@ -163,28 +363,45 @@ impl ProductService {
 pub enum ApplicationError {
     #[error("Product not found")]
     ProductNotFound,
+    #[error("Webhook not found")]
+    WebhookNotFound,
+    #[error("Category not found")]
+    CategoryNotFound,
+    #[error("Reservation not found")]
+    ReservationNotFound,
+    #[error("Product is not in the cart")]
+    CartItemNotFound,
+    #[error("Order not found")]
+    OrderNotFound,
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Invalid email or password")]
+    InvalidCredentials,
+    #[error("API key not found")]
+    ApiKeyNotFound,
+    #[error("Invalid or revoked API key")]
+    InvalidApiKey,
+    #[error("Rate limit exceeded; retry in {retry_after_secs} seconds")]
+    RateLimited { retry_after_secs: u64 },
     #[error("Domain error: {0}")]
     DomainError(#[from] DomainError),
     #[error("Repository error: {0}")]
     RepositoryError(#[from] RepositoryError),
-    #[allow(dead_code)]
     #[error("Validation error: {0}")]
     ValidationError(String),
-    #[allow(dead_code)]
+    #[error("Invalid fields: {}", describe(.0))]
+    InvalidFields(Vec<FieldError>),
     #[error("Authorization error: {0}")]
     AuthorizationError(String),
-    #[allow(dead_code)]
     #[error("Internal error: {0}")]
     InternalError(String),
 }
 
 impl ApplicationError {
-    #[allow(dead_code)]
     pub fn validation(message: impl Into<String>) -> Self {
         Self::ValidationError(message.into())
     }
 
-    #[allow(dead_code)]
     pub fn authorization(message: impl Into<String>) -> Self {
         Self::AuthorizationError(message.into())
     }
@@ -193,4 +410,261 @@ impl ApplicationError {
     pub fn internal(message: impl Into<String>) -> Self {
         Self::InternalError(message.into())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sqlx::SqlitePool;
+
+    use crate::application::{CreateReservationRequest, InventoryService, ProductResponse, UserResponse};
+    use crate::infrastructure::{migrated_pool, SqliteProductRepository};
+
+    struct Catalog {
+        products: ProductService,
+        inventory: InventoryService,
+        pool: SqlitePool,
+    }
+
+    async fn catalog() -> Catalog {
+        let pool = migrated_pool().await;
+        let repository = Arc::new(SqliteProductRepository::new(pool.clone()));
+        repository.initialize().await.expect("seed data");
+        Catalog {
+            products: ProductService::new(repository.clone()),
+            inventory: InventoryService::new(repository.clone(), repository),
+            pool,
+        }
+    }
+
+    fn signed_in(role: &str) -> Actor {
+        Actor::user(&UserResponse {
+            id: 1,
+            email: format!("{}@example.com", role),
+            name: role.to_string(),
+            role: role.to_string(),
+            created_at: Utc::now(),
+        })
+    }
+
+    fn desk() -> CreateProductRequest {
+        CreateProductRequest {
+            name: "Standing Desk".to_string(),
+            description: None,
+            price: 499.0,
+            currency: None,
+            stock: 4,
+            tags: Vec::new(),
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    fn reprice(price: f64) -> UpdateProductRequest {
+        UpdateProductRequest {
+            name: None,
+            description: None,
+            price: Some(price),
+            currency: None,
+            stock: None,
+            version: None,
+            tags: None,
+            attributes: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn catalog_changes_follow_the_role_policy() {
+        let Catalog { products, .. } = catalog().await;
+        let (admin, editor, viewer) = (signed_in("admin"), signed_in("catalog_editor"), signed_in("viewer"));
+        let refused = |result: Result<_, ApplicationError>| matches!(result, Err(ApplicationError::AuthorizationError(_)));
+        let before = products.get_product_by_id(1).await.unwrap();
+
+        for actor in [&viewer, &Actor::anonymous("api")] {
+            assert!(refused(products.create_product(desk(), actor).await.map(|_| ())));
+            assert!(refused(products.update_product(1, reprice(1.0), actor).await.map(|_| ())));
+            assert!(refused(products.delete_product(1, None, actor).await.map(|_| ())));
+        }
+
+        let created = products.create_product(desk(), &editor).await.unwrap();
+        products.update_product(created.id, reprice(450.0), &editor).await.unwrap();
+        assert!(refused(products.delete_product(created.id, None, &editor).await.map(|_| ())));
+
+        assert!(products.delete_product(created.id, None, &admin).await.unwrap());
+        // Refused writes left nothing behind
+        let after = products.get_product_by_id(1).await.unwrap();
+        assert_eq!((after.price_minor, after.version), (before.price_minor, before.version));
+    }
+
+    fn invalid_fields(result: Result<ProductResponse, ApplicationError>) -> Vec<(String, &'static str)> {
+        match result {
+            Err(ApplicationError::InvalidFields(errors)) => {
+                errors.into_iter().map(|error| (error.field, error.code)).collect()
+            }
+            other => panic!("expected field errors, got {:?}", other),
+        }
+    }
+
+    fn fields(errors: &[(&str, &'static str)]) -> Vec<(String, &'static str)> {
+        errors.iter().map(|(field, code)| (field.to_string(), *code)).collect()
+    }
+
+    #[tokio::test]
+    async fn product_input_is_checked_field_by_field_in_one_pass() {
+        let Catalog { products, inventory, .. } = catalog().await;
+        let admin = Actor::system("test");
+        let before = products.get_product_by_id(1).await.unwrap();
+
+        let everything_wrong = CreateProductRequest {
+            name: " ".to_string(),
+            description: None,
+            price: -1.0,
+            currency: Some("dollars".to_string()),
+            stock: -5,
+            tags: Vec::new(),
+            attributes: BTreeMap::new(),
+        };
+        assert_eq!(
+            invalid_fields(products.create_product(everything_wrong, &admin).await),
+            fields(&[("name", "required"), ("currency", "invalid"), ("price", "negative"), ("stock", "negative")])
+        );
+        let too_big = CreateProductRequest { name: "x".repeat(256), price: 1e12, ..desk() };
+        assert_eq!(
+            invalid_fields(products.create_product(too_big, &admin).await),
+            fields(&[("name", "too_long"), ("price", "too_high")])
+        );
+        // The limit depends on the currency's minor unit
+        let yen = CreateProductRequest { price: 5_000_000.0, currency: Some("JPY".to_string()), ..desk() };
+        assert!(products.create_product(yen, &admin).await.is_ok());
+
+        let update = UpdateProductRequest { name: Some(String::new()), stock: Some(-1), ..reprice(-3.0) };
+        assert_eq!(
+            invalid_fields(products.update_product(1, update, &admin).await),
+            fields(&[("name", "required"), ("price", "negative"), ("stock", "negative")])
+        );
+        let hold = CreateReservationRequest { quantity: 4, ttl_seconds: None };
+        let reservation = inventory.reserve_stock(1, hold, &Actor::system("test")).await.unwrap();
+        let below_reserved = UpdateProductRequest { stock: Some(3), ..reprice(1.0) };
+        assert_eq!(
+            invalid_fields(products.update_product(1, below_reserved, &admin).await),
+            fields(&[("stock", "below_reserved")])
+        );
+        inventory.release_reservation(reservation.id, &Actor::system("test")).await.unwrap();
+        // A new currency needs a new price; the current one is kept
+        let euros = UpdateProductRequest { price: None, currency: Some("EUR".to_string()), ..reprice(0.0) };
+        assert_eq!(invalid_fields(products.update_product(1, euros, &admin).await), fields(&[("price", "required")]));
+        let same = UpdateProductRequest { price: None, currency: Some(before.currency.clone()), ..reprice(0.0) };
+        assert!(products.update_product(1, same, &admin).await.is_ok());
+        // Nothing was written
+        let after = products.get_product_by_id(1).await.unwrap();
+        assert_eq!((&after.name, after.price_minor, after.stock), (&before.name, before.price_minor, before.stock));
+    }
+
+    #[tokio::test]
+    async fn pages_report_what_is_left_from_any_offset() {
+        let Catalog { products, .. } = catalog().await;
+        let listing = |page: Option<usize>, offset: Option<usize>| SearchProductsQuery {
+            page,
+            offset,
+            per_page: Some(2),
+            ..SearchProductsQuery::default()
+        };
+        let shape = |page: &PaginatedResponse<ProductResponse>| (page.items.len(), page.page, page.has_prev, page.has_next);
+
+        // Five seeded products, two to a page
+        assert_eq!(shape(&products.search_products(listing(Some(2), None)).await.unwrap()), (2, 2, true, true));
+        assert_eq!(shape(&products.search_products(listing(Some(3), None)).await.unwrap()), (1, 3, true, false));
+        // The last two rows, although offset 3 starts inside page 2
+        assert_eq!(shape(&products.search_products(listing(None, Some(3))).await.unwrap()), (2, 2, true, false));
+        assert_eq!(shape(&products.search_products(listing(None, Some(1))).await.unwrap()), (2, 1, true, true));
+
+        for (page, offset) in [(Some(usize::MAX), None), (Some(usize::MAX / 2), None), (None, Some(usize::MAX))] {
+            assert!(matches!(
+                products.search_products(listing(page, offset)).await,
+                Err(ApplicationError::ValidationError(_))
+            ));
+        }
+    }
+
+    /// The fields named by the latest `product.updated` event
+    async fn last_changes(pool: &SqlitePool) -> serde_json::Value {
+        let payload: String = sqlx::query_scalar(
+            "SELECT payload FROM outbox WHERE event_type = 'product.updated' ORDER BY id DESC LIMIT 1"
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        serde_json::from_str::<serde_json::Value>(&payload).unwrap()["changes"].take()
+    }
+
+    #[tokio::test]
+    async fn updates_change_only_the_fields_sent() {
+        let Catalog { products, pool, .. } = catalog().await;
+        let admin = Actor::system("test");
+        let update = |body: serde_json::Value| -> UpdateProductRequest { serde_json::from_value(body).unwrap() };
+        let before = products.get_product_by_id(1).await.unwrap();
+        assert!(before.description.is_some());
+
+        // Sending the current name is not a change, and leaving the description out keeps it
+        let body = serde_json::json!({ "name": before.name, "price": 1999.0 });
+        let repriced = products.update_product(1, update(body), &admin).await.unwrap();
+        assert_eq!(repriced.description, before.description);
+        assert_eq!(last_changes(&pool).await, serde_json::json!(["price"]));
+
+        let body = serde_json::json!({ "description": null, "stock": before.stock });
+        let cleared = products.update_product(1, update(body), &admin).await.unwrap();
+        assert_eq!(cleared.description, None);
+        assert_eq!(last_changes(&pool).await, serde_json::json!(["description"]));
+    }
+
+    #[tokio::test]
+    async fn product_tags_and_attributes_are_normalized_validated_and_reported() {
+        let Catalog { products, pool, .. } = catalog().await;
+        let admin = Actor::system("test");
+
+        let created = products.create_product(CreateProductRequest {
+            tags: vec![" Standing  Desk ".to_string(), "office".to_string(), "OFFICE".to_string()],
+            attributes: BTreeMap::from([("Top Material".to_string(), " Oak ".to_string())]),
+            ..desk()
+        }, &admin).await.unwrap();
+        assert_eq!(created.tags, vec!["office", "standing desk"]);
+        assert_eq!(created.attributes, BTreeMap::from([("top_material".to_string(), "Oak".to_string())]));
+
+        let retag = UpdateProductRequest { tags: Some(vec!["ergonomic".to_string()]), price: None, ..reprice(0.0) };
+        let updated = products.update_product(created.id, retag, &admin).await.unwrap();
+        assert_eq!(updated.tags, vec!["ergonomic"]);
+        assert_eq!(updated.attributes, created.attributes, "attributes left out are kept");
+        assert_eq!(last_changes(&pool).await, serde_json::json!(["tags"]));
+
+        let invalid = CreateProductRequest {
+            tags: vec!["a,b".to_string()],
+            attributes: BTreeMap::from([("colour!".to_string(), "red".to_string())]),
+            ..desk()
+        };
+        assert_eq!(
+            invalid_fields(products.create_product(invalid, &admin).await),
+            fields(&[("tags", "invalid"), ("attributes", "invalid")])
+        );
+
+        let mut listing = SearchProductsQuery::default().with_facet_filters(vec![
+            ("tag".to_string(), "Ergonomic".to_string()),
+            ("attr.top_material".to_string(), "Oak".to_string()),
+            ("attr.color".to_string(), "Red".to_string()),
+        ]);
+        let page = products.search_products(listing).await.unwrap();
+        assert!(page.items.is_empty(), "no product is red");
+        let facets = page.facets.unwrap();
+        let color = facets.attributes.iter().find(|facet| facet.key == "color").unwrap();
+        let chosen: Vec<(&str, usize, bool)> = color.values.iter().map(|v| (v.value.as_str(), v.count, v.selected)).collect();
+        // The chosen colour stays listed so it can be unticked
+        assert_eq!(chosen, vec![("Red", 0, true)]);
+
+        listing = SearchProductsQuery::default().with_facet_filters(vec![("tag".to_string(), "ergonomic".to_string())]);
+        let page = products.search_products(listing).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert!(page.facets.unwrap().tags.iter().any(|tag| tag.value == "ergonomic" && tag.selected && tag.count == 1));
+
+        let bad_filter = SearchProductsQuery::default().with_facet_filters(vec![("attr.a b!".to_string(), "x".to_string())]);
+        assert!(matches!(products.search_products(bad_filter).await, Err(ApplicationError::ValidationError(_))));
+    }
+}
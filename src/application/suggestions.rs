@@ -0,0 +1,360 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+
+use crate::application::dtos::{ProductSuggestion, SuggestQuery, SuggestionsResponse};
+use crate::application::events::EventHandler;
+use crate::application::services::ApplicationError;
+use crate::domain::{Product, ProductEvent, ProductRepository};
+
+/// Suggestions of each kind returned when the client does not ask for a number
+pub const DEFAULT_SUGGESTIONS: usize = 8;
+/// Most suggestions of each kind a client may ask for
+pub const MAX_SUGGESTIONS: usize = 20;
+/// Longest query, in characters, that is looked up; every unknown word is scored against
+/// the whole vocabulary while the index is locked, so the work has to stay bounded
+pub const MAX_QUERY_CHARS: usize = 100;
+/// Most words a query may have
+pub const MAX_QUERY_WORDS: usize = 8;
+
+/// A product as the suggestion index sees it
+struct IndexedProduct {
+    id: i64,
+    name: String,
+    /// Lowercased name, for ranking
+    folded: String,
+    /// Words of the name and tags
+    words: Vec<String>,
+}
+
+impl IndexedProduct {
+    fn suggestion(&self) -> ProductSuggestion {
+        ProductSuggestion { id: self.id, name: self.name.clone() }
+    }
+}
+
+/// Product names and tags split into words, with how many products use each word
+#[derive(Default)]
+struct SuggestionIndex {
+    products: Vec<IndexedProduct>,
+    vocabulary: HashMap<String, usize>,
+}
+
+impl SuggestionIndex {
+    fn build(products: &[Product]) -> Self {
+        let mut index = Self::default();
+        for product in products {
+            let name = product.name().value().to_string();
+            let mut words = words(&name);
+            words.extend(product.tags().iter().flat_map(|tag| self::words(tag.value())));
+            words.sort();
+            words.dedup();
+            for word in &words {
+                *index.vocabulary.entry(word.clone()).or_default() += 1;
+            }
+            index.products.push(IndexedProduct {
+                id: product.id().value(),
+                folded: name.to_lowercase(),
+                name,
+                words,
+            });
+        }
+        index
+    }
+
+    /// Products in which every one of `terms` starts a word of the name or a tag, best first
+    fn matching(&self, terms: &[String], limit: usize) -> Vec<&IndexedProduct> {
+        let mut found: Vec<&IndexedProduct> = self
+            .products
+            .iter()
+            .filter(|product| terms.iter().all(|term| product.words.iter().any(|word| word.starts_with(term))))
+            .collect();
+        // Names that start with what was typed first, then the shortest, i.e. the closest completion
+        let typed = terms.join(" ");
+        found.sort_by_key(|product| (!product.folded.starts_with(&typed), product.name.len(), product.folded.clone()));
+        found.truncate(limit);
+        found
+    }
+
+    fn suggest(&self, text: &str, limit: usize) -> SuggestionsResponse {
+        let text = text.trim();
+        let terms = words(text);
+        let mut response = SuggestionsResponse {
+            query: text.to_string(),
+            completions: Vec::new(),
+            did_you_mean: None,
+            corrections: Vec::new(),
+        };
+        if terms.is_empty() {
+            return response;
+        }
+
+        response.completions = self.matching(&terms, limit).into_iter().map(IndexedProduct::suggestion).collect();
+        let corrected: Vec<String> = terms
+            .iter()
+            .map(|term| {
+                if self.is_known(term) {
+                    term.clone()
+                } else {
+                    self.closest(term).unwrap_or(term).to_string()
+                }
+            })
+            .collect();
+        if corrected != terms {
+            response.corrections = self.matching(&corrected, limit).into_iter().map(IndexedProduct::suggestion).collect();
+            response.did_you_mean = Some(corrected.join(" "));
+        }
+        response
+    }
+
+    fn is_known(&self, term: &str) -> bool {
+        self.vocabulary.keys().any(|word| word.starts_with(term))
+    }
+
+    /// The word `term` was most likely meant to start, within the typos allowed for its length
+    fn closest(&self, term: &str) -> Option<&str> {
+        let typed: Vec<char> = term.chars().collect();
+        let allowed = allowed_typos(typed.len());
+        if allowed == 0 {
+            return None;
+        }
+        self.vocabulary
+            .iter()
+            .map(|(word, uses)| {
+                let word_chars: Vec<char> = word.chars().collect();
+                (prefix_distance(&typed, &word_chars), Reverse(*uses), word.len(), word.as_str())
+            })
+            .filter(|(distance, ..)| *distance <= allowed)
+            .min()
+            .map(|(.., word)| word)
+    }
+}
+
+/// Application service for search-as-you-type suggestions.
+///
+/// The index lives in memory. It is built at startup and rebuilt whenever the event bus
+/// reports that a product was added, renamed, retagged or removed, so it may trail a write by one
+/// outbox relay cycle.
+pub struct SuggestionService {
+    repository: Arc<dyn ProductRepository>,
+    index: RwLock<SuggestionIndex>,
+}
+
+impl SuggestionService {
+    pub fn new(repository: Arc<dyn ProductRepository>) -> Self {
+        Self { repository, index: RwLock::new(SuggestionIndex::default()) }
+    }
+
+    /// Rebuild the index from the catalog
+    pub async fn refresh(&self) -> Result<(), ApplicationError> {
+        let products = self.repository.find_all().await.map_err(ApplicationError::RepositoryError)?;
+        let index = SuggestionIndex::build(&products);
+        *self.index.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = index;
+        Ok(())
+    }
+
+    /// Products whose name or tag words start with the typed words, and, when a word matches
+    /// nothing, the query with that word corrected and the products it finds
+    pub fn suggest(&self, query: &SuggestQuery) -> Result<SuggestionsResponse, ApplicationError> {
+        let limit = query.limit.unwrap_or(DEFAULT_SUGGESTIONS);
+        if limit == 0 || limit > MAX_SUGGESTIONS {
+            return Err(ApplicationError::validation(format!(
+                "limit must be between 1 and {}",
+                MAX_SUGGESTIONS
+            )));
+        }
+        let text = query.q.as_deref().unwrap_or_default();
+        if text.chars().count() > MAX_QUERY_CHARS || words(text).len() > MAX_QUERY_WORDS {
+            return Err(ApplicationError::validation(format!(
+                "q must be at most {} characters and {} words",
+                MAX_QUERY_CHARS, MAX_QUERY_WORDS
+            )));
+        }
+        let index = self.index.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Ok(index.suggest(text, limit))
+    }
+}
+
+#[async_trait]
+impl EventHandler for SuggestionService {
+    fn name(&self) -> &'static str {
+        "suggestions"
+    }
+
+    async fn handle(&self, event: &ProductEvent) -> Result<(), ApplicationError> {
+        match event {
+            ProductEvent::ProductCreated { .. } | ProductEvent::ProductDeleted { .. } => self.refresh().await,
+            ProductEvent::ProductUpdated { changes, .. }
+                if changes.iter().any(|change| change == "name" || change == "tags") =>
+            {
+                self.refresh().await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Lowercased runs of letters and digits
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Typos forgiven in a word of `length` characters; short words are too ambiguous to correct
+fn allowed_typos(length: usize) -> usize {
+    match length {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// Fewest single-character insertions, deletions, substitutions or swaps of neighbours
+/// that turn `typed` into some prefix of `word`, so a word still being typed is not
+/// charged for its missing end
+fn prefix_distance(typed: &[char], word: &[char]) -> usize {
+    let mut rows = vec![(0..=word.len()).collect::<Vec<_>>()];
+    for (i, &t) in typed.iter().enumerate() {
+        let mut row = vec![i + 1; word.len() + 1];
+        for (j, &w) in word.iter().enumerate() {
+            let above = &rows[i];
+            let mut best = (above[j + 1] + 1).min(row[j] + 1).min(above[j] + usize::from(t != w));
+            if i > 0 && j > 0 && t == word[j - 1] && typed[i - 1] == w {
+                best = best.min(rows[i - 1][j - 1] + 1);
+            }
+            row[j + 1] = best;
+        }
+        rows.push(row);
+    }
+    rows.last().and_then(|row| row.iter().copied().min()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::domain::{Currency, Money, ProductId, ProductName, StockQuantity, Tag};
+    use crate::infrastructure::{migrated_pool, SqliteProductRepository};
+
+    fn product(id: i64, name: &str) -> Product {
+        Product::reconstitute(
+            ProductId::new(id).unwrap(),
+            ProductName::new(name.to_string()).unwrap(),
+            None,
+            Money::new(100, Currency::USD).unwrap(),
+            StockQuantity::new(1).unwrap(),
+            StockQuantity::new(0).unwrap(),
+            Utc::now(),
+            Utc::now(),
+            1,
+        )
+    }
+
+    fn catalog(names: &[&str]) -> SuggestionIndex {
+        let products: Vec<Product> = names.iter().enumerate().map(|(i, name)| product(i as i64 + 1, name)).collect();
+        SuggestionIndex::build(&products)
+    }
+
+    fn suggest(index: SuggestionIndex, q: &str) -> SuggestionsResponse {
+        index.suggest(q, DEFAULT_SUGGESTIONS)
+    }
+
+    fn names(suggestions: &[ProductSuggestion]) -> Vec<&str> {
+        suggestions.iter().map(|suggestion| suggestion.name.as_str()).collect()
+    }
+
+    const NAMES: &[&str] = &["MacBook Pro 16\"", "MacBook Air", "iPhone 15 Pro", "Magic Mouse", "iPad Air"];
+
+    #[test]
+    fn words_being_typed_complete_to_product_names() {
+        let found = suggest(catalog(NAMES), "mac");
+        assert_eq!(names(&found.completions), vec!["MacBook Air", "MacBook Pro 16\""]);
+        assert_eq!(found.did_you_mean, None);
+
+        assert_eq!(names(&suggest(catalog(NAMES), "air mac").completions), vec!["MacBook Air"]);
+        assert!(suggest(catalog(NAMES), "  ").completions.is_empty());
+    }
+
+    #[test]
+    fn misspelt_words_are_corrected_by_edit_distance() {
+        let found = suggest(catalog(NAMES), "macbok pro");
+        assert!(found.completions.is_empty());
+        assert_eq!(found.did_you_mean.as_deref(), Some("macbook pro"));
+        assert_eq!(names(&found.corrections), vec!["MacBook Pro 16\""]);
+
+        // A swapped pair of letters counts as one typo, even halfway through a word
+        assert_eq!(suggest(catalog(NAMES), "ipohn").did_you_mean.as_deref(), Some("iphone"));
+        // Too far from anything, or too short to guess
+        assert_eq!(suggest(catalog(NAMES), "keyboard").did_you_mean, None);
+        assert_eq!(suggest(catalog(NAMES), "xz").did_you_mean, None);
+    }
+
+    #[test]
+    fn tags_are_searched_and_corrected_like_name_words() {
+        let tagged = |id, name, tags: &[&str]| {
+            product(id, name).with_details(tags.iter().map(|tag| Tag::new(tag).unwrap()).collect(), Vec::new())
+        };
+        let index = SuggestionIndex::build(&[
+            tagged(1, "AirPods Pro", &["wireless", "noise cancelling"]),
+            tagged(2, "EarPods", &["wired"]),
+        ]);
+
+        assert_eq!(names(&index.suggest("noise", DEFAULT_SUGGESTIONS).completions), vec!["AirPods Pro"]);
+        assert_eq!(names(&index.suggest("wire", DEFAULT_SUGGESTIONS).completions), vec!["EarPods", "AirPods Pro"]);
+        assert_eq!(index.suggest("wirless", DEFAULT_SUGGESTIONS).did_you_mean.as_deref(), Some("wireless"));
+    }
+
+    #[test]
+    fn prefix_distance_ignores_the_untyped_end_of_a_word() {
+        let distance = |typed: &str, word: &str| {
+            prefix_distance(&typed.chars().collect::<Vec<_>>(), &word.chars().collect::<Vec<_>>())
+        };
+        assert_eq!(distance("mac", "macbook"), 0);
+        assert_eq!(distance("mcb", "macbook"), 1);
+        assert_eq!(distance("amc", "macbook"), 1);
+        assert_eq!(distance("macbok", "macbook"), 1);
+        assert!(distance("watch", "macbook") > 2);
+    }
+
+    #[tokio::test]
+    async fn suggestions_are_refreshed_by_product_events() {
+        let repository = Arc::new(SqliteProductRepository::new(migrated_pool().await));
+        repository.initialize().await.expect("seed data");
+        let suggestions = SuggestionService::new(repository.clone());
+        suggestions.refresh().await.unwrap();
+        let suggest = |q: &str| SuggestQuery { q: Some(q.to_string()), limit: None };
+        assert_eq!(suggestions.suggest(&suggest("mechanical")).unwrap().completions.len(), 0);
+
+        let saved = repository.save(product(1, "Mechanical Keyboard"), &[]).await.unwrap();
+        let event = ProductEvent::ProductCreated {
+            product_id: saved.id().clone(),
+            name: saved.name().clone(),
+            price: saved.price().clone(),
+        };
+        suggestions.handle(&event).await.unwrap();
+
+        let found = suggestions.suggest(&suggest("mechanical")).unwrap();
+        assert_eq!(found.completions[0].name, "Mechanical Keyboard");
+        let found = suggestions.suggest(&suggest("keybaord")).unwrap();
+        assert_eq!(found.did_you_mean.as_deref(), Some("keyboard"));
+        assert!(matches!(
+            suggestions.suggest(&SuggestQuery { q: None, limit: Some(0) }),
+            Err(ApplicationError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn long_queries_are_rejected_before_they_are_scored() {
+        let suggestions = SuggestionService::new(Arc::new(SqliteProductRepository::new(migrated_pool().await)));
+        let suggest = |q: String| suggestions.suggest(&SuggestQuery { q: Some(q), limit: None });
+
+        assert!(suggest("a ".repeat(MAX_QUERY_WORDS)).is_ok());
+        assert!(matches!(suggest("a ".repeat(MAX_QUERY_WORDS + 1)), Err(ApplicationError::ValidationError(_))));
+        assert!(suggest("x".repeat(MAX_QUERY_CHARS)).is_ok());
+        assert!(matches!(suggest("x".repeat(MAX_QUERY_CHARS + 1)), Err(ApplicationError::ValidationError(_))));
+    }
+}
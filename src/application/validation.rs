@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::application::dtos::{CreateProductRequest, UpdateProductRequest};
+use crate::application::services::ApplicationError;
+use crate::domain::{
+    normalize_attributes, normalize_tags, Currency, DomainError, Money, ProductAttribute, ProductName, StockQuantity,
+    Tag,
+};
+
+/// A problem with one field of a request.
+///
+/// `code` is stable and meant for clients to branch on; `message` is for people.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// Collects field errors so a whole request can be checked in one pass
+#[derive(Debug, Default)]
+pub struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The value if `result` is valid; otherwise the refusal is recorded against `field`
+    pub fn check<T>(&mut self, field: &str, result: Result<T, DomainError>) -> Option<T> {
+        result.map_err(|err| self.add(field, code(&err), reason(err))).ok()
+    }
+
+    pub fn add(&mut self, field: &str, code: &'static str, message: impl Into<String>) {
+        self.0.push(FieldError { field: field.to_string(), code, message: message.into() });
+    }
+
+    /// Fails with every collected error, if there are any
+    pub fn into_result(self) -> Result<(), ApplicationError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(self.into_error())
+        }
+    }
+
+    /// `ApplicationError::InvalidFields` with everything collected so far
+    pub fn into_error(self) -> ApplicationError {
+        ApplicationError::InvalidFields(self.0)
+    }
+
+    /// A single error against `field`
+    pub fn only(field: &str, code: &'static str, message: impl Into<String>) -> ApplicationError {
+        let mut errors = Self::new();
+        errors.add(field, code, message);
+        errors.into_error()
+    }
+}
+
+/// One line per field, for logs and plain-text messages
+pub fn describe(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("{}: {}", error.field, error.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// ============================================================================
+// Product Rules
+// ============================================================================
+//
+// Every field is run through its value object so the limits live in one place;
+// the refusals are collected rather than returned so all problems surface at once.
+
+/// Check every field of a new product
+pub fn validate_create_product(request: &CreateProductRequest) -> Result<(), ApplicationError> {
+    let mut errors = FieldErrors::new();
+    errors.check("name", ProductName::new(request.name.clone()));
+    let currency = match request.currency.as_deref() {
+        Some(code) => errors.check("currency", Currency::new(code)),
+        None => Some(Currency::default()),
+    };
+    errors.check("price", Money::from_decimal(request.price, currency.unwrap_or_default()));
+    errors.check("stock", StockQuantity::new(request.stock));
+    errors.check("tags", parse_tags(&request.tags));
+    errors.check("attributes", parse_attributes(&request.attributes));
+    errors.into_result()
+}
+
+/// Check every supplied field of a product update; `current_price` is the product's price now
+pub fn validate_update_product(request: &UpdateProductRequest, current_price: &Money) -> Result<(), ApplicationError> {
+    let mut errors = FieldErrors::new();
+    if let Some(name) = &request.name {
+        errors.check("name", ProductName::new(name.clone()));
+    }
+    let new_currency = request.currency.as_deref().map(|code| errors.check("currency", Currency::new(code)));
+    match (request.price, new_currency) {
+        (Some(price), currency) => {
+            errors.check("price", Money::from_decimal(price, currency.flatten().unwrap_or(current_price.currency())));
+        }
+        // An amount means something else in another currency, so it cannot be carried over
+        (None, Some(Some(currency))) if currency != current_price.currency() => errors.add(
+            "price",
+            "required",
+            format!("Give a price in {} when changing the currency", currency.code()),
+        ),
+        _ => {}
+    }
+    if let Some(stock) = request.stock {
+        errors.check("stock", StockQuantity::new(stock));
+    }
+    if let Some(tags) = &request.tags {
+        errors.check("tags", parse_tags(tags));
+    }
+    if let Some(attributes) = &request.attributes {
+        errors.check("attributes", parse_attributes(attributes));
+    }
+    errors.into_result()
+}
+
+/// The stable code a value object's refusal is reported with
+fn code(err: &DomainError) -> &'static str {
+    match err {
+        DomainError::EmptyProductName => "required",
+        DomainError::ProductNameTooLong(_) => "too_long",
+        DomainError::PriceNotANumber => "not_a_number",
+        DomainError::NegativePrice | DomainError::NegativeStock => "negative",
+        DomainError::PriceTooHigh(_) => "too_high",
+        _ => "invalid",
+    }
+}
+
+/// What to tell people, without the variant's "Invalid ...:" prefix
+fn reason(err: DomainError) -> String {
+    match err {
+        DomainError::InvalidCurrency(message)
+        | DomainError::InvalidTag(message)
+        | DomainError::InvalidAttribute(message) => message,
+        other => other.to_string(),
+    }
+}
+
+/// The tags of a product request, or the first reason they cannot be used
+pub fn parse_tags(tags: &[String]) -> Result<Vec<Tag>, DomainError> {
+    normalize_tags(tags.iter().map(|tag| Tag::new(tag)).collect::<Result<_, _>>()?)
+}
+
+/// The attributes of a product request, or the first reason they cannot be used
+pub fn parse_attributes(attributes: &BTreeMap<String, String>) -> Result<Vec<ProductAttribute>, DomainError> {
+    normalize_attributes(
+        attributes
+            .iter()
+            .map(|(key, value)| ProductAttribute::new(key, value))
+            .collect::<Result<_, _>>()?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::MAX_PRODUCT_NAME_LENGTH;
+
+    fn create(fields: serde_json::Value) -> CreateProductRequest {
+        let mut request = serde_json::json!({ "name": "Widget", "price": 9.99, "stock": 3 });
+        request.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+        serde_json::from_value(request).unwrap()
+    }
+
+    fn refusals(result: Result<(), ApplicationError>) -> Vec<(String, &'static str, String)> {
+        match result {
+            Ok(()) => Vec::new(),
+            Err(ApplicationError::InvalidFields(errors)) => {
+                errors.into_iter().map(|error| (error.field, error.code, error.message)).collect()
+            }
+            Err(other) => panic!("expected field errors, got {:?}", other),
+        }
+    }
+
+    fn codes(result: Result<(), ApplicationError>) -> Vec<(String, &'static str)> {
+        refusals(result).into_iter().map(|(field, code, _)| (field, code)).collect()
+    }
+
+    #[test]
+    fn each_value_object_refusal_has_its_own_code() {
+        let cases = [
+            (serde_json::json!({ "name": "  " }), ("name", "required")),
+            (serde_json::json!({ "name": "x".repeat(MAX_PRODUCT_NAME_LENGTH + 1) }), ("name", "too_long")),
+            (serde_json::json!({ "currency": "EURO" }), ("currency", "invalid")),
+            (serde_json::json!({ "price": -0.01 }), ("price", "negative")),
+            (serde_json::json!({ "price": 1e12 }), ("price", "too_high")),
+            (serde_json::json!({ "stock": -1 }), ("stock", "negative")),
+            (serde_json::json!({ "tags": [""] }), ("tags", "invalid")),
+            (serde_json::json!({ "attributes": { "": "x" } }), ("attributes", "invalid")),
+        ];
+        for (fields, (field, code)) in cases {
+            let request = create(fields.clone());
+            assert_eq!(codes(validate_create_product(&request)), vec![(field.to_string(), code)], "{}", fields);
+        }
+        assert!(codes(validate_create_product(&create(serde_json::json!({})))).is_empty());
+
+        // JSON has no NaN, but a form field can still send "NaN"
+        let mut errors = FieldErrors::new();
+        assert!(errors.check("price", Money::from_decimal(f64::NAN, Currency::USD)).is_none());
+        assert_eq!(codes(errors.into_result()), vec![("price".to_string(), "not_a_number")]);
+    }
+
+    #[test]
+    fn the_limits_are_the_value_objects_own() {
+        let longest = create(serde_json::json!({ "name": "x".repeat(MAX_PRODUCT_NAME_LENGTH) }));
+        assert!(validate_create_product(&longest).is_ok());
+        assert!(ProductName::new(longest.name).is_ok());
+
+        let max = Money::new(Money::MAX_PRICE_MINOR, Currency::USD).unwrap();
+        let at_max = create(serde_json::json!({ "price": Money::MAX_PRICE_MINOR as f64 / 100.0 }));
+        assert!(validate_create_product(&at_max).is_ok());
+
+        let over = create(serde_json::json!({ "price": 1e12 }));
+        let messages = refusals(validate_create_product(&over));
+        assert_eq!(messages[0].2, format!("Price cannot exceed {}", max));
+        assert_eq!(
+            messages[0].2,
+            Money::from_decimal(over.price, Currency::USD).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn every_bad_field_is_reported_in_order() {
+        let request = create(serde_json::json!({
+            "name": "",
+            "currency": "1",
+            "price": -1.0,
+            "stock": -5,
+            "tags": [""],
+            "attributes": { "": "" },
+        }));
+        let found = refusals(validate_create_product(&request));
+        let fields: Vec<_> = found.iter().map(|(field, _, _)| field.as_str()).collect();
+        assert_eq!(fields, ["name", "currency", "price", "stock", "tags", "attributes"]);
+        assert_eq!(found[1].2, "'1' is not an ISO-4217 code");
+    }
+
+    #[test]
+    fn updates_only_check_what_they_send() {
+        let current = Money::new(1_000, Currency::USD).unwrap();
+        let update = |fields: serde_json::Value| -> UpdateProductRequest { serde_json::from_value(fields).unwrap() };
+
+        assert!(validate_update_product(&update(serde_json::json!({})), &current).is_ok());
+        assert_eq!(
+            codes(validate_update_product(&update(serde_json::json!({ "stock": -1, "name": "" })), &current)),
+            vec![("name".to_string(), "required"), ("stock".to_string(), "negative")]
+        );
+    }
+}
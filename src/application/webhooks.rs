@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use crate::application::dtos::{
+    CreateWebhookRequest, UpdateWebhookRequest, WebhookResponse, WebhookDeliveryResponse,
+};
+use crate::application::policy::{Actor, Permission};
+use crate::application::services::ApplicationError;
+use crate::domain::{
+    DomainError, WebhookEventType, WebhookId, WebhookRepository, WebhookSubscription,
+};
+
+/// Number of delivery log entries returned per webhook
+pub const DELIVERY_LOG_LIMIT: usize = 50;
+
+/// Application service for managing webhook subscriptions. Every operation needs
+/// `ManageWebhooks`: subscriptions receive every product event and their delivery logs
+/// show receiver responses.
+pub struct WebhookService {
+    repository: Arc<dyn WebhookRepository>,
+}
+
+impl WebhookService {
+    pub fn new(repository: Arc<dyn WebhookRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Create a subscription. The secret is echoed back only in this response.
+    pub async fn create_webhook(
+        &self,
+        request: CreateWebhookRequest,
+        actor: &Actor,
+    ) -> Result<WebhookResponse, ApplicationError> {
+        actor.authorize(Permission::ManageWebhooks)?;
+        let events = parse_event_types(&request.events)?;
+        let secret = request
+            .secret
+            .unwrap_or_else(|| format!("whsec_{}", uuid::Uuid::new_v4().simple()));
+
+        let id = self.repository.next_id().await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        let webhook = WebhookSubscription::new(id, request.url, events, secret)
+            .map_err(ApplicationError::DomainError)?;
+        let secret = webhook.secret().to_string();
+
+        let saved = self.repository.save(webhook).await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        tracing::info!(webhook_id = saved.id().value(), actor = actor.name(), "webhook created");
+
+        let mut response = WebhookResponse::from(saved);
+        response.secret = Some(secret);
+        Ok(response)
+    }
+
+    pub async fn list_webhooks(&self, actor: &Actor) -> Result<Vec<WebhookResponse>, ApplicationError> {
+        actor.authorize(Permission::ManageWebhooks)?;
+        let webhooks = self.repository.find_all().await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        Ok(webhooks.into_iter().map(WebhookResponse::from).collect())
+    }
+
+    pub async fn get_webhook(&self, id: i64, actor: &Actor) -> Result<WebhookResponse, ApplicationError> {
+        actor.authorize(Permission::ManageWebhooks)?;
+        let webhook = self.find(id).await?;
+        Ok(WebhookResponse::from(webhook))
+    }
+
+    pub async fn update_webhook(
+        &self,
+        id: i64,
+        request: UpdateWebhookRequest,
+        actor: &Actor,
+    ) -> Result<WebhookResponse, ApplicationError> {
+        actor.authorize(Permission::ManageWebhooks)?;
+        let mut webhook = self.find(id).await?;
+
+        let events = request.events.as_deref().map(parse_event_types).transpose()?;
+        webhook.update(request.url, events, request.secret, request.active)
+            .map_err(ApplicationError::DomainError)?;
+
+        let updated = self.repository.update(webhook).await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        Ok(WebhookResponse::from(updated))
+    }
+
+    pub async fn delete_webhook(&self, id: i64, actor: &Actor) -> Result<bool, ApplicationError> {
+        actor.authorize(Permission::ManageWebhooks)?;
+        let webhook_id = WebhookId::new(id).map_err(ApplicationError::DomainError)?;
+
+        let deleted = self.repository.delete(&webhook_id).await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        if !deleted {
+            return Err(ApplicationError::WebhookNotFound);
+        }
+        tracing::info!(webhook_id = id, actor = actor.name(), "webhook deleted");
+        Ok(deleted)
+    }
+
+    /// Recent deliveries for a webhook, newest first
+    pub async fn list_deliveries(
+        &self,
+        id: i64,
+        actor: &Actor,
+    ) -> Result<Vec<WebhookDeliveryResponse>, ApplicationError> {
+        actor.authorize(Permission::ManageWebhooks)?;
+        let webhook = self.find(id).await?;
+
+        let deliveries = self.repository.find_deliveries(webhook.id(), DELIVERY_LOG_LIMIT).await
+            .map_err(ApplicationError::RepositoryError)?;
+
+        Ok(deliveries.into_iter().map(WebhookDeliveryResponse::from).collect())
+    }
+
+    async fn find(&self, id: i64) -> Result<WebhookSubscription, ApplicationError> {
+        let webhook_id = WebhookId::new(id).map_err(ApplicationError::DomainError)?;
+
+        self.repository.find_by_id(&webhook_id).await
+            .map_err(ApplicationError::RepositoryError)?
+            .ok_or(ApplicationError::WebhookNotFound)
+    }
+}
+
+fn parse_event_types(names: &[String]) -> Result<Vec<WebhookEventType>, ApplicationError> {
+    names
+        .iter()
+        .map(|name| WebhookEventType::try_from(name.as_str()))
+        .collect::<Result<Vec<_>, DomainError>>()
+        .map_err(ApplicationError::DomainError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::application::UserResponse;
+    use crate::infrastructure::{migrated_pool, SqliteWebhookRepository};
+
+    #[tokio::test]
+    async fn subscriptions_are_validated_and_hide_their_secret() {
+        let pool = migrated_pool().await;
+        let service = WebhookService::new(Arc::new(SqliteWebhookRepository::new(pool)));
+        let request = |url: &str, events: &[&str]| CreateWebhookRequest {
+            url: url.to_string(),
+            events: events.iter().map(|event| event.to_string()).collect(),
+            secret: None,
+        };
+        let admin = Actor::system("test");
+
+        for invalid in [
+            request("ftp://erp.example.com/hook", &["product.created"]),
+            request("https://erp.example.com/hook", &[]),
+            request("https://erp.example.com/hook", &["product.renamed"]),
+        ] {
+            assert!(matches!(
+                service.create_webhook(invalid, &admin).await,
+                Err(ApplicationError::DomainError(DomainError::InvalidWebhook(_)))
+            ));
+        }
+
+        let created = service
+            .create_webhook(request("https://erp.example.com/hook", &["product.deleted", "product.created"]), &admin)
+            .await
+            .unwrap();
+        assert!(created.secret.as_deref().is_some_and(|secret| secret.starts_with("whsec_")));
+        assert_eq!(created.events, vec!["product.created", "product.deleted"]);
+
+        let listed = service.list_webhooks(&admin).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].secret.is_none());
+    }
+
+    #[tokio::test]
+    async fn only_admins_manage_webhooks() {
+        let pool = migrated_pool().await;
+        let service = WebhookService::new(Arc::new(SqliteWebhookRepository::new(pool)));
+        let subscription = CreateWebhookRequest {
+            url: "https://erp.example.com/hook".to_string(),
+            events: vec!["product.created".to_string()],
+            secret: None,
+        };
+        let id = service.create_webhook(subscription, &Actor::system("test")).await.unwrap().id;
+        let editor = Actor::user(&UserResponse {
+            id: 2,
+            email: "editor@example.com".to_string(),
+            name: "Editor".to_string(),
+            role: "catalog_editor".to_string(),
+            created_at: chrono::Utc::now(),
+        });
+        let refused = |result: Result<(), ApplicationError>| matches!(result, Err(ApplicationError::AuthorizationError(_)));
+        let retarget = || UpdateWebhookRequest {
+            url: Some("https://attacker.example.com/hook".to_string()),
+            events: None,
+            secret: None,
+            active: None,
+        };
+        let create = || CreateWebhookRequest {
+            url: "https://attacker.example.com/hook".to_string(),
+            events: vec!["product.created".to_string()],
+            secret: None,
+        };
+
+        for actor in [&editor, &Actor::anonymous("api")] {
+            assert!(refused(service.create_webhook(create(), actor).await.map(|_| ())));
+            assert!(refused(service.list_webhooks(actor).await.map(|_| ())));
+            assert!(refused(service.get_webhook(id, actor).await.map(|_| ())));
+            assert!(refused(service.update_webhook(id, retarget(), actor).await.map(|_| ())));
+            assert!(refused(service.delete_webhook(id, actor).await.map(|_| ())));
+            assert!(refused(service.list_deliveries(id, actor).await.map(|_| ())));
+        }
+        let kept = service.get_webhook(id, &Actor::system("test")).await.unwrap();
+        assert_eq!(kept.url, "https://erp.example.com/hook");
+    }
+}
@@ -0,0 +1,480 @@
+//! Runtime configuration.
+//!
+//! Every setting has a key such as `database.url`. Its value comes from, in rising order of
+//! precedence: the built-in default, the TOML config file, an `APP_*` environment variable
+//! and a command-line flag.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+use crate::infrastructure::{PoolConfig, WebhookConfig, MIGRATIONS_DIR};
+use crate::presentation::Environment;
+
+/// Config file read when neither `--config` nor `APP_CONFIG` names one; it may be absent
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+/// Environment variable naming the config file
+const CONFIG_FILE_VAR: &str = "APP_CONFIG";
+
+/// Every setting, in the order they are printed
+const KEYS: [&str; 14] = [
+    "env",
+    "database.url",
+    "database.max_connections",
+    "database.min_connections",
+    "database.acquire_timeout_secs",
+    "database.migrations_dir",
+    "server.listen",
+    "server.static_dir",
+    "log.level",
+    "features.webhooks",
+    "features.reservation_expiry",
+    "features.seed_data",
+    "webhooks.concurrency",
+    "webhooks.allow_private_targets",
+];
+
+/// Command-line flags that set a single key; `--set <key>=<value>` sets any of them
+const FLAGS: [(&str, &str); 5] = [
+    ("--env", "env"),
+    ("--database-url", "database.url"),
+    ("--listen", "server.listen"),
+    ("--static-dir", "server.static_dir"),
+    ("--log-level", "log.level"),
+];
+
+/// Parts of the app that can be switched off
+#[derive(Debug, Clone, Copy)]
+pub struct Features {
+    /// Deliver domain events to webhook subscriptions
+    pub webhooks: bool,
+    /// Expire stale stock reservations in the background
+    pub reservation_expiry: bool,
+    /// Fill an empty catalog with the demo products
+    pub seed_data: bool,
+}
+
+/// Settings the process runs with
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub env: Environment,
+    pub database_url: String,
+    pub pool: PoolConfig,
+    pub migrations_dir: PathBuf,
+    pub listen: SocketAddr,
+    pub static_dir: PathBuf,
+    pub log_level: tracing::Level,
+    pub features: Features,
+    pub webhooks: WebhookConfig,
+    /// Where each value that is not a default came from
+    sources: BTreeMap<&'static str, Source>,
+}
+
+/// The layer a value came from
+#[derive(Debug, Clone)]
+enum Source {
+    Default,
+    File(PathBuf),
+    Env(String),
+    Flag(String),
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Env(name) => write!(f, "${}", name),
+            Source::Flag(flag) => write!(f, "{}", flag),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            env: Environment::default(),
+            database_url: "sqlite:products.db".to_string(),
+            pool: PoolConfig::default(),
+            migrations_dir: PathBuf::from(MIGRATIONS_DIR),
+            listen: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            static_dir: PathBuf::from("static"),
+            log_level: tracing::Level::INFO,
+            features: Features { webhooks: true, reservation_expiry: true, seed_data: true },
+            webhooks: WebhookConfig::default(),
+            sources: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Read every layer and check the result. All problems are reported together.
+    pub fn load(args: &ConfigArgs) -> Result<Self> {
+        let named = args.config_file.clone().or_else(|| std::env::var_os(CONFIG_FILE_VAR).map(PathBuf::from));
+        let file = match named {
+            Some(path) => {
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Cannot read config file {}", path.display()))?;
+                Some((path, text))
+            }
+            None => std::fs::read_to_string(DEFAULT_CONFIG_FILE)
+                .ok()
+                .map(|text| (PathBuf::from(DEFAULT_CONFIG_FILE), text)),
+        };
+
+        Self::from_layers(file, |name| std::env::var(name).ok(), &args.overrides)
+    }
+
+    fn from_layers(
+        file: Option<(PathBuf, String)>,
+        env: impl Fn(&str) -> Option<String>,
+        flags: &[Override],
+    ) -> Result<Self> {
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+
+        if let Some((path, text)) = file {
+            match file_settings(&text) {
+                Ok(settings) => {
+                    for (key, value) in settings {
+                        config.apply(&key, &value, Source::File(path.clone()), &mut errors);
+                    }
+                }
+                Err(err) => errors.push(format!("{}: {}", path.display(), err)),
+            }
+        }
+        for key in KEYS {
+            let name = env_var(key);
+            if let Some(value) = env(&name) {
+                config.apply(key, &value, Source::Env(name), &mut errors);
+            }
+        }
+        for flag in flags {
+            config.apply(&flag.key, &flag.value, Source::Flag(flag.flag.clone()), &mut errors);
+        }
+
+        errors.extend(config.validate());
+        if !errors.is_empty() {
+            bail!("Invalid configuration:\n  - {}", errors.join("\n  - "));
+        }
+        Ok(config)
+    }
+
+    fn apply(&mut self, key: &str, value: &str, source: Source, errors: &mut Vec<String>) {
+        match KEYS.iter().find(|known| **known == key) {
+            Some(key) => match self.set(key, value) {
+                Ok(()) => {
+                    self.sources.insert(key, source);
+                }
+                Err(message) => errors.push(format!("{} (from {}): {}", key, source, message)),
+            },
+            None => errors.push(format!("{} (from {}): unknown setting", key, source)),
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match key {
+            "env" => self.env = value.parse()?,
+            "database.url" => self.database_url = value.to_string(),
+            "database.max_connections" => self.pool.max_connections = number(value)?,
+            "database.min_connections" => self.pool.min_connections = number(value)?,
+            "database.acquire_timeout_secs" => self.pool.acquire_timeout = Duration::from_secs(number(value)?),
+            "database.migrations_dir" => self.migrations_dir = PathBuf::from(value),
+            "server.listen" => {
+                self.listen = value
+                    .parse()
+                    .map_err(|_| format!("'{}' is not an address and port such as 0.0.0.0:3000", value))?
+            }
+            "server.static_dir" => self.static_dir = PathBuf::from(value),
+            "log.level" => {
+                self.log_level = value
+                    .parse()
+                    .map_err(|_| format!("'{}' is not one of error, warn, info, debug or trace", value))?
+            }
+            "features.webhooks" => self.features.webhooks = switch(value)?,
+            "features.reservation_expiry" => self.features.reservation_expiry = switch(value)?,
+            "features.seed_data" => self.features.seed_data = switch(value)?,
+            "webhooks.concurrency" => self.webhooks.concurrency = number(value)?,
+            "webhooks.allow_private_targets" => self.webhooks.allow_private_targets = switch(value)?,
+            _ => unreachable!("{} is listed in KEYS but not handled", key),
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut check = |ok: bool, key: &str, message: String| {
+            if !ok {
+                errors.push(format!("{} (from {}): {}", key, self.source(key), message));
+            }
+        };
+
+        check(
+            self.database_url.starts_with("sqlite:"),
+            "database.url",
+            format!("'{}' is not a SQLite URL such as sqlite:products.db", redact_url(&self.database_url)),
+        );
+        check(self.pool.max_connections >= 1, "database.max_connections", "must be at least 1".to_string());
+        check(
+            self.pool.min_connections <= self.pool.max_connections,
+            "database.min_connections",
+            format!("cannot exceed database.max_connections ({})", self.pool.max_connections),
+        );
+        check(
+            !self.pool.acquire_timeout.is_zero(),
+            "database.acquire_timeout_secs",
+            "must be at least 1".to_string(),
+        );
+        check(self.webhooks.concurrency >= 1, "webhooks.concurrency", "must be at least 1".to_string());
+        check(
+            self.migrations_dir.is_dir(),
+            "database.migrations_dir",
+            format!("{} is not a directory", self.migrations_dir.display()),
+        );
+        check(
+            self.static_dir.is_dir(),
+            "server.static_dir",
+            format!("{} is not a directory", self.static_dir.display()),
+        );
+        errors
+    }
+
+    fn source(&self, key: &str) -> &Source {
+        self.sources.get(key).unwrap_or(&Source::Default)
+    }
+
+    /// A setting's value as printed; secrets are masked
+    fn display_value(&self, key: &str) -> String {
+        match key {
+            "env" => format!("{:?}", self.env).to_lowercase(),
+            "database.url" => redact_url(&self.database_url),
+            "database.max_connections" => self.pool.max_connections.to_string(),
+            "database.min_connections" => self.pool.min_connections.to_string(),
+            "database.acquire_timeout_secs" => self.pool.acquire_timeout.as_secs().to_string(),
+            "database.migrations_dir" => self.migrations_dir.display().to_string(),
+            "server.listen" => self.listen.to_string(),
+            "server.static_dir" => self.static_dir.display().to_string(),
+            "log.level" => self.log_level.to_string().to_lowercase(),
+            "features.webhooks" => self.features.webhooks.to_string(),
+            "features.reservation_expiry" => self.features.reservation_expiry.to_string(),
+            "features.seed_data" => self.features.seed_data.to_string(),
+            "webhooks.concurrency" => self.webhooks.concurrency.to_string(),
+            "webhooks.allow_private_targets" => self.webhooks.allow_private_targets.to_string(),
+            _ => unreachable!("{} is listed in KEYS but not handled", key),
+        }
+    }
+}
+
+/// The effective configuration, one setting per line with where it came from
+impl std::fmt::Display for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for key in KEYS {
+            writeln!(f, "  {:<30} = {:<24} ({})", key, self.display_value(key), self.source(key))?;
+        }
+        Ok(())
+    }
+}
+
+/// A setting given on the command line
+#[derive(Debug, Clone)]
+struct Override {
+    flag: String,
+    key: String,
+    value: String,
+}
+
+/// Configuration flags from the command line
+#[derive(Debug, Default)]
+pub struct ConfigArgs {
+    pub config_file: Option<PathBuf>,
+    overrides: Vec<Override>,
+}
+
+impl ConfigArgs {
+    /// Take the configuration flags out of `args`, returning them and the remaining arguments.
+    /// Values follow their flag either as the next argument or after `=`.
+    pub fn extract(args: Vec<String>) -> Result<(Self, Vec<String>)> {
+        let mut config = Self::default();
+        let mut rest = Vec::new();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            let key = FLAGS.iter().find(|(known, _)| *known == flag).map(|(_, key)| *key);
+            if key.is_none() && flag != "--config" && flag != "--set" {
+                rest.push(arg);
+                continue;
+            }
+
+            let value = match inline {
+                Some(value) => value,
+                None => args.next().with_context(|| format!("{} needs a value", flag))?,
+            };
+            match (flag.as_str(), key) {
+                ("--config", _) => config.config_file = Some(PathBuf::from(value)),
+                ("--set", _) => {
+                    let (key, value) = value
+                        .split_once('=')
+                        .with_context(|| format!("--set expects <key>=<value>, got '{}'", value))?;
+                    config.overrides.push(Override {
+                        flag: "--set".to_string(),
+                        key: key.trim().to_string(),
+                        value: value.to_string(),
+                    });
+                }
+                (_, Some(key)) => config.overrides.push(Override { flag, key: key.to_string(), value }),
+                (_, None) => unreachable!("only configuration flags get here"),
+            }
+        }
+        Ok((config, rest))
+    }
+}
+
+/// The environment variable for a key, e.g. `APP_DATABASE_URL` for `database.url`
+fn env_var(key: &str) -> String {
+    format!("APP_{}", key.replace('.', "_").to_uppercase())
+}
+
+/// Dotted keys and values from a config file; tables become key prefixes
+fn file_settings(text: &str) -> Result<Vec<(String, String)>, String> {
+    let table: toml::Table = text.parse().map_err(|err: toml::de::Error| err.to_string().trim().to_string())?;
+    let mut settings = Vec::new();
+    flatten("", &table, &mut settings)?;
+    Ok(settings)
+}
+
+fn flatten(prefix: &str, table: &toml::Table, settings: &mut Vec<(String, String)>) -> Result<(), String> {
+    for (name, value) in table {
+        let key = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+        match value {
+            toml::Value::Table(table) => flatten(&key, table, settings)?,
+            toml::Value::String(text) => settings.push((key, text.clone())),
+            toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                settings.push((key, value.to_string()))
+            }
+            _ => return Err(format!("{}: expected a string, number or boolean", key)),
+        }
+    }
+    Ok(())
+}
+
+fn number<T: FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("'{}' is not a whole number", value))
+}
+
+fn switch(value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "on" | "yes" | "1" => Ok(true),
+        "false" | "off" | "no" | "0" => Ok(false),
+        _ => Err(format!("'{}' is not true or false", value)),
+    }
+}
+
+/// The URL with any password masked, e.g. `postgres://app:****@db/shop`
+fn redact_url(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    match authority.rsplit_once('@').and_then(|(userinfo, host)| Some((userinfo.split_once(':')?.0, host))) {
+        Some((user, host)) => format!("{}://{}:****@{}{}", scheme, user, host, path),
+        None => url.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> (ConfigArgs, Vec<String>) {
+        ConfigArgs::extract(args.iter().map(|arg| arg.to_string()).collect()).unwrap()
+    }
+
+    fn load(file: Option<&str>, env: &[(&str, &str)], flags: &[&str]) -> Result<Config> {
+        let env: BTreeMap<String, String> = env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let file = file.map(|text| (PathBuf::from("app.toml"), text.to_string()));
+        let (flags, _) = args(flags);
+        // Directories are resolved from the crate root, where `migrations/` and `static/` live
+        let root = env!("CARGO_MANIFEST_DIR");
+        let mut overrides = vec![
+            Override { flag: "test".into(), key: "database.migrations_dir".into(), value: format!("{}/migrations", root) },
+            Override { flag: "test".into(), key: "server.static_dir".into(), value: format!("{}/static", root) },
+        ];
+        overrides.extend(flags.overrides);
+        Config::from_layers(file, |name| env.get(name).cloned(), &overrides)
+    }
+
+    #[test]
+    fn later_layers_win_and_each_value_remembers_its_source() {
+        let file = r#"
+            env = "production"
+            [database]
+            url = "sqlite:from-file.db"
+            max_connections = 4
+            [server]
+            listen = "127.0.0.1:8080"
+            [features]
+            webhooks = false
+        "#;
+        let env = [("APP_DATABASE_URL", "sqlite:from-env.db"), ("APP_LOG_LEVEL", "debug"), ("APP_SERVER_LISTEN", "127.0.0.1:9090")];
+        let config = load(Some(file), &env, &["--listen", "127.0.0.1:7070", "--set=features.seed_data=off"]).unwrap();
+
+        assert_eq!(config.env, Environment::Production);
+        assert_eq!(config.database_url, "sqlite:from-env.db");
+        assert_eq!(config.pool.max_connections, 4);
+        assert_eq!(config.pool.min_connections, PoolConfig::default().min_connections);
+        assert_eq!(config.listen, "127.0.0.1:7070".parse().unwrap());
+        assert_eq!(config.log_level, tracing::Level::DEBUG);
+        assert!(!config.features.webhooks && !config.features.seed_data && config.features.reservation_expiry);
+
+        let printed = config.to_string();
+        assert!(printed.lines().any(|line| line.contains("database.url") && line.ends_with("($APP_DATABASE_URL)")));
+        assert!(printed.lines().any(|line| line.contains("server.listen") && line.ends_with("(--listen)")));
+        assert!(printed.lines().any(|line| line.contains("database.max_connections") && line.ends_with("(app.toml)")));
+        assert!(printed.lines().any(|line| line.contains("features.reservation_expiry") && line.ends_with("(default)")));
+    }
+
+    #[test]
+    fn every_problem_is_reported_with_where_it_came_from() {
+        let file = "[database]\nmax_connections = 2\nmin_connections = 5\npool = 3\n";
+        let err = load(Some(file), &[("APP_SERVER_LISTEN", "localhost")], &["--log-level", "loud"])
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("database.pool (from app.toml): unknown setting"), "{}", err);
+        assert!(err.contains("server.listen (from $APP_SERVER_LISTEN): 'localhost' is not an address"), "{}", err);
+        assert!(err.contains("log.level (from --log-level): 'loud'"), "{}", err);
+        assert!(err.contains("database.min_connections (from app.toml): cannot exceed database.max_connections (2)"), "{}", err);
+
+        let err = load(Some("[database\n"), &[], &[]).unwrap_err().to_string();
+        assert!(err.contains("app.toml:"), "{}", err);
+    }
+
+    #[test]
+    fn configuration_flags_are_separated_from_the_other_arguments() {
+        let (config, rest) = args(&["--config", "prod.toml", "--grant-role", "a@example.com", "admin", "--env=production"]);
+        assert_eq!(config.config_file, Some(PathBuf::from("prod.toml")));
+        assert_eq!(rest, ["--grant-role", "a@example.com", "admin"]);
+        assert_eq!(config.overrides[0].key, "env");
+        assert!(ConfigArgs::extract(vec!["--listen".to_string()]).is_err());
+    }
+
+    #[test]
+    fn passwords_in_database_urls_are_masked() {
+        assert_eq!(redact_url("postgres://shop:hunter2@db:5432/shop"), "postgres://shop:****@db:5432/shop");
+        assert_eq!(redact_url("sqlite:products.db"), "sqlite:products.db");
+        assert_eq!(redact_url("postgres://shop@db/shop"), "postgres://shop@db/shop");
+
+        let config = load(None, &[("APP_DATABASE_URL", "mysql://root:hunter2@db/shop")], &[]).unwrap_err();
+        assert!(!config.to_string().contains("hunter2"));
+    }
+}
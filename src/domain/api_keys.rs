@@ -0,0 +1,180 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::DomainError;
+use crate::domain::repositories::RepositoryError;
+
+/// Longest key name
+pub const MAX_API_KEY_NAME_LENGTH: usize = 100;
+/// Highest per-key rate limit that can be configured, in requests per minute
+pub const MAX_RATE_LIMIT_PER_MINUTE: u32 = 10_000;
+
+/// API Key ID Value Object
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ApiKeyId(i64);
+
+impl ApiKeyId {
+    pub fn new(value: i64) -> Result<Self, DomainError> {
+        if value <= 0 {
+            return Err(DomainError::InvalidApiKey("Invalid API key ID".to_string()));
+        }
+        Ok(Self(value))
+    }
+
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+}
+
+/// What a machine client may do with an API key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ApiScope {
+    ReadProducts,
+    WriteProducts,
+    ManageStock,
+}
+
+impl ApiScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::ReadProducts => "products:read",
+            ApiScope::WriteProducts => "products:write",
+            ApiScope::ManageStock => "stock:manage",
+        }
+    }
+}
+
+impl TryFrom<&str> for ApiScope {
+    type Error = DomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "products:read" => Ok(ApiScope::ReadProducts),
+            "products:write" => Ok(ApiScope::WriteProducts),
+            "stock:manage" => Ok(ApiScope::ManageStock),
+            other => Err(DomainError::InvalidApiKey(format!(
+                "Unknown scope '{}', expected products:read, products:write or stock:manage",
+                other
+            ))),
+        }
+    }
+}
+
+/// API Key Entity - credentials for a machine client of the REST API.
+///
+/// Only a hash of the secret is kept, plus a short prefix so admins can tell keys apart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKey {
+    id: ApiKeyId,
+    name: String,
+    prefix: String,
+    key_hash: String,
+    scopes: Vec<ApiScope>,
+    rate_limit_per_minute: Option<u32>,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    pub fn issue(
+        id: ApiKeyId,
+        name: &str,
+        prefix: String,
+        key_hash: String,
+        scopes: Vec<ApiScope>,
+        rate_limit_per_minute: Option<u32>,
+    ) -> Result<Self, DomainError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(DomainError::InvalidApiKey("Name cannot be empty".to_string()));
+        }
+        if name.chars().count() > MAX_API_KEY_NAME_LENGTH {
+            return Err(DomainError::InvalidApiKey(format!(
+                "Name cannot exceed {} characters",
+                MAX_API_KEY_NAME_LENGTH
+            )));
+        }
+
+        let mut scopes = scopes;
+        scopes.sort();
+        scopes.dedup();
+        if scopes.is_empty() {
+            return Err(DomainError::InvalidApiKey("Grant at least one scope".to_string()));
+        }
+
+        if let Some(limit) = rate_limit_per_minute {
+            if limit == 0 || limit > MAX_RATE_LIMIT_PER_MINUTE {
+                return Err(DomainError::InvalidApiKey(format!(
+                    "Rate limit must be between 1 and {} requests per minute",
+                    MAX_RATE_LIMIT_PER_MINUTE
+                )));
+            }
+        }
+
+        Ok(Self {
+            id,
+            name: name.to_string(),
+            prefix,
+            key_hash,
+            scopes,
+            rate_limit_per_minute,
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked_at: None,
+        })
+    }
+
+    /// Rebuild a key from persisted state
+    #[allow(clippy::too_many_arguments)]
+    pub fn reconstitute(
+        id: ApiKeyId,
+        name: String,
+        prefix: String,
+        key_hash: String,
+        scopes: Vec<ApiScope>,
+        rate_limit_per_minute: Option<u32>,
+        created_at: DateTime<Utc>,
+        last_used_at: Option<DateTime<Utc>>,
+        revoked_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self { id, name, prefix, key_hash, scopes, rate_limit_per_minute, created_at, last_used_at, revoked_at }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+
+    // Getters
+    pub fn id(&self) -> &ApiKeyId { &self.id }
+    pub fn name(&self) -> &str { &self.name }
+    pub fn prefix(&self) -> &str { &self.prefix }
+    pub fn key_hash(&self) -> &str { &self.key_hash }
+    pub fn scopes(&self) -> &[ApiScope] { &self.scopes }
+    pub fn rate_limit_per_minute(&self) -> Option<u32> { self.rate_limit_per_minute }
+    pub fn created_at(&self) -> DateTime<Utc> { self.created_at }
+    pub fn last_used_at(&self) -> Option<DateTime<Utc>> { self.last_used_at }
+    pub fn revoked_at(&self) -> Option<DateTime<Utc>> { self.revoked_at }
+}
+
+/// Repository trait for API keys
+#[async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    async fn find_all(&self) -> Result<Vec<ApiKey>, RepositoryError>;
+
+    async fn find_by_id(&self, id: &ApiKeyId) -> Result<Option<ApiKey>, RepositoryError>;
+
+    async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, RepositoryError>;
+
+    async fn save(&self, key: ApiKey) -> Result<ApiKey, RepositoryError>;
+
+    /// Mark the key revoked at `at`; false if it does not exist or was already revoked
+    async fn revoke(&self, id: &ApiKeyId, at: DateTime<Utc>) -> Result<bool, RepositoryError>;
+
+    /// Record that the key authenticated a request at `at`
+    async fn record_use(&self, id: &ApiKeyId, at: DateTime<Utc>) -> Result<(), RepositoryError>;
+
+    /// Get next available ID
+    async fn next_id(&self) -> Result<ApiKeyId, RepositoryError>;
+}
@@ -0,0 +1,119 @@
+use crate::domain::entities::DomainError;
+
+/// Most tags a product may carry
+pub const MAX_TAGS: usize = 20;
+/// Longest tag accepted
+pub const MAX_TAG_LENGTH: usize = 40;
+/// Most attributes a product may carry
+pub const MAX_ATTRIBUTES: usize = 20;
+/// Longest attribute key accepted
+pub const MAX_ATTRIBUTE_KEY_LENGTH: usize = 40;
+/// Longest attribute value accepted
+pub const MAX_ATTRIBUTE_VALUE_LENGTH: usize = 100;
+
+/// Tag Value Object - a free-form label such as "sale" or "wireless". Tags are lowercased
+/// and runs of whitespace collapse to one space, so "New  Arrival" and "new arrival" are
+/// the same tag.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Tag(String);
+
+impl Tag {
+    pub fn new(value: &str) -> Result<Self, DomainError> {
+        let tag = value.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if tag.is_empty() {
+            return Err(DomainError::InvalidTag("Tags cannot be empty".to_string()));
+        }
+        if tag.chars().count() > MAX_TAG_LENGTH {
+            return Err(DomainError::InvalidTag(format!("Tags cannot exceed {} characters", MAX_TAG_LENGTH)));
+        }
+        // Commas separate tags in the product forms
+        if tag.contains(',') {
+            return Err(DomainError::InvalidTag(format!("Tag '{}' cannot contain a comma", tag)));
+        }
+        Ok(Self(tag))
+    }
+
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Product Attribute Value Object - a key/value pair such as `color: Space Black`.
+///
+/// Keys are lowercase letters, digits, `_` and `-`; spaces become underscores, so
+/// "Storage Size" is stored as `storage_size`. Values keep their case.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProductAttribute {
+    key: String,
+    value: String,
+}
+
+impl ProductAttribute {
+    pub fn new(key: &str, value: &str) -> Result<Self, DomainError> {
+        let key = key.split_whitespace().collect::<Vec<_>>().join("_").to_lowercase();
+        if key.is_empty() {
+            return Err(DomainError::InvalidAttribute("Attribute keys cannot be empty".to_string()));
+        }
+        if key.chars().count() > MAX_ATTRIBUTE_KEY_LENGTH {
+            return Err(DomainError::InvalidAttribute(format!(
+                "Attribute keys cannot exceed {} characters",
+                MAX_ATTRIBUTE_KEY_LENGTH
+            )));
+        }
+        if !key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+            return Err(DomainError::InvalidAttribute(format!(
+                "Attribute key '{}' may only contain letters, digits, '_' and '-'",
+                key
+            )));
+        }
+
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(DomainError::InvalidAttribute(format!("Attribute '{}' needs a value", key)));
+        }
+        if value.chars().count() > MAX_ATTRIBUTE_VALUE_LENGTH {
+            return Err(DomainError::InvalidAttribute(format!(
+                "Attribute values cannot exceed {} characters",
+                MAX_ATTRIBUTE_VALUE_LENGTH
+            )));
+        }
+        Ok(Self { key, value: value.to_string() })
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// Sort and de-duplicate tags, enforcing `MAX_TAGS`
+pub fn normalize_tags(mut tags: Vec<Tag>) -> Result<Vec<Tag>, DomainError> {
+    tags.sort();
+    tags.dedup();
+    if tags.len() > MAX_TAGS {
+        return Err(DomainError::InvalidTag(format!("A product can have at most {} tags", MAX_TAGS)));
+    }
+    Ok(tags)
+}
+
+/// Sort attributes by key, enforcing one value per key and `MAX_ATTRIBUTES`
+pub fn normalize_attributes(mut attributes: Vec<ProductAttribute>) -> Result<Vec<ProductAttribute>, DomainError> {
+    attributes.sort();
+    attributes.dedup();
+    if let Some(pair) = attributes.windows(2).find(|pair| pair[0].key == pair[1].key) {
+        return Err(DomainError::InvalidAttribute(format!(
+            "Attribute '{}' is given more than one value",
+            pair[0].key
+        )));
+    }
+    if attributes.len() > MAX_ATTRIBUTES {
+        return Err(DomainError::InvalidAttribute(format!(
+            "A product can have at most {} attributes",
+            MAX_ATTRIBUTES
+        )));
+    }
+    Ok(attributes)
+}
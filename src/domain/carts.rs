@@ -0,0 +1,233 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::{Currency, DomainError, Money, Product, ProductId};
+use crate::domain::repositories::RepositoryError;
+
+/// Most units of a single product one cart may hold
+pub const MAX_LINE_QUANTITY: i32 = 999;
+
+/// Cart ID Value Object
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CartId(i64);
+
+impl CartId {
+    pub fn new(value: i64) -> Result<Self, DomainError> {
+        if value <= 0 {
+            return Err(DomainError::InvalidCart("Invalid cart ID".to_string()));
+        }
+        Ok(Self(value))
+    }
+
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+}
+
+/// One product in a cart, priced when it was first added
+#[derive(Debug, Clone, PartialEq)]
+pub struct CartLine {
+    product_id: ProductId,
+    quantity: i32,
+    unit_price: Money,
+    added_at: DateTime<Utc>,
+}
+
+impl CartLine {
+    /// Rebuild a line from persisted state
+    pub fn reconstitute(product_id: ProductId, quantity: i32, unit_price: Money, added_at: DateTime<Utc>) -> Self {
+        Self { product_id, quantity, unit_price, added_at }
+    }
+
+    /// Unit price times quantity
+    pub fn subtotal(&self) -> Result<Money, DomainError> {
+        self.unit_price.checked_mul(self.quantity as u32)
+    }
+
+    // Getters
+    pub fn product_id(&self) -> &ProductId { &self.product_id }
+    pub fn quantity(&self) -> i32 { self.quantity }
+    pub fn unit_price(&self) -> &Money { &self.unit_price }
+    pub fn added_at(&self) -> DateTime<Utc> { self.added_at }
+}
+
+/// Cart Aggregate - the products a visitor intends to buy, found by an opaque session token.
+///
+/// Line quantities never exceed what the product has available at the time they are set;
+/// all lines share one currency so the cart has a single total.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cart {
+    id: CartId,
+    token: String,
+    lines: Vec<CartLine>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl Cart {
+    pub fn new(id: CartId, token: String) -> Result<Self, DomainError> {
+        if token.trim().is_empty() {
+            return Err(DomainError::InvalidCart("Cart token cannot be empty".to_string()));
+        }
+
+        let now = Utc::now();
+        Ok(Self {
+            id,
+            token,
+            lines: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Rebuild a cart from persisted state
+    pub fn reconstitute(
+        id: CartId,
+        token: String,
+        lines: Vec<CartLine>,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    ) -> Self {
+        Self { id, token, lines, created_at, updated_at }
+    }
+
+    /// Add `quantity` units of `product`, capturing its current price on a new line.
+    /// The line's total quantity must fit the product's available stock.
+    pub fn add_item(&mut self, product: &Product, quantity: i32) -> Result<(), DomainError> {
+        Self::ensure_positive(quantity)?;
+        let existing = self.line(product.id()).map_or(0, CartLine::quantity);
+        let total = existing
+            .checked_add(quantity)
+            .ok_or_else(|| DomainError::InvalidCart("Quantity too high".to_string()))?;
+        self.set_line(product, total)
+    }
+
+    /// Replace the quantity of a product already in the cart; zero removes the line.
+    /// Returns false when the product is not in the cart.
+    pub fn set_quantity(&mut self, product: &Product, quantity: i32) -> Result<bool, DomainError> {
+        if self.line(product.id()).is_none() {
+            return Ok(false);
+        }
+        if quantity == 0 {
+            return Ok(self.remove_item(product.id()));
+        }
+        Self::ensure_positive(quantity)?;
+        self.set_line(product, quantity)?;
+        Ok(true)
+    }
+
+    /// Returns false when the product is not in the cart
+    pub fn remove_item(&mut self, product_id: &ProductId) -> bool {
+        let before = self.lines.len();
+        self.lines.retain(|line| line.product_id != *product_id);
+        let removed = self.lines.len() != before;
+        if removed {
+            self.updated_at = Utc::now();
+        }
+        removed
+    }
+
+    /// Update the line for `product` to its current price.
+    /// Returns false when the product is not in the cart or its price is unchanged.
+    pub fn reprice(&mut self, product: &Product) -> bool {
+        let Some(line) = self.lines.iter_mut().find(|line| line.product_id == *product.id()) else {
+            return false;
+        };
+        if line.unit_price == *product.price() {
+            return false;
+        }
+        line.unit_price = product.price().clone();
+        self.updated_at = Utc::now();
+        true
+    }
+
+    pub fn clear(&mut self) {
+        if !self.lines.is_empty() {
+            self.lines.clear();
+            self.updated_at = Utc::now();
+        }
+    }
+
+    /// Total number of units across all lines
+    pub fn item_count(&self) -> i32 {
+        self.lines.iter().map(CartLine::quantity).sum()
+    }
+
+    /// Sum of all line subtotals, in the cart's currency
+    pub fn total(&self) -> Result<Money, DomainError> {
+        self.lines.iter().try_fold(Money::zero(self.currency()), |total, line| {
+            total.checked_add(&line.subtotal()?)
+        })
+    }
+
+    /// Currency of the lines, or the default currency for an empty cart
+    pub fn currency(&self) -> Currency {
+        self.lines.first().map_or_else(Currency::default, |line| line.unit_price.currency())
+    }
+
+    fn set_line(&mut self, product: &Product, quantity: i32) -> Result<(), DomainError> {
+        if quantity > MAX_LINE_QUANTITY {
+            return Err(DomainError::InvalidCart(format!(
+                "At most {} units of a product can be added to a cart",
+                MAX_LINE_QUANTITY
+            )));
+        }
+        if quantity > product.available_stock() {
+            return Err(DomainError::InsufficientStock);
+        }
+
+        let now = Utc::now();
+        match self.lines.iter_mut().find(|line| line.product_id == *product.id()) {
+            Some(line) => line.quantity = quantity,
+            None => {
+                let currency = self.currency();
+                if !self.lines.is_empty() && product.price().currency() != currency {
+                    return Err(DomainError::CurrencyMismatch(
+                        currency.code().to_string(),
+                        product.price().currency().code().to_string(),
+                    ));
+                }
+                self.lines.push(CartLine {
+                    product_id: product.id().clone(),
+                    quantity,
+                    unit_price: product.price().clone(),
+                    added_at: now,
+                });
+            }
+        }
+        self.updated_at = now;
+        Ok(())
+    }
+
+    fn line(&self, product_id: &ProductId) -> Option<&CartLine> {
+        self.lines.iter().find(|line| line.product_id == *product_id)
+    }
+
+    fn ensure_positive(quantity: i32) -> Result<(), DomainError> {
+        if quantity <= 0 {
+            return Err(DomainError::InvalidCart("Quantity must be positive".to_string()));
+        }
+        Ok(())
+    }
+
+    // Getters
+    #[allow(dead_code)]
+    pub fn id(&self) -> &CartId { &self.id }
+    pub fn token(&self) -> &str { &self.token }
+    pub fn lines(&self) -> &[CartLine] { &self.lines }
+    pub fn created_at(&self) -> DateTime<Utc> { self.created_at }
+    pub fn updated_at(&self) -> DateTime<Utc> { self.updated_at }
+}
+
+/// Repository trait for carts
+#[async_trait]
+pub trait CartRepository: Send + Sync {
+    async fn find_by_token(&self, token: &str) -> Result<Option<Cart>, RepositoryError>;
+
+    /// Store the cart, creating it on first save, and replace its lines
+    async fn save(&self, cart: Cart) -> Result<Cart, RepositoryError>;
+
+    /// Get next available ID
+    async fn next_id(&self) -> Result<CartId, RepositoryError>;
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::{DomainError, ProductId};
+use crate::domain::repositories::RepositoryError;
+
+/// Longest category name accepted
+pub const MAX_CATEGORY_NAME_LENGTH: usize = 100;
+/// Longest slug accepted
+pub const MAX_SLUG_LENGTH: usize = 100;
+
+/// Category ID Value Object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CategoryId(i64);
+
+impl CategoryId {
+    pub fn new(value: i64) -> Result<Self, DomainError> {
+        if value <= 0 {
+            return Err(DomainError::InvalidCategory("Invalid category ID".to_string()));
+        }
+        Ok(Self(value))
+    }
+
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+}
+
+/// Slug Value Object - the URL name of a category: lowercase ASCII letters and digits
+/// in runs separated by single hyphens
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Slug(String);
+
+impl Slug {
+    pub fn new(value: &str) -> Result<Self, DomainError> {
+        let value = value.trim();
+        let well_formed = value
+            .split('-')
+            .all(|run| !run.is_empty() && run.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+        if value.is_empty() || !well_formed {
+            return Err(DomainError::InvalidCategory(format!(
+                "Slug '{}' must be lowercase letters and digits separated by single hyphens",
+                value
+            )));
+        }
+        if value.len() > MAX_SLUG_LENGTH {
+            return Err(DomainError::InvalidCategory(format!(
+                "Slug cannot exceed {} characters",
+                MAX_SLUG_LENGTH
+            )));
+        }
+        Ok(Self(value.to_string()))
+    }
+
+    /// Derive a slug from a name, e.g. "Phones & Tablets" → `phones-tablets`
+    pub fn from_name(name: &str) -> Result<Self, DomainError> {
+        let lowered = name.to_ascii_lowercase();
+        let runs: Vec<&str> = lowered
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|run| !run.is_empty())
+            .collect();
+        if runs.is_empty() {
+            return Err(DomainError::InvalidCategory(format!(
+                "Cannot derive a slug from '{}'; give one explicitly",
+                name.trim()
+            )));
+        }
+        let mut slug = runs.join("-");
+        slug.truncate(MAX_SLUG_LENGTH);
+        Self::new(slug.trim_end_matches('-'))
+    }
+
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Category Entity - a node of the catalog taxonomy. Products belong to any number of
+/// categories; a category belongs to at most one parent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Category {
+    id: CategoryId,
+    name: String,
+    slug: Slug,
+    parent_id: Option<CategoryId>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl Category {
+    pub fn new(id: CategoryId, name: &str, slug: Slug, parent_id: Option<CategoryId>) -> Result<Self, DomainError> {
+        let now = Utc::now();
+        Ok(Self {
+            id,
+            name: Self::validate_name(name)?,
+            slug,
+            parent_id,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Rebuild a category from persisted state
+    pub fn reconstitute(
+        id: CategoryId,
+        name: String,
+        slug: Slug,
+        parent_id: Option<CategoryId>,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    ) -> Self {
+        Self { id, name, slug, parent_id, created_at, updated_at }
+    }
+
+    /// Rename or move the category. `parent_id` of `Some(None)` makes it a top-level category;
+    /// the caller checks the new parent with [`CategoryTree::check_parent`].
+    pub fn update(
+        &mut self,
+        name: Option<&str>,
+        slug: Option<Slug>,
+        parent_id: Option<Option<CategoryId>>,
+    ) -> Result<(), DomainError> {
+        if let Some(name) = name {
+            self.name = Self::validate_name(name)?;
+        }
+        if let Some(slug) = slug {
+            self.slug = slug;
+        }
+        if let Some(parent_id) = parent_id {
+            self.parent_id = parent_id;
+        }
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    fn validate_name(name: &str) -> Result<String, DomainError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(DomainError::InvalidCategory("Category name cannot be empty".to_string()));
+        }
+        if name.chars().count() > MAX_CATEGORY_NAME_LENGTH {
+            return Err(DomainError::InvalidCategory(format!(
+                "Category name cannot exceed {} characters",
+                MAX_CATEGORY_NAME_LENGTH
+            )));
+        }
+        Ok(name.to_string())
+    }
+
+    // Getters
+    pub fn id(&self) -> &CategoryId {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn slug(&self) -> &Slug {
+        &self.slug
+    }
+
+    pub fn parent_id(&self) -> Option<&CategoryId> {
+        self.parent_id.as_ref()
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+}
+
+/// Every category, arranged by parent
+pub struct CategoryTree {
+    categories: Vec<Category>,
+    children: HashMap<Option<CategoryId>, Vec<usize>>,
+}
+
+impl CategoryTree {
+    pub fn new(mut categories: Vec<Category>) -> Self {
+        categories.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()).then(a.id.0.cmp(&b.id.0)));
+        let mut children: HashMap<Option<CategoryId>, Vec<usize>> = HashMap::new();
+        for (index, category) in categories.iter().enumerate() {
+            children.entry(category.parent_id).or_default().push(index);
+        }
+        Self { categories, children }
+    }
+
+    pub fn get(&self, id: &CategoryId) -> Option<&Category> {
+        self.categories.iter().find(|category| category.id == *id)
+    }
+
+    /// Every category with its depth, parents before their children and siblings by name
+    pub fn walk(&self) -> Vec<(&Category, usize)> {
+        let mut walked = Vec::with_capacity(self.categories.len());
+        let mut pending: Vec<(usize, usize)> = self.child_indexes(None).iter().rev().map(|&i| (i, 0)).collect();
+        while let Some((index, depth)) = pending.pop() {
+            let category = &self.categories[index];
+            walked.push((category, depth));
+            pending.extend(self.child_indexes(Some(category.id)).iter().rev().map(|&i| (i, depth + 1)));
+        }
+        walked
+    }
+
+    /// The categories from the top of the tree down to `id`, for breadcrumbs
+    pub fn path(&self, id: &CategoryId) -> Vec<&Category> {
+        let mut path = Vec::new();
+        let mut next = self.get(id);
+        while let Some(category) = next {
+            // A cycle can only come from hand-edited rows; stop rather than loop
+            if path.iter().any(|seen: &&Category| seen.id == category.id) {
+                break;
+            }
+            path.push(category);
+            next = category.parent_id.and_then(|parent| self.get(&parent));
+        }
+        path.reverse();
+        path
+    }
+
+    /// Check that `category` may be placed under `parent`: the parent must exist and must
+    /// not be the category itself or one of its descendants
+    pub fn check_parent(&self, category: Option<&CategoryId>, parent: &CategoryId) -> Result<(), DomainError> {
+        if self.get(parent).is_none() {
+            return Err(DomainError::InvalidCategory(format!("Parent category {} does not exist", parent.0)));
+        }
+        if let Some(category) = category {
+            if self.path(parent).iter().any(|ancestor| ancestor.id == *category) {
+                return Err(DomainError::InvalidCategory(
+                    "A category cannot be moved under itself or one of its subcategories".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn child_indexes(&self, parent: Option<CategoryId>) -> &[usize] {
+        self.children.get(&parent).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+/// Repository trait for the category tree and product assignments
+#[async_trait]
+pub trait CategoryRepository: Send + Sync {
+    async fn find_all(&self) -> Result<Vec<Category>, RepositoryError>;
+
+    async fn find_by_id(&self, id: &CategoryId) -> Result<Option<Category>, RepositoryError>;
+
+    async fn find_by_slug(&self, slug: &Slug) -> Result<Option<Category>, RepositoryError>;
+
+    async fn save(&self, category: Category) -> Result<Category, RepositoryError>;
+
+    async fn update(&self, category: Category) -> Result<Category, RepositoryError>;
+
+    /// Delete a category that has no products and no subcategories.
+    /// Fails with `DomainError::CategoryNotEmpty` otherwise.
+    async fn delete(&self, id: &CategoryId) -> Result<bool, RepositoryError>;
+
+    /// Categories `product_id` is assigned to
+    async fn find_for_product(&self, product_id: &ProductId) -> Result<Vec<Category>, RepositoryError>;
+
+    /// Replace the categories `product_id` is assigned to
+    async fn assign(&self, product_id: &ProductId, categories: &[CategoryId]) -> Result<(), RepositoryError>;
+
+    /// Get next available ID
+    async fn next_id(&self) -> Result<CategoryId, RepositoryError>;
+}
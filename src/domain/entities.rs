@@ -1,6 +1,9 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::domain::attributes::{normalize_attributes, normalize_tags, ProductAttribute, Tag};
+use crate::domain::inventory::{StockMovement, StockMovementReason};
+
 /// Product Entity - Core business entity
 #[derive(Debug, Clone, PartialEq)]
 pub struct Product {
@@ -9,61 +12,258 @@ pub struct Product {
     description: Option<String>,
     price: Money,
     stock: StockQuantity,
+    /// Units of `stock` held by active reservations
+    reserved: StockQuantity,
+    /// Sorted and free of duplicates
+    tags: Vec<Tag>,
+    /// Sorted by key, one value per key
+    attributes: Vec<ProductAttribute>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
+    /// Optimistic concurrency token, incremented by the repository on every update
+    version: i64,
+    /// Stock changes made since the product was loaded, written to the ledger on save
+    stock_movements: Vec<StockMovement>,
+    /// Whether tags or attributes changed since the product was loaded, so the repository
+    /// only rewrites them when needed
+    details_changed: bool,
 }
 
 impl Product {
+    /// Create a product. Any initial stock is recorded as a restock by `actor`.
     pub fn new(
         id: ProductId,
         name: ProductName,
         description: Option<String>,
         price: Money,
         stock: StockQuantity,
+        actor: &str,
     ) -> Self {
         let now = Utc::now();
-        Self {
+        let mut product = Self {
             id,
             name,
             description,
             price,
             stock,
+            reserved: StockQuantity(0),
+            tags: Vec::new(),
+            attributes: Vec::new(),
             created_at: now,
             updated_at: now,
+            version: 1,
+            stock_movements: Vec::new(),
+            details_changed: false,
+        };
+        if product.stock.value() > 0 {
+            product.record_movement(product.stock.value(), StockMovementReason::Restock, actor, Some("Initial stock".to_string()));
+        }
+        product
+    }
+
+    /// Rebuild a product from persisted state, keeping its stored timestamps and version
+    #[allow(clippy::too_many_arguments)]
+    pub fn reconstitute(
+        id: ProductId,
+        name: ProductName,
+        description: Option<String>,
+        price: Money,
+        stock: StockQuantity,
+        reserved: StockQuantity,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+        version: i64,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            description,
+            price,
+            stock,
+            reserved,
+            tags: Vec::new(),
+            attributes: Vec::new(),
+            created_at,
+            updated_at,
+            version,
+            stock_movements: Vec::new(),
+            details_changed: false,
+        }
+    }
+
+    /// Attach tags and attributes to a new or rebuilt product. They are taken as given, so
+    /// they should come from `normalize_tags` and `normalize_attributes` or from storage.
+    pub fn with_details(mut self, tags: Vec<Tag>, attributes: Vec<ProductAttribute>) -> Self {
+        self.tags = tags;
+        self.attributes = attributes;
+        self
+    }
+
+    /// Replace the product's tags. Duplicates are dropped; returns whether anything changed.
+    pub fn set_tags(&mut self, tags: Vec<Tag>) -> Result<bool, DomainError> {
+        let tags = normalize_tags(tags)?;
+        if tags == self.tags {
+            return Ok(false);
+        }
+        self.tags = tags;
+        self.details_changed = true;
+        self.updated_at = Utc::now();
+        Ok(true)
+    }
+
+    /// Replace the product's attributes, at most one value per key; returns whether
+    /// anything changed.
+    pub fn set_attributes(&mut self, attributes: Vec<ProductAttribute>) -> Result<bool, DomainError> {
+        let attributes = normalize_attributes(attributes)?;
+        if attributes == self.attributes {
+            return Ok(false);
         }
+        self.attributes = attributes;
+        self.details_changed = true;
+        self.updated_at = Utc::now();
+        Ok(true)
     }
 
+    /// Apply the given changes and return the names of the fields whose value actually changed.
+    /// `updated_at` only moves when something changed; a new stock level is recorded as a
+    /// correction by `actor`.
     pub fn update(
         &mut self,
         name: Option<ProductName>,
         description: Option<Option<String>>,
         price: Option<Money>,
         stock: Option<StockQuantity>,
-    ) -> Result<(), DomainError> {
-        if let Some(name) = name {
+        actor: &str,
+    ) -> Result<Vec<String>, DomainError> {
+        if stock.as_ref().is_some_and(|stock| stock.value() < self.reserved.value()) {
+            return Err(DomainError::InvalidStock(format!(
+                "Stock cannot be lower than the {} units currently reserved",
+                self.reserved.value()
+            )));
+        }
+
+        let mut changes = Vec::new();
+
+        if let Some(name) = name.filter(|name| *name != self.name) {
             self.name = name;
+            changes.push("name".to_string());
         }
-        if let Some(description) = description {
+        if let Some(description) = description.filter(|description| *description != self.description) {
             self.description = description;
+            changes.push("description".to_string());
         }
-        if let Some(price) = price {
+        if let Some(price) = price.filter(|price| *price != self.price) {
             self.price = price;
+            changes.push("price".to_string());
         }
-        if let Some(stock) = stock {
+        if let Some(stock) = stock.filter(|stock| *stock != self.stock) {
+            let delta = stock.value() - self.stock.value();
             self.stock = stock;
+            self.record_movement(delta, StockMovementReason::Correction, actor, None);
+            changes.push("stock".to_string());
+        }
+
+        if !changes.is_empty() {
+            self.updated_at = Utc::now();
+        }
+        Ok(changes)
+    }
+
+    /// Units that can still be sold or reserved
+    pub fn available_stock(&self) -> i32 {
+        self.stock.value() - self.reserved.value()
+    }
+
+    /// Add (`delta > 0`) or remove (`delta < 0`) stock on hand. Reserved units cannot be
+    /// removed. Returns the stock level before the adjustment.
+    pub fn adjust_stock(
+        &mut self,
+        delta: i32,
+        reason: StockMovementReason,
+        actor: &str,
+        note: Option<String>,
+    ) -> Result<StockQuantity, DomainError> {
+        let old_stock = self.stock.clone();
+        let mut stock = self.stock.clone();
+
+        if delta != 0 && !reason.allows(delta) {
+            return Err(DomainError::InvalidStock(format!(
+                "A {} cannot {} stock",
+                reason.as_str(),
+                if delta > 0 { "add" } else { "remove" }
+            )));
+        }
+
+        match delta {
+            0 => return Err(DomainError::InvalidStock("Adjustment cannot be zero".to_string())),
+            delta if delta > 0 => stock.increase(delta)?,
+            delta => {
+                let amount = delta.checked_neg().ok_or(DomainError::InsufficientStock)?;
+                StockQuantity::new(self.available_stock())?.decrease(amount)?;
+                stock.decrease(amount)?;
+            }
+        }
+
+        self.stock = stock;
+        self.record_movement(delta, reason, actor, note);
+        self.updated_at = Utc::now();
+        Ok(old_stock)
+    }
+
+    /// Hold `quantity` available units for a reservation
+    pub fn reserve_stock(&mut self, quantity: i32) -> Result<(), DomainError> {
+        if quantity <= 0 {
+            return Err(DomainError::InvalidStock("Reservation quantity must be positive".to_string()));
         }
+        StockQuantity::new(self.available_stock())?.decrease(quantity)?;
+        self.reserved.increase(quantity)?;
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Return held units to the available pool
+    pub fn release_reserved_stock(&mut self, quantity: i32) -> Result<(), DomainError> {
+        self.reserved.decrease(quantity)?;
         self.updated_at = Utc::now();
         Ok(())
     }
 
+    /// Turn held units into a sale by `actor`, removing them from stock.
+    /// Returns the stock level before the commit.
+    pub fn commit_reserved_stock(&mut self, quantity: i32, actor: &str) -> Result<StockQuantity, DomainError> {
+        let old_stock = self.stock.clone();
+        self.reserved.decrease(quantity)?;
+        self.stock.decrease(quantity)?;
+        self.record_movement(-quantity, StockMovementReason::Sale, actor, None);
+        self.updated_at = Utc::now();
+        Ok(old_stock)
+    }
+
+    fn record_movement(&mut self, delta: i32, reason: StockMovementReason, actor: &str, note: Option<String>) {
+        self.stock_movements.push(StockMovement {
+            delta,
+            reason,
+            actor: actor.to_string(),
+            note,
+            stock_after: self.stock.value(),
+            created_at: Utc::now(),
+        });
+    }
+
     // Getters
     pub fn id(&self) -> &ProductId { &self.id }
     pub fn name(&self) -> &ProductName { &self.name }
     pub fn description(&self) -> &Option<String> { &self.description }
     pub fn price(&self) -> &Money { &self.price }
     pub fn stock(&self) -> &StockQuantity { &self.stock }
+    pub fn reserved(&self) -> &StockQuantity { &self.reserved }
+    pub fn tags(&self) -> &[Tag] { &self.tags }
+    pub fn attributes(&self) -> &[ProductAttribute] { &self.attributes }
     pub fn created_at(&self) -> &DateTime<Utc> { &self.created_at }
     pub fn updated_at(&self) -> &DateTime<Utc> { &self.updated_at }
+    pub fn version(&self) -> i64 { self.version }
+    pub fn pending_stock_movements(&self) -> &[StockMovement] { &self.stock_movements }
+    pub fn details_changed(&self) -> bool { self.details_changed }
 }
 
 /// Product ID Value Object
@@ -89,6 +289,9 @@ impl From<i64> for ProductId {
     }
 }
 
+/// Longest product name, in bytes
+pub const MAX_PRODUCT_NAME_LENGTH: usize = 255;
+
 /// Product Name Value Object
 #[derive(Debug, Clone, PartialEq)]
 pub struct ProductName(String);
@@ -96,10 +299,10 @@ pub struct ProductName(String);
 impl ProductName {
     pub fn new(value: String) -> Result<Self, DomainError> {
         if value.trim().is_empty() {
-            return Err(DomainError::InvalidProductName("Product name cannot be empty".to_string()));
+            return Err(DomainError::EmptyProductName);
         }
-        if value.len() > 255 {
-            return Err(DomainError::InvalidProductName("Product name too long".to_string()));
+        if value.len() > MAX_PRODUCT_NAME_LENGTH {
+            return Err(DomainError::ProductNameTooLong(MAX_PRODUCT_NAME_LENGTH));
         }
         Ok(Self(value.trim().to_string()))
     }
@@ -117,34 +320,247 @@ impl TryFrom<String> for ProductName {
     }
 }
 
-/// Money Value Object
-#[derive(Debug, Clone, PartialEq)]
-pub struct Money(f64);
+/// Active ISO-4217 currencies and the decimal places of their minor unit, sorted by code
+const ISO_4217: &[(&str, u32)] = &[
+    ("AED", 2), ("AFN", 2), ("ALL", 2), ("AMD", 2), ("ANG", 2), ("AOA", 2), ("ARS", 2), ("AUD", 2),
+    ("AWG", 2), ("AZN", 2), ("BAM", 2), ("BBD", 2), ("BDT", 2), ("BGN", 2), ("BHD", 3), ("BIF", 0),
+    ("BMD", 2), ("BND", 2), ("BOB", 2), ("BOV", 2), ("BRL", 2), ("BSD", 2), ("BTN", 2), ("BWP", 2),
+    ("BYN", 2), ("BZD", 2), ("CAD", 2), ("CDF", 2), ("CHE", 2), ("CHF", 2), ("CHW", 2), ("CLF", 4),
+    ("CLP", 0), ("CNY", 2), ("COP", 2), ("COU", 2), ("CRC", 2), ("CUC", 2), ("CUP", 2), ("CVE", 2),
+    ("CZK", 2), ("DJF", 0), ("DKK", 2), ("DOP", 2), ("DZD", 2), ("EGP", 2), ("ERN", 2), ("ETB", 2),
+    ("EUR", 2), ("FJD", 2), ("FKP", 2), ("GBP", 2), ("GEL", 2), ("GHS", 2), ("GIP", 2), ("GMD", 2),
+    ("GNF", 0), ("GTQ", 2), ("GYD", 2), ("HKD", 2), ("HNL", 2), ("HTG", 2), ("HUF", 2), ("IDR", 2),
+    ("ILS", 2), ("INR", 2), ("IQD", 3), ("IRR", 2), ("ISK", 0), ("JMD", 2), ("JOD", 3), ("JPY", 0),
+    ("KES", 2), ("KGS", 2), ("KHR", 2), ("KMF", 0), ("KPW", 2), ("KRW", 0), ("KWD", 3), ("KYD", 2),
+    ("KZT", 2), ("LAK", 2), ("LBP", 2), ("LKR", 2), ("LRD", 2), ("LSL", 2), ("LYD", 3), ("MAD", 2),
+    ("MDL", 2), ("MGA", 2), ("MKD", 2), ("MMK", 2), ("MNT", 2), ("MOP", 2), ("MRU", 2), ("MUR", 2),
+    ("MVR", 2), ("MWK", 2), ("MXN", 2), ("MXV", 2), ("MYR", 2), ("MZN", 2), ("NAD", 2), ("NGN", 2),
+    ("NIO", 2), ("NOK", 2), ("NPR", 2), ("NZD", 2), ("OMR", 3), ("PAB", 2), ("PEN", 2), ("PGK", 2),
+    ("PHP", 2), ("PKR", 2), ("PLN", 2), ("PYG", 0), ("QAR", 2), ("RON", 2), ("RSD", 2), ("RUB", 2),
+    ("RWF", 0), ("SAR", 2), ("SBD", 2), ("SCR", 2), ("SDG", 2), ("SEK", 2), ("SGD", 2), ("SHP", 2),
+    ("SLE", 2), ("SOS", 2), ("SRD", 2), ("SSP", 2), ("STN", 2), ("SVC", 2), ("SYP", 2), ("SZL", 2),
+    ("THB", 2), ("TJS", 2), ("TMT", 2), ("TND", 3), ("TOP", 2), ("TRY", 2), ("TTD", 2), ("TWD", 2),
+    ("TZS", 2), ("UAH", 2), ("UGX", 0), ("USD", 2), ("USN", 2), ("UYI", 0), ("UYU", 2), ("UYW", 4),
+    ("UZS", 2), ("VED", 2), ("VES", 2), ("VND", 0), ("VUV", 0), ("WST", 2), ("XAF", 0), ("XCD", 2),
+    ("XCG", 2), ("XOF", 0), ("XPF", 0), ("YER", 2), ("ZAR", 2), ("ZMW", 2), ("ZWG", 2),
+];
 
-impl Money {
-    pub fn new(value: f64) -> Result<Self, DomainError> {
-        if value < 0.0 {
-            return Err(DomainError::InvalidMoney("Price cannot be negative".to_string()));
-        }
-        if value > 999999.99 {
-            return Err(DomainError::InvalidMoney("Price too high".to_string()));
+/// Currency Value Object - ISO-4217 alphabetic code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency([u8; 3]);
+
+impl Currency {
+    pub const USD: Currency = Currency(*b"USD");
+
+    pub fn new(code: &str) -> Result<Self, DomainError> {
+        let code = code.trim().to_ascii_uppercase();
+        let bytes = code.as_bytes();
+        if bytes.len() != 3 || !bytes.iter().all(u8::is_ascii_uppercase) || Self::lookup(&code).is_none() {
+            return Err(DomainError::InvalidCurrency(format!("'{}' is not an ISO-4217 code", code)));
         }
-        Ok(Self((value * 100.0).round() / 100.0)) // Round to 2 decimal places
+        Ok(Self([bytes[0], bytes[1], bytes[2]]))
     }
 
-    pub fn value(&self) -> f64 {
-        self.0
+    pub fn code(&self) -> &str {
+        // Only ASCII uppercase letters are ever stored
+        std::str::from_utf8(&self.0).unwrap_or("XXX")
+    }
+
+    /// Number of decimal places in the currency's minor unit
+    pub fn minor_units(&self) -> u32 {
+        // Only codes found in the table are ever stored
+        Self::lookup(self.code()).unwrap_or(2)
+    }
+
+    fn lookup(code: &str) -> Option<u32> {
+        ISO_4217
+            .binary_search_by_key(&code, |(known, _)| known)
+            .ok()
+            .map(|index| ISO_4217[index].1)
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Self::USD
     }
 }
 
-impl TryFrom<f64> for Money {
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl TryFrom<&str> for Currency {
     type Error = DomainError;
 
-    fn try_from(value: f64) -> Result<Self, Self::Error> {
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
         Self::new(value)
     }
 }
 
+/// Money Value Object - integer amount in the currency's minor unit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Money {
+    amount_minor: i64,
+    currency: Currency,
+}
+
+impl Money {
+    /// Upper bound for a single price, in minor units. The ceiling in major units therefore
+    /// depends on the currency: 999,999.99 USD, 99,999,999 JPY or 99,999.999 KWD.
+    pub const MAX_PRICE_MINOR: i64 = 99_999_999;
+
+    pub fn new(amount_minor: i64, currency: Currency) -> Result<Self, DomainError> {
+        if amount_minor < 0 {
+            return Err(DomainError::NegativePrice);
+        }
+        if amount_minor > Self::MAX_PRICE_MINOR {
+            return Err(Self::too_high(currency));
+        }
+        Ok(Self { amount_minor, currency })
+    }
+
+    /// Build from a decimal amount such as `19.99`, rounding to the nearest minor unit
+    pub fn from_decimal(value: f64, currency: Currency) -> Result<Self, DomainError> {
+        if !value.is_finite() {
+            return Err(DomainError::PriceNotANumber);
+        }
+        if value < 0.0 {
+            return Err(DomainError::NegativePrice);
+        }
+        let scaled = (value * 10f64.powi(currency.minor_units() as i32)).round();
+        if scaled > Self::MAX_PRICE_MINOR as f64 {
+            return Err(Self::too_high(currency));
+        }
+        Self::new(scaled as i64, currency)
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self { amount_minor: 0, currency }
+    }
+
+    pub fn amount_minor(&self) -> i64 {
+        self.amount_minor
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Decimal representation, for display and legacy JSON fields only
+    pub fn to_decimal(&self) -> f64 {
+        self.amount_minor as f64 / 10f64.powi(self.currency.minor_units() as i32)
+    }
+
+    pub fn checked_add(&self, other: &Money) -> Result<Money, DomainError> {
+        self.ensure_same_currency(other)?;
+        let amount_minor = self.amount_minor
+            .checked_add(other.amount_minor)
+            .ok_or_else(|| DomainError::InvalidMoney("Amount overflow".to_string()))?;
+        Ok(Self { amount_minor, currency: self.currency })
+    }
+
+    #[allow(dead_code)]
+    pub fn checked_sub(&self, other: &Money) -> Result<Money, DomainError> {
+        self.ensure_same_currency(other)?;
+        let amount_minor = self.amount_minor
+            .checked_sub(other.amount_minor)
+            .filter(|amount| *amount >= 0)
+            .ok_or_else(|| DomainError::InvalidMoney("Amount cannot become negative".to_string()))?;
+        Ok(Self { amount_minor, currency: self.currency })
+    }
+
+    /// Multiply a unit price by a quantity
+    pub fn checked_mul(&self, quantity: u32) -> Result<Money, DomainError> {
+        let amount_minor = self.amount_minor
+            .checked_mul(i64::from(quantity))
+            .ok_or_else(|| DomainError::InvalidMoney("Amount overflow".to_string()))?;
+        Ok(Self { amount_minor, currency: self.currency })
+    }
+
+    /// Split the amount proportionally to `ratios` without losing a minor unit.
+    /// Remainders are handed out one unit at a time, starting with the first share;
+    /// for a negative amount the units taken away go the same way.
+    #[allow(dead_code)]
+    pub fn allocate(&self, ratios: &[u32]) -> Result<Vec<Money>, DomainError> {
+        let total: u64 = ratios.iter().map(|r| u64::from(*r)).sum();
+        if total == 0 {
+            return Err(DomainError::InvalidMoney("Allocation ratios must not all be zero".to_string()));
+        }
+
+        let amount = i128::from(self.amount_minor);
+        let mut shares: Vec<i64> = ratios
+            .iter()
+            .map(|ratio| (amount * i128::from(*ratio) / i128::from(total)) as i64)
+            .collect();
+
+        // Division truncates toward zero, so the remainder has the amount's sign and is
+        // smaller than the number of non-zero ratios
+        let mut remainder = self.amount_minor - shares.iter().sum::<i64>();
+        let step = remainder.signum();
+        for (share, ratio) in shares.iter_mut().zip(ratios) {
+            if remainder == 0 {
+                break;
+            }
+            if *ratio > 0 {
+                *share += step;
+                remainder -= step;
+            }
+        }
+
+        Ok(shares
+            .into_iter()
+            .map(|amount_minor| Self { amount_minor, currency: self.currency })
+            .collect())
+    }
+
+    /// Split the amount into `parts` near-equal shares
+    #[allow(dead_code)]
+    pub fn split(&self, parts: u32) -> Result<Vec<Money>, DomainError> {
+        if parts == 0 {
+            return Err(DomainError::InvalidMoney("Cannot split into zero parts".to_string()));
+        }
+        self.allocate(&vec![1; parts as usize])
+    }
+
+    fn too_high(currency: Currency) -> DomainError {
+        DomainError::PriceTooHigh(Self { amount_minor: Self::MAX_PRICE_MINOR, currency }.to_string())
+    }
+
+    fn ensure_same_currency(&self, other: &Money) -> Result<(), DomainError> {
+        if self.currency != other.currency {
+            return Err(DomainError::CurrencyMismatch(
+                self.currency.code().to_string(),
+                other.currency.code().to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let precision = self.currency.minor_units();
+        if precision == 0 {
+            return write!(f, "{} {}", self.amount_minor, self.currency);
+        }
+        let sign = if self.amount_minor < 0 { "-" } else { "" };
+        let amount = self.amount_minor.unsigned_abs();
+        let scale = 10u64.pow(precision);
+        write!(
+            f,
+            "{}{}.{:0width$} {}",
+            sign,
+            amount / scale,
+            amount % scale,
+            self.currency,
+            width = precision as usize
+        )
+    }
+}
+
 /// Stock Quantity Value Object
 #[derive(Debug, Clone, PartialEq)]
 pub struct StockQuantity(i32);
@@ -152,7 +568,7 @@ pub struct StockQuantity(i32);
 impl StockQuantity {
     pub fn new(value: i32) -> Result<Self, DomainError> {
         if value < 0 {
-            return Err(DomainError::InvalidStock("Stock cannot be negative".to_string()));
+            return Err(DomainError::NegativeStock);
         }
         Ok(Self(value))
     }
@@ -166,7 +582,6 @@ impl StockQuantity {
         self.0 > 0
     }
 
-    #[allow(dead_code)]
     pub fn decrease(&mut self, amount: i32) -> Result<(), DomainError> {
         if amount < 0 {
             return Err(DomainError::InvalidStock("Decrease amount cannot be negative".to_string()));
@@ -178,12 +593,12 @@ impl StockQuantity {
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub fn increase(&mut self, amount: i32) -> Result<(), DomainError> {
         if amount < 0 {
             return Err(DomainError::InvalidStock("Increase amount cannot be negative".to_string()));
         }
-        self.0 += amount;
+        self.0 = self.0.checked_add(amount)
+            .ok_or_else(|| DomainError::InvalidStock("Stock too high".to_string()))?;
         Ok(())
     }
 }
@@ -201,22 +616,57 @@ impl TryFrom<i32> for StockQuantity {
 pub enum DomainError {
     #[error("Invalid product ID")]
     InvalidProductId,
-    #[error("Invalid product name: {0}")]
-    InvalidProductName(String),
+    #[error("Product name cannot be empty")]
+    EmptyProductName,
+    #[error("Product name cannot exceed {0} characters")]
+    ProductNameTooLong(usize),
+    #[error("Price must be a number")]
+    PriceNotANumber,
+    #[error("Price cannot be negative")]
+    NegativePrice,
+    #[error("Price cannot exceed {0}")]
+    PriceTooHigh(String),
     #[error("Invalid money value: {0}")]
     InvalidMoney(String),
+    #[error("Invalid currency: {0}")]
+    InvalidCurrency(String),
+    #[error("Currency mismatch: {0} vs {1}")]
+    CurrencyMismatch(String, String),
+    #[error("Stock cannot be negative")]
+    NegativeStock,
     #[error("Invalid stock value: {0}")]
     InvalidStock(String),
-    #[allow(dead_code)]
+    #[error("Invalid webhook: {0}")]
+    InvalidWebhook(String),
     #[error("Insufficient stock available")]
     InsufficientStock,
+    #[error("Invalid reservation: {0}")]
+    InvalidReservation(String),
+    #[error("Invalid cart: {0}")]
+    InvalidCart(String),
+    #[error("Invalid order: {0}")]
+    InvalidOrder(String),
+    #[error("{0}")]
+    InvalidUser(String),
+    #[error("Invalid API key: {0}")]
+    InvalidApiKey(String),
+    #[error("Invalid tag: {0}")]
+    InvalidTag(String),
+    #[error("Invalid attribute: {0}")]
+    InvalidAttribute(String),
+    #[error("Invalid category: {0}")]
+    InvalidCategory(String),
+    #[error(
+        "Category still has {products} product(s) and {subcategories} subcategory(ies); \
+         move or unassign them before deleting it"
+    )]
+    CategoryNotEmpty { products: usize, subcategories: usize },
     #[allow(dead_code)]
     #[error("Product not found")]
     ProductNotFound,
 }
 
 /// Product Domain Events
-#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub enum ProductEvent {
     ProductCreated {
@@ -236,4 +686,105 @@ pub enum ProductEvent {
         old_stock: StockQuantity,
         new_stock: StockQuantity,
     },
-}
\ No newline at end of file
+}
+
+impl ProductEvent {
+    /// Stable dotted name, e.g. `product.created`
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            ProductEvent::ProductCreated { .. } => "product.created",
+            ProductEvent::ProductUpdated { .. } => "product.updated",
+            ProductEvent::ProductDeleted { .. } => "product.deleted",
+            ProductEvent::StockChanged { .. } => "product.stock_changed",
+        }
+    }
+
+    pub fn product_id(&self) -> &ProductId {
+        match self {
+            ProductEvent::ProductCreated { product_id, .. }
+            | ProductEvent::ProductUpdated { product_id, .. }
+            | ProductEvent::ProductDeleted { product_id }
+            | ProductEvent::StockChanged { product_id, .. } => product_id,
+        }
+    }
+
+    /// The same event about the product stored under `id`, for products whose id is
+    /// only assigned once they are saved
+    pub fn with_product_id(mut self, id: ProductId) -> Self {
+        match &mut self {
+            ProductEvent::ProductCreated { product_id, .. }
+            | ProductEvent::ProductUpdated { product_id, .. }
+            | ProductEvent::ProductDeleted { product_id }
+            | ProductEvent::StockChanged { product_id, .. } => *product_id = id,
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usd(amount_minor: i64) -> Money {
+        Money::new(amount_minor, Currency::USD).unwrap()
+    }
+
+    fn amounts(shares: &[Money]) -> Vec<i64> {
+        shares.iter().map(Money::amount_minor).collect()
+    }
+
+    #[test]
+    fn arithmetic_is_checked() {
+        assert_eq!(usd(150).checked_add(&usd(250)).unwrap(), usd(400));
+        assert_eq!(usd(400).checked_sub(&usd(150)).unwrap(), usd(250));
+        assert_eq!(usd(1_999).checked_mul(3).unwrap(), usd(5_997));
+
+        assert!(matches!(usd(100).checked_sub(&usd(101)), Err(DomainError::InvalidMoney(_))));
+        let huge = usd(Money::MAX_PRICE_MINOR).checked_mul(u32::MAX).unwrap().checked_mul(20).unwrap();
+        assert!(matches!(huge.checked_add(&huge), Err(DomainError::InvalidMoney(_))));
+        assert!(matches!(huge.checked_mul(2), Err(DomainError::InvalidMoney(_))));
+
+        let euros = Money::new(100, Currency::new("EUR").unwrap()).unwrap();
+        for result in [usd(100).checked_add(&euros), usd(100).checked_sub(&euros)] {
+            assert!(matches!(result, Err(DomainError::CurrencyMismatch(a, b)) if a == "USD" && b == "EUR"));
+        }
+    }
+
+    #[test]
+    fn allocation_never_loses_a_minor_unit() {
+        assert_eq!(amounts(&usd(100).split(3).unwrap()), vec![34, 33, 33]);
+        assert_eq!(amounts(&usd(5).allocate(&[3, 7]).unwrap()), vec![2, 3]);
+        // Zero ratios get nothing, not even remainder units
+        assert_eq!(amounts(&usd(101).allocate(&[0, 1, 1]).unwrap()), vec![0, 51, 50]);
+        assert_eq!(amounts(&usd(2).split(4).unwrap()), vec![1, 1, 0, 0]);
+
+        let refund = Money { amount_minor: -100, currency: Currency::USD };
+        assert_eq!(amounts(&refund.split(3).unwrap()), vec![-34, -33, -33]);
+
+        assert!(matches!(usd(100).split(0), Err(DomainError::InvalidMoney(_))));
+        assert!(matches!(usd(100).allocate(&[0, 0]), Err(DomainError::InvalidMoney(_))));
+    }
+
+    #[test]
+    fn only_iso_4217_codes_are_currencies() {
+        assert_eq!(Currency::new(" eur ").unwrap().code(), "EUR");
+        assert_eq!(Currency::new("JPY").unwrap().minor_units(), 0);
+        assert_eq!(Currency::new("KWD").unwrap().minor_units(), 3);
+        for code in ["ZZZ", "ABC", "US", "USDX", "U$D"] {
+            assert!(matches!(Currency::new(code), Err(DomainError::InvalidCurrency(_))), "{}", code);
+        }
+        assert!(ISO_4217.windows(2).all(|pair| pair[0].0 < pair[1].0), "table must stay sorted");
+    }
+
+    #[test]
+    fn display_puts_the_sign_before_the_whole_amount() {
+        let money = |amount_minor, code| Money { amount_minor, currency: Currency::new(code).unwrap() };
+
+        assert_eq!(usd(1999).to_string(), "19.99 USD");
+        assert_eq!(money(-150, "USD").to_string(), "-1.50 USD");
+        assert_eq!(money(-5, "USD").to_string(), "-0.05 USD");
+        assert_eq!(money(-1500, "KWD").to_string(), "-1.500 KWD");
+        assert_eq!(money(-900, "JPY").to_string(), "-900 JPY");
+        assert_eq!(money(i64::MIN, "USD").to_string(), "-92233720368547758.08 USD");
+    }
+}
@@ -0,0 +1,271 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::{DomainError, Product, ProductEvent, ProductId};
+use crate::domain::repositories::RepositoryError;
+
+/// Why stock on hand changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StockMovementReason {
+    Sale,
+    Restock,
+    Correction,
+    Return,
+}
+
+impl StockMovementReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StockMovementReason::Sale => "sale",
+            StockMovementReason::Restock => "restock",
+            StockMovementReason::Correction => "correction",
+            StockMovementReason::Return => "return",
+        }
+    }
+
+    /// Sales only remove stock, restocks and returns only add it, corrections go either way
+    pub fn allows(&self, delta: i32) -> bool {
+        match self {
+            StockMovementReason::Sale => delta < 0,
+            StockMovementReason::Restock | StockMovementReason::Return => delta > 0,
+            StockMovementReason::Correction => delta != 0,
+        }
+    }
+}
+
+impl TryFrom<&str> for StockMovementReason {
+    type Error = DomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "sale" => Ok(StockMovementReason::Sale),
+            "restock" => Ok(StockMovementReason::Restock),
+            "correction" => Ok(StockMovementReason::Correction),
+            "return" => Ok(StockMovementReason::Return),
+            other => Err(DomainError::InvalidStock(format!(
+                "Unknown reason '{}', expected sale, restock, correction or return",
+                other
+            ))),
+        }
+    }
+}
+
+/// One entry of the stock ledger
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockMovement {
+    pub delta: i32,
+    pub reason: StockMovementReason,
+    /// Who made the change
+    pub actor: String,
+    pub note: Option<String>,
+    /// Stock on hand right after this movement
+    pub stock_after: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A product whose stock on hand differs from the sum of its ledger entries
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockDiscrepancy {
+    pub product_id: ProductId,
+    pub stock: i32,
+    pub ledger_total: i64,
+}
+
+/// Reservation ID Value Object
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ReservationId(i64);
+
+impl ReservationId {
+    pub fn new(value: i64) -> Result<Self, DomainError> {
+        if value <= 0 {
+            return Err(DomainError::InvalidReservation("Invalid reservation ID".to_string()));
+        }
+        Ok(Self(value))
+    }
+
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+}
+
+/// Lifecycle of a stock reservation. Only `Active` reservations hold stock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReservationStatus {
+    Active,
+    Committed,
+    Released,
+    Expired,
+}
+
+impl ReservationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReservationStatus::Active => "active",
+            ReservationStatus::Committed => "committed",
+            ReservationStatus::Released => "released",
+            ReservationStatus::Expired => "expired",
+        }
+    }
+}
+
+impl TryFrom<&str> for ReservationStatus {
+    type Error = DomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "active" => Ok(ReservationStatus::Active),
+            "committed" => Ok(ReservationStatus::Committed),
+            "released" => Ok(ReservationStatus::Released),
+            "expired" => Ok(ReservationStatus::Expired),
+            other => Err(DomainError::InvalidReservation(format!("Unknown status '{}'", other))),
+        }
+    }
+}
+
+/// Stock Reservation Entity - units held for a buyer until committed, released or expired
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockReservation {
+    id: ReservationId,
+    product_id: ProductId,
+    quantity: i32,
+    status: ReservationStatus,
+    expires_at: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl StockReservation {
+    pub fn new(
+        id: ReservationId,
+        product_id: ProductId,
+        quantity: i32,
+        ttl: Duration,
+    ) -> Result<Self, DomainError> {
+        if quantity <= 0 {
+            return Err(DomainError::InvalidReservation("Quantity must be positive".to_string()));
+        }
+        if ttl <= Duration::zero() {
+            return Err(DomainError::InvalidReservation("Expiry must be in the future".to_string()));
+        }
+
+        let now = Utc::now();
+        Ok(Self {
+            id,
+            product_id,
+            quantity,
+            status: ReservationStatus::Active,
+            expires_at: now + ttl,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Rebuild a reservation from persisted state
+    pub fn reconstitute(
+        id: ReservationId,
+        product_id: ProductId,
+        quantity: i32,
+        status: ReservationStatus,
+        expires_at: DateTime<Utc>,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    ) -> Self {
+        Self { id, product_id, quantity, status, expires_at, created_at, updated_at }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Confirm the sale. Expired reservations can no longer be committed.
+    pub fn commit(&mut self, now: DateTime<Utc>) -> Result<(), DomainError> {
+        self.ensure_active()?;
+        if self.is_expired(now) {
+            return Err(DomainError::InvalidReservation("Reservation has expired".to_string()));
+        }
+        self.transition(ReservationStatus::Committed, now);
+        Ok(())
+    }
+
+    pub fn release(&mut self, now: DateTime<Utc>) -> Result<(), DomainError> {
+        self.ensure_active()?;
+        self.transition(ReservationStatus::Released, now);
+        Ok(())
+    }
+
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Result<(), DomainError> {
+        self.ensure_active()?;
+        if !self.is_expired(now) {
+            return Err(DomainError::InvalidReservation("Reservation has not expired yet".to_string()));
+        }
+        self.transition(ReservationStatus::Expired, now);
+        Ok(())
+    }
+
+    fn ensure_active(&self) -> Result<(), DomainError> {
+        if self.status != ReservationStatus::Active {
+            return Err(DomainError::InvalidReservation(format!(
+                "Reservation is already {}",
+                self.status.as_str()
+            )));
+        }
+        Ok(())
+    }
+
+    fn transition(&mut self, status: ReservationStatus, now: DateTime<Utc>) {
+        self.status = status;
+        self.updated_at = now;
+    }
+
+    // Getters
+    pub fn id(&self) -> &ReservationId { &self.id }
+    pub fn product_id(&self) -> &ProductId { &self.product_id }
+    pub fn quantity(&self) -> i32 { self.quantity }
+    pub fn status(&self) -> ReservationStatus { self.status }
+    pub fn expires_at(&self) -> DateTime<Utc> { self.expires_at }
+    pub fn created_at(&self) -> DateTime<Utc> { self.created_at }
+    pub fn updated_at(&self) -> DateTime<Utc> { self.updated_at }
+}
+
+/// Repository trait for stock reservations.
+///
+/// Every write stores the reservation together with the product's stock counters,
+/// under the same version check as [`ProductRepository::update`](crate::domain::ProductRepository::update).
+#[async_trait]
+pub trait InventoryRepository: Send + Sync {
+    async fn find_reservation(&self, id: &ReservationId) -> Result<Option<StockReservation>, RepositoryError>;
+
+    /// Active reservations whose expiry has passed, optionally for one product
+    async fn find_expired_reservations(
+        &self,
+        now: DateTime<Utc>,
+        product_id: Option<&ProductId>,
+        limit: usize,
+    ) -> Result<Vec<StockReservation>, RepositoryError>;
+
+    /// Store a new reservation and the product holding its units
+    async fn create_reservation(
+        &self,
+        product: Product,
+        reservation: StockReservation,
+    ) -> Result<(Product, StockReservation), RepositoryError>;
+
+    /// Store a reservation that left the active state, the product's counters and
+    /// `events`. Fails with `ConcurrentModification` if either changed meanwhile.
+    async fn update_reservation(
+        &self,
+        product: Product,
+        reservation: StockReservation,
+        events: &[ProductEvent],
+    ) -> Result<(Product, StockReservation), RepositoryError>;
+
+    /// Most recent ledger entries for a product, newest first
+    async fn find_stock_movements(&self, product_id: &ProductId, limit: usize) -> Result<Vec<StockMovement>, RepositoryError>;
+
+    /// Products whose stock does not match the sum of their ledger entries
+    async fn reconcile_stock(&self) -> Result<Vec<StockDiscrepancy>, RepositoryError>;
+
+    /// Get next available ID
+    async fn next_reservation_id(&self) -> Result<ReservationId, RepositoryError>;
+}
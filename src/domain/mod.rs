@@ -1,5 +1,21 @@
+pub mod api_keys;
+pub mod attributes;
+pub mod carts;
+pub mod categories;
 pub mod entities;
+pub mod inventory;
+pub mod orders;
 pub mod repositories;
+pub mod users;
+pub mod webhooks;
 
+pub use api_keys::*;
+pub use attributes::*;
+pub use carts::*;
+pub use categories::*;
 pub use entities::*;
-pub use repositories::*;
\ No newline at end of file
+pub use inventory::*;
+pub use orders::*;
+pub use repositories::*;
+pub use users::*;
+pub use webhooks::*;
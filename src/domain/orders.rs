@@ -0,0 +1,262 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::carts::Cart;
+use crate::domain::entities::{Currency, DomainError, Money, Product, ProductEvent, ProductId};
+use crate::domain::repositories::RepositoryError;
+use crate::domain::users::UserId;
+
+/// Order ID Value Object
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OrderId(i64);
+
+impl OrderId {
+    pub fn new(value: i64) -> Result<Self, DomainError> {
+        if value <= 0 {
+            return Err(DomainError::InvalidOrder("Invalid order ID".to_string()));
+        }
+        Ok(Self(value))
+    }
+
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+}
+
+/// Order lifecycle: pending → paid → shipped → delivered. Orders can be cancelled
+/// until they ship; once paid, only by staff, who arrange the refund.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    Pending,
+    Paid,
+    Shipped,
+    Delivered,
+    Cancelled,
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Paid => "paid",
+            OrderStatus::Shipped => "shipped",
+            OrderStatus::Delivered => "delivered",
+            OrderStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn can_transition_to(&self, next: OrderStatus) -> bool {
+        matches!(
+            (self, next),
+            (OrderStatus::Pending, OrderStatus::Paid)
+                | (OrderStatus::Paid, OrderStatus::Shipped)
+                | (OrderStatus::Shipped, OrderStatus::Delivered)
+                | (OrderStatus::Pending | OrderStatus::Paid, OrderStatus::Cancelled)
+        )
+    }
+}
+
+impl TryFrom<&str> for OrderStatus {
+    type Error = DomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "pending" => Ok(OrderStatus::Pending),
+            "paid" => Ok(OrderStatus::Paid),
+            "shipped" => Ok(OrderStatus::Shipped),
+            "delivered" => Ok(OrderStatus::Delivered),
+            "cancelled" => Ok(OrderStatus::Cancelled),
+            other => Err(DomainError::InvalidOrder(format!("Unknown status '{}'", other))),
+        }
+    }
+}
+
+/// Whose orders a request may see: those placed from its cart session and, for a signed-in
+/// customer, every order they placed while signed in, whichever session it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderOwner {
+    pub session_token: String,
+    pub user_id: Option<UserId>,
+}
+
+impl OrderOwner {
+    pub fn new(session_token: &str, user_id: Option<UserId>) -> Self {
+        Self { session_token: session_token.to_string(), user_id }
+    }
+}
+
+/// One product of an order, with the name and price it was sold under
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderLine {
+    product_id: ProductId,
+    product_name: String,
+    quantity: i32,
+    unit_price: Money,
+}
+
+impl OrderLine {
+    pub fn new(product: &Product, quantity: i32, unit_price: Money) -> Result<Self, DomainError> {
+        if quantity <= 0 {
+            return Err(DomainError::InvalidOrder("Quantity must be positive".to_string()));
+        }
+        Ok(Self {
+            product_id: product.id().clone(),
+            product_name: product.name().value().to_string(),
+            quantity,
+            unit_price,
+        })
+    }
+
+    /// Rebuild a line from persisted state
+    pub fn reconstitute(product_id: ProductId, product_name: String, quantity: i32, unit_price: Money) -> Self {
+        Self { product_id, product_name, quantity, unit_price }
+    }
+
+    /// Unit price times quantity
+    pub fn subtotal(&self) -> Result<Money, DomainError> {
+        self.unit_price.checked_mul(self.quantity as u32)
+    }
+
+    // Getters
+    pub fn product_id(&self) -> &ProductId { &self.product_id }
+    pub fn product_name(&self) -> &str { &self.product_name }
+    pub fn quantity(&self) -> i32 { self.quantity }
+    pub fn unit_price(&self) -> &Money { &self.unit_price }
+}
+
+/// Order Aggregate - products bought together by one session, priced in one currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct Order {
+    id: OrderId,
+    session_token: String,
+    /// The customer who was signed in when the order was placed
+    user_id: Option<UserId>,
+    status: OrderStatus,
+    lines: Vec<OrderLine>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl Order {
+    /// Start a pending order for `owner`. It needs at least one line and a single currency.
+    pub fn place(id: OrderId, owner: &OrderOwner, lines: Vec<OrderLine>) -> Result<Self, DomainError> {
+        let Some(first) = lines.first() else {
+            return Err(DomainError::InvalidOrder("An order needs at least one item".to_string()));
+        };
+        let currency = first.unit_price.currency();
+        if let Some(line) = lines.iter().find(|line| line.unit_price.currency() != currency) {
+            return Err(DomainError::CurrencyMismatch(
+                currency.code().to_string(),
+                line.unit_price.currency().code().to_string(),
+            ));
+        }
+
+        let now = Utc::now();
+        let order = Self {
+            id,
+            session_token: owner.session_token.clone(),
+            user_id: owner.user_id.clone(),
+            status: OrderStatus::Pending,
+            lines,
+            created_at: now,
+            updated_at: now,
+        };
+        order.total()?;
+        Ok(order)
+    }
+
+    /// Rebuild an order from persisted state
+    pub fn reconstitute(
+        id: OrderId,
+        session_token: String,
+        user_id: Option<UserId>,
+        status: OrderStatus,
+        lines: Vec<OrderLine>,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    ) -> Self {
+        Self { id, session_token, user_id, status, lines, created_at, updated_at }
+    }
+
+    /// Whether `owner` placed this order, from the same session or signed in as the same customer
+    pub fn belongs_to(&self, owner: &OrderOwner) -> bool {
+        self.session_token == owner.session_token
+            || owner.user_id.as_ref().is_some_and(|user_id| self.user_id.as_ref() == Some(user_id))
+    }
+
+    /// Move the order to `next`. Returns the status it had before.
+    pub fn transition_to(&mut self, next: OrderStatus) -> Result<OrderStatus, DomainError> {
+        if !self.status.can_transition_to(next) {
+            return Err(DomainError::InvalidOrder(format!(
+                "A {} order cannot become {}",
+                self.status.as_str(),
+                next.as_str()
+            )));
+        }
+        let previous = self.status;
+        self.status = next;
+        self.updated_at = Utc::now();
+        Ok(previous)
+    }
+
+    /// Sum of all line subtotals
+    pub fn total(&self) -> Result<Money, DomainError> {
+        self.lines.iter().try_fold(Money::zero(self.currency()), |total, line| {
+            total.checked_add(&line.subtotal()?)
+        })
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.lines.first().map_or_else(Currency::default, |line| line.unit_price.currency())
+    }
+
+    /// Total number of units across all lines
+    pub fn item_count(&self) -> i32 {
+        self.lines.iter().map(OrderLine::quantity).sum()
+    }
+
+    // Getters
+    pub fn id(&self) -> &OrderId { &self.id }
+    pub fn session_token(&self) -> &str { &self.session_token }
+    pub fn user_id(&self) -> Option<&UserId> { self.user_id.as_ref() }
+    pub fn status(&self) -> OrderStatus { self.status }
+    pub fn lines(&self) -> &[OrderLine] { &self.lines }
+    pub fn created_at(&self) -> DateTime<Utc> { self.created_at }
+    pub fn updated_at(&self) -> DateTime<Utc> { self.updated_at }
+}
+
+/// Repository trait for orders.
+///
+/// Writes store the order together with the products whose stock it changed and their
+/// events, under the same version check as [`ProductRepository::update`](crate::domain::ProductRepository::update).
+#[async_trait]
+pub trait OrderRepository: Send + Sync {
+    async fn find_by_id(&self, id: &OrderId) -> Result<Option<Order>, RepositoryError>;
+
+    /// Most recent orders that belong to `owner`, newest first
+    async fn find_by_owner(&self, owner: &OrderOwner, limit: usize) -> Result<Vec<Order>, RepositoryError>;
+
+    /// Store a new order and the products it sold, and empty the `cart` it was placed from.
+    /// Fails with `ConcurrentModification` if a product or the stored cart changed meanwhile.
+    async fn place(
+        &self,
+        order: Order,
+        cart: &Cart,
+        products: &[Product],
+        events: &[ProductEvent],
+    ) -> Result<Order, RepositoryError>;
+
+    /// Store a status change and any products it restocked. Fails with `ConcurrentModification`
+    /// if the stored order no longer has status `expected` or a product changed meanwhile.
+    async fn update_status(
+        &self,
+        order: Order,
+        expected: OrderStatus,
+        products: &[Product],
+        events: &[ProductEvent],
+    ) -> Result<Order, RepositoryError>;
+
+    /// Get next available ID
+    async fn next_id(&self) -> Result<OrderId, RepositoryError>;
+}
@@ -1,5 +1,118 @@
+use std::collections::BTreeMap;
+
 use async_trait::async_trait;
-use crate::domain::entities::{Product, ProductId, DomainError};
+use chrono::{DateTime, Utc};
+use crate::domain::entities::{Product, ProductId, Currency, DomainError, ProductEvent};
+
+/// Field a product listing can be ordered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProductSortField {
+    #[default]
+    CreatedAt,
+    /// Best full-text match first; without a search it orders like `CreatedAt`
+    Relevance,
+    Name,
+    Price,
+    Stock,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Inclusive price bounds in minor units of a single currency.
+/// Prices in other currencies never match a price range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceRange {
+    pub currency: Currency,
+    pub min_minor: Option<i64>,
+    pub max_minor: Option<i64>,
+}
+
+/// Filtering, ordering and paging options for product listings
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductQuery {
+    pub search: Option<String>,
+    /// Slug of a category; products in it or any of its subcategories match
+    pub category: Option<String>,
+    /// Products carrying any of these tags
+    pub tags: Vec<String>,
+    /// For each attribute key, products whose value is any of those listed. Different
+    /// keys must all match.
+    pub attributes: BTreeMap<String, Vec<String>>,
+    pub price: Option<PriceRange>,
+    pub in_stock_only: bool,
+    pub created_after: Option<DateTime<Utc>>,
+    pub sort_by: ProductSortField,
+    pub direction: SortDirection,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Default for ProductQuery {
+    fn default() -> Self {
+        Self {
+            search: None,
+            category: None,
+            tags: Vec::new(),
+            attributes: BTreeMap::new(),
+            price: None,
+            in_stock_only: false,
+            created_after: None,
+            sort_by: ProductSortField::default(),
+            direction: SortDirection::default(),
+            offset: 0,
+            limit: 20,
+        }
+    }
+}
+
+/// Marks the start of a matched term in `SearchHighlight` text
+pub const HIGHLIGHT_START: char = '\u{2}';
+/// Marks the end of a matched term in `SearchHighlight` text
+pub const HIGHLIGHT_END: char = '\u{3}';
+
+/// Where a product matched a full-text search. Matched terms are wrapped in
+/// `HIGHLIGHT_START` and `HIGHLIGHT_END`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHighlight {
+    pub product_id: i64,
+    /// The whole name
+    pub name: String,
+    /// A few words of the description around the best match
+    pub snippet: String,
+}
+
+/// One page of products plus the number of products matching the query
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductPage {
+    pub products: Vec<Product>,
+    pub total: usize,
+    /// Highlighted matches for the products on the page, when the search used the
+    /// full-text index
+    pub highlights: Vec<SearchHighlight>,
+}
+
+/// How many products carry one tag or attribute value
+#[derive(Debug, Clone, PartialEq)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Tag and attribute value counts for the products matching a listing, most common first.
+///
+/// Each facet is counted with every filter except its own, so picking a color still shows
+/// how many products the other colors would add.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Facets {
+    pub tags: Vec<FacetCount>,
+    /// Per attribute key, in key order
+    pub attributes: Vec<(String, Vec<FacetCount>)>,
+}
 
 /// Repository trait for Product aggregate
 #[async_trait]
@@ -11,16 +124,32 @@ pub trait ProductRepository: Send + Sync {
     async fn find_by_id(&self, id: &ProductId) -> Result<Option<Product>, RepositoryError>;
     
     /// Search products by name
+    #[allow(dead_code)]
     async fn search_by_name(&self, query: &str) -> Result<Vec<Product>, RepositoryError>;
+
+    /// Find one page of products matching the query's filters, in the query's order
+    async fn find_page(&self, query: &ProductQuery) -> Result<ProductPage, RepositoryError>;
+
+    /// Count tag and attribute values across every product matching the query's filters
+    async fn find_facets(&self, query: &ProductQuery) -> Result<Facets, RepositoryError>;
     
-    /// Save a new product
-    async fn save(&self, product: Product) -> Result<Product, RepositoryError>;
+    /// Save a new product, recording `events` in the same transaction against the
+    /// id the product is given on insert
+    async fn save(&self, product: Product, events: &[ProductEvent]) -> Result<Product, RepositoryError>;
     
-    /// Update existing product
-    async fn update(&self, product: Product) -> Result<Product, RepositoryError>;
+    /// Update existing product if its stored version still equals `product.version()`,
+    /// recording `events` in the same transaction.
+    /// Fails with `ConcurrentModification` when another writer got there first.
+    async fn update(&self, product: Product, events: &[ProductEvent]) -> Result<Product, RepositoryError>;
     
-    /// Delete product by ID
-    async fn delete(&self, id: &ProductId) -> Result<bool, RepositoryError>;
+    /// Delete product by ID, recording `events` in the same transaction if a row was removed.
+    /// With an `expected_version`, fails with `ConcurrentModification` if the stored version differs.
+    async fn delete(
+        &self,
+        id: &ProductId,
+        expected_version: Option<i64>,
+        events: &[ProductEvent],
+    ) -> Result<bool, RepositoryError>;
     
     /// Check if product exists
     async fn exists(&self, id: &ProductId) -> Result<bool, RepositoryError>;
@@ -41,7 +170,6 @@ pub enum RepositoryError {
     NotFound,
     #[error("Constraint violation: {0}")]
     ConstraintViolation(String),
-    #[allow(dead_code)]
     #[error("Concurrent modification detected")]
     ConcurrentModification,
     #[error("Domain error: {0}")]
@@ -0,0 +1,225 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::DomainError;
+use crate::domain::repositories::RepositoryError;
+
+/// Shortest password accepted at registration
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+/// Longest password accepted; hashing cost grows with the input
+pub const MAX_PASSWORD_LENGTH: usize = 128;
+/// Longest display name
+pub const MAX_USER_NAME_LENGTH: usize = 100;
+
+/// User ID Value Object
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct UserId(i64);
+
+impl UserId {
+    pub fn new(value: i64) -> Result<Self, DomainError> {
+        if value <= 0 {
+            return Err(DomainError::InvalidUser("Invalid user ID".to_string()));
+        }
+        Ok(Self(value))
+    }
+
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+}
+
+/// Email Value Object - trimmed and lower-cased, so it can be compared directly
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Email(String);
+
+impl Email {
+    pub fn new(value: &str) -> Result<Self, DomainError> {
+        let value = value.trim().to_lowercase();
+        let valid = value.len() <= 254
+            && !value.chars().any(char::is_whitespace)
+            && value.split_once('@').is_some_and(|(local, domain)| {
+                !local.is_empty()
+                    && !domain.contains('@')
+                    && domain.split('.').count() > 1
+                    && domain.split('.').all(|part| !part.is_empty())
+            });
+        if !valid {
+            return Err(DomainError::InvalidUser("Enter a valid email address".to_string()));
+        }
+        Ok(Self(value))
+    }
+
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
+/// What an account may do with the catalog. New accounts are viewers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Role {
+    Admin,
+    CatalogEditor,
+    #[default]
+    Viewer,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::CatalogEditor => "catalog_editor",
+            Role::Viewer => "viewer",
+        }
+    }
+}
+
+impl TryFrom<&str> for Role {
+    type Error = DomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "admin" => Ok(Role::Admin),
+            "catalog_editor" => Ok(Role::CatalogEditor),
+            "viewer" => Ok(Role::Viewer),
+            other => Err(DomainError::InvalidUser(format!(
+                "Unknown role '{}', expected admin, catalog_editor or viewer",
+                other
+            ))),
+        }
+    }
+}
+
+/// Check a new password against the length policy
+pub fn validate_password(password: &str) -> Result<(), DomainError> {
+    let length = password.chars().count();
+    if length < MIN_PASSWORD_LENGTH {
+        return Err(DomainError::InvalidUser(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LENGTH
+        )));
+    }
+    if length > MAX_PASSWORD_LENGTH {
+        return Err(DomainError::InvalidUser(format!(
+            "Password cannot exceed {} characters",
+            MAX_PASSWORD_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// User Entity - a customer account that signs in with email and password.
+///
+/// Only the password hash is kept; the password itself never reaches the entity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct User {
+    id: UserId,
+    email: Email,
+    name: String,
+    password_hash: String,
+    role: Role,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl User {
+    pub fn register(id: UserId, email: Email, name: &str, password_hash: String) -> Result<Self, DomainError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(DomainError::InvalidUser("Name cannot be empty".to_string()));
+        }
+        if name.chars().count() > MAX_USER_NAME_LENGTH {
+            return Err(DomainError::InvalidUser(format!(
+                "Name cannot exceed {} characters",
+                MAX_USER_NAME_LENGTH
+            )));
+        }
+
+        let now = Utc::now();
+        Ok(Self {
+            id,
+            email,
+            name: name.to_string(),
+            password_hash,
+            role: Role::default(),
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Rebuild a user from persisted state
+    pub fn reconstitute(
+        id: UserId,
+        email: Email,
+        name: String,
+        password_hash: String,
+        role: Role,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    ) -> Self {
+        Self { id, email, name, password_hash, role, created_at, updated_at }
+    }
+
+    pub fn set_role(&mut self, role: Role) {
+        if self.role != role {
+            self.role = role;
+            self.updated_at = Utc::now();
+        }
+    }
+
+    // Getters
+    pub fn id(&self) -> &UserId { &self.id }
+    pub fn email(&self) -> &Email { &self.email }
+    pub fn name(&self) -> &str { &self.name }
+    pub fn password_hash(&self) -> &str { &self.password_hash }
+    pub fn role(&self) -> Role { self.role }
+    pub fn created_at(&self) -> DateTime<Utc> { self.created_at }
+    pub fn updated_at(&self) -> DateTime<Utc> { self.updated_at }
+}
+
+/// Turns passwords into salted hashes and checks passwords against them
+pub trait PasswordHasher: Send + Sync {
+    fn hash(&self, password: &str) -> Result<String, DomainError>;
+
+    /// False for a wrong password or a hash that cannot be read
+    fn verify(&self, password: &str, password_hash: &str) -> bool;
+}
+
+/// Repository trait for user accounts
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn find_by_id(&self, id: &UserId) -> Result<Option<User>, RepositoryError>;
+
+    async fn find_by_email(&self, email: &Email) -> Result<Option<User>, RepositoryError>;
+
+    /// Store a new user. Fails with `ConstraintViolation` if the email is taken.
+    async fn create(&self, user: User) -> Result<User, RepositoryError>;
+
+    /// Store changes to an existing user's name and role
+    async fn update(&self, user: User) -> Result<User, RepositoryError>;
+
+    /// Get next available ID
+    async fn next_id(&self) -> Result<UserId, RepositoryError>;
+}
+
+/// Repository trait for sign-in sessions.
+///
+/// Sessions are looked up by a hash of their token, so a leaked table cannot be
+/// replayed as cookies.
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    async fn create_session(
+        &self,
+        token_hash: &str,
+        user_id: &UserId,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), RepositoryError>;
+
+    /// The user signed in with the session, unless it expired before `now`
+    async fn find_session_user(&self, token_hash: &str, now: DateTime<Utc>) -> Result<Option<User>, RepositoryError>;
+
+    async fn delete_session(&self, token_hash: &str) -> Result<(), RepositoryError>;
+
+    /// Remove sessions that expired before `now`, returning how many were removed
+    async fn delete_expired_sessions(&self, now: DateTime<Utc>) -> Result<u64, RepositoryError>;
+}
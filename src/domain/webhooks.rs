@@ -0,0 +1,244 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::{DomainError, ProductEvent};
+use crate::domain::repositories::RepositoryError;
+
+/// Shortest secret accepted for signing deliveries
+pub const MIN_SECRET_LENGTH: usize = 16;
+
+/// Webhook ID Value Object
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WebhookId(i64);
+
+impl WebhookId {
+    pub fn new(value: i64) -> Result<Self, DomainError> {
+        if value <= 0 {
+            return Err(DomainError::InvalidWebhook("Invalid webhook ID".to_string()));
+        }
+        Ok(Self(value))
+    }
+
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+}
+
+/// Product event kinds a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebhookEventType {
+    ProductCreated,
+    ProductUpdated,
+    ProductDeleted,
+    StockChanged,
+}
+
+impl WebhookEventType {
+    /// Same names as [`ProductEvent::event_type`]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventType::ProductCreated => "product.created",
+            WebhookEventType::ProductUpdated => "product.updated",
+            WebhookEventType::ProductDeleted => "product.deleted",
+            WebhookEventType::StockChanged => "product.stock_changed",
+        }
+    }
+
+    pub fn of(event: &ProductEvent) -> Self {
+        match event {
+            ProductEvent::ProductCreated { .. } => WebhookEventType::ProductCreated,
+            ProductEvent::ProductUpdated { .. } => WebhookEventType::ProductUpdated,
+            ProductEvent::ProductDeleted { .. } => WebhookEventType::ProductDeleted,
+            ProductEvent::StockChanged { .. } => WebhookEventType::StockChanged,
+        }
+    }
+}
+
+impl TryFrom<&str> for WebhookEventType {
+    type Error = DomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "product.created" => Ok(WebhookEventType::ProductCreated),
+            "product.updated" => Ok(WebhookEventType::ProductUpdated),
+            "product.deleted" => Ok(WebhookEventType::ProductDeleted),
+            "product.stock_changed" => Ok(WebhookEventType::StockChanged),
+            other => Err(DomainError::InvalidWebhook(format!("Unknown event type '{}'", other))),
+        }
+    }
+}
+
+/// Webhook Subscription Entity - a receiver for product events
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookSubscription {
+    id: WebhookId,
+    url: String,
+    events: Vec<WebhookEventType>,
+    secret: String,
+    active: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl WebhookSubscription {
+    pub fn new(
+        id: WebhookId,
+        url: String,
+        events: Vec<WebhookEventType>,
+        secret: String,
+    ) -> Result<Self, DomainError> {
+        let now = Utc::now();
+        Ok(Self {
+            id,
+            url: Self::validate_url(url)?,
+            events: Self::validate_events(events)?,
+            secret: Self::validate_secret(secret)?,
+            active: true,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Rebuild a subscription from persisted state
+    pub fn reconstitute(
+        id: WebhookId,
+        url: String,
+        events: Vec<WebhookEventType>,
+        secret: String,
+        active: bool,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    ) -> Self {
+        Self { id, url, events, secret, active, created_at, updated_at }
+    }
+
+    pub fn update(
+        &mut self,
+        url: Option<String>,
+        events: Option<Vec<WebhookEventType>>,
+        secret: Option<String>,
+        active: Option<bool>,
+    ) -> Result<(), DomainError> {
+        if let Some(url) = url {
+            self.url = Self::validate_url(url)?;
+        }
+        if let Some(events) = events {
+            self.events = Self::validate_events(events)?;
+        }
+        if let Some(secret) = secret {
+            self.secret = Self::validate_secret(secret)?;
+        }
+        if let Some(active) = active {
+            self.active = active;
+        }
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Whether this subscription should receive `event`
+    pub fn matches(&self, event: &ProductEvent) -> bool {
+        self.active && self.events.contains(&WebhookEventType::of(event))
+    }
+
+    fn validate_url(url: String) -> Result<String, DomainError> {
+        let url = url.trim();
+        let rest = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .ok_or_else(|| DomainError::InvalidWebhook("URL must start with http:// or https://".to_string()))?;
+        if rest.is_empty() || rest.starts_with('/') || url.chars().any(char::is_whitespace) {
+            return Err(DomainError::InvalidWebhook("URL must include a host".to_string()));
+        }
+        if url.len() > 2048 {
+            return Err(DomainError::InvalidWebhook("URL too long".to_string()));
+        }
+        Ok(url.to_string())
+    }
+
+    fn validate_events(mut events: Vec<WebhookEventType>) -> Result<Vec<WebhookEventType>, DomainError> {
+        events.sort();
+        events.dedup();
+        if events.is_empty() {
+            return Err(DomainError::InvalidWebhook("Subscribe to at least one event type".to_string()));
+        }
+        Ok(events)
+    }
+
+    fn validate_secret(secret: String) -> Result<String, DomainError> {
+        if secret.len() < MIN_SECRET_LENGTH {
+            return Err(DomainError::InvalidWebhook(format!(
+                "Secret must be at least {} characters",
+                MIN_SECRET_LENGTH
+            )));
+        }
+        Ok(secret)
+    }
+
+    // Getters
+    pub fn id(&self) -> &WebhookId {
+        &self.id
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn events(&self) -> &[WebhookEventType] {
+        &self.events
+    }
+
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+}
+
+/// Delivery outcome, as recorded in the delivery log
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: WebhookId,
+    pub event_type: String,
+    /// `pending`, `delivered` or `failed`
+    pub status: String,
+    pub attempts: i64,
+    /// HTTP status of the most recent attempt, if the receiver answered
+    pub response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Repository trait for webhook subscriptions
+#[async_trait]
+pub trait WebhookRepository: Send + Sync {
+    async fn find_all(&self) -> Result<Vec<WebhookSubscription>, RepositoryError>;
+
+    async fn find_by_id(&self, id: &WebhookId) -> Result<Option<WebhookSubscription>, RepositoryError>;
+
+    async fn save(&self, webhook: WebhookSubscription) -> Result<WebhookSubscription, RepositoryError>;
+
+    async fn update(&self, webhook: WebhookSubscription) -> Result<WebhookSubscription, RepositoryError>;
+
+    /// Delete a subscription together with its delivery log
+    async fn delete(&self, id: &WebhookId) -> Result<bool, RepositoryError>;
+
+    /// Most recent deliveries for a subscription, newest first
+    async fn find_deliveries(&self, id: &WebhookId, limit: usize) -> Result<Vec<WebhookDelivery>, RepositoryError>;
+
+    /// Get next available ID
+    async fn next_id(&self) -> Result<WebhookId, RepositoryError>;
+}
+
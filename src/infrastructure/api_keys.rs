@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::domain::{ApiKey, ApiKeyId, ApiKeyRepository, ApiScope, RepositoryError};
+use crate::infrastructure::repositories::{format_timestamp, parse_timestamp};
+
+const API_KEY_COLUMNS: &str =
+    "id, name, prefix, key_hash, scopes, rate_limit_per_minute, created_at, last_used_at, revoked_at";
+
+pub struct SqliteApiKeyRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteApiKeyRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+fn optional_timestamp(row: &SqliteRow, column: &str) -> Result<Option<DateTime<Utc>>, RepositoryError> {
+    row.get::<Option<String>, _>(column)
+        .map(|value| parse_timestamp(&value))
+        .transpose()
+        .map_err(|e| RepositoryError::Internal(format!("Invalid {}: {}", column, e)))
+}
+
+fn row_to_api_key(row: &SqliteRow) -> Result<ApiKey, RepositoryError> {
+    let scopes = row
+        .get::<String, _>("scopes")
+        .split(',')
+        .map(ApiScope::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    let created_at = parse_timestamp(&row.get::<String, _>("created_at"))
+        .map_err(|e| RepositoryError::Internal(format!("Invalid created_at: {}", e)))?;
+    let rate_limit = row.get::<Option<i64>, _>("rate_limit_per_minute")
+        .map(u32::try_from)
+        .transpose()
+        .map_err(|e| RepositoryError::Internal(format!("Invalid rate_limit_per_minute: {}", e)))?;
+
+    Ok(ApiKey::reconstitute(
+        ApiKeyId::new(row.get("id"))?,
+        row.get("name"),
+        row.get("prefix"),
+        row.get("key_hash"),
+        scopes,
+        rate_limit,
+        created_at,
+        optional_timestamp(row, "last_used_at")?,
+        optional_timestamp(row, "revoked_at")?,
+    ))
+}
+
+fn join_scopes(key: &ApiKey) -> String {
+    key.scopes().iter().map(ApiScope::as_str).collect::<Vec<_>>().join(",")
+}
+
+#[async_trait]
+impl ApiKeyRepository for SqliteApiKeyRepository {
+    async fn find_all(&self) -> Result<Vec<ApiKey>, RepositoryError> {
+        let rows = sqlx::query(&format!("SELECT {} FROM api_keys ORDER BY id", API_KEY_COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(row_to_api_key).collect()
+    }
+
+    async fn find_by_id(&self, id: &ApiKeyId) -> Result<Option<ApiKey>, RepositoryError> {
+        let row = sqlx::query(&format!("SELECT {} FROM api_keys WHERE id = ?", API_KEY_COLUMNS))
+            .bind(id.value())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(row_to_api_key).transpose()
+    }
+
+    async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, RepositoryError> {
+        let row = sqlx::query(&format!("SELECT {} FROM api_keys WHERE key_hash = ?", API_KEY_COLUMNS))
+            .bind(key_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(row_to_api_key).transpose()
+    }
+
+    async fn save(&self, key: ApiKey) -> Result<ApiKey, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        let id: i64 = sqlx::query(
+            "INSERT INTO api_keys (name, prefix, key_hash, scopes, rate_limit_per_minute, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)
+             RETURNING id"
+        )
+        .bind(key.name())
+        .bind(key.prefix())
+        .bind(key.key_hash())
+        .bind(join_scopes(&key))
+        .bind(key.rate_limit_per_minute().map(i64::from))
+        .bind(format_timestamp(&key.created_at()))
+        .fetch_one(&mut *tx)
+        .await?
+        .get("id");
+        tx.commit().await?;
+
+        self.find_by_id(&ApiKeyId::new(id)?)
+            .await?
+            .ok_or(RepositoryError::Internal("Failed to retrieve created API key".to_string()))
+    }
+
+    async fn revoke(&self, id: &ApiKeyId, at: DateTime<Utc>) -> Result<bool, RepositoryError> {
+        let result = sqlx::query("UPDATE api_keys SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL")
+            .bind(format_timestamp(&at))
+            .bind(id.value())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn record_use(&self, id: &ApiKeyId, at: DateTime<Utc>) -> Result<(), RepositoryError> {
+        sqlx::query("UPDATE api_keys SET last_used_at = ? WHERE id = ?")
+            .bind(format_timestamp(&at))
+            .bind(id.value())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn next_id(&self) -> Result<ApiKeyId, RepositoryError> {
+        // Placeholder; the real ID is assigned by auto-increment on insert
+        Ok(ApiKeyId::new(1)?)
+    }
+}
@@ -0,0 +1,116 @@
+use async_trait::async_trait;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::domain::{
+    Cart, CartId, CartLine, CartRepository, Currency, Money, ProductId, RepositoryError,
+};
+use crate::infrastructure::repositories::{format_timestamp, parse_timestamp};
+
+pub struct SqliteCartRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteCartRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    async fn find_lines(&self, cart_id: i64) -> Result<Vec<CartLine>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT product_id, quantity, unit_price_minor, currency, added_at
+             FROM cart_items
+             WHERE cart_id = ?
+             ORDER BY added_at, product_id"
+        )
+        .bind(cart_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_line).collect()
+    }
+}
+
+fn row_to_line(row: &SqliteRow) -> Result<CartLine, RepositoryError> {
+    let added_at = parse_timestamp(&row.get::<String, _>("added_at"))
+        .map_err(|e| RepositoryError::Internal(format!("Invalid added_at: {}", e)))?;
+    let currency = Currency::new(&row.get::<String, _>("currency"))?;
+
+    Ok(CartLine::reconstitute(
+        ProductId::new(row.get("product_id"))?,
+        row.get("quantity"),
+        Money::new(row.get("unit_price_minor"), currency)?,
+        added_at,
+    ))
+}
+
+#[async_trait]
+impl CartRepository for SqliteCartRepository {
+    async fn find_by_token(&self, token: &str) -> Result<Option<Cart>, RepositoryError> {
+        let row = sqlx::query("SELECT id, token, created_at, updated_at FROM carts WHERE token = ?")
+            .bind(token)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let id: i64 = row.get("id");
+        let created_at = parse_timestamp(&row.get::<String, _>("created_at"))
+            .map_err(|e| RepositoryError::Internal(format!("Invalid created_at: {}", e)))?;
+        let updated_at = parse_timestamp(&row.get::<String, _>("updated_at"))
+            .map_err(|e| RepositoryError::Internal(format!("Invalid updated_at: {}", e)))?;
+
+        Ok(Some(Cart::reconstitute(
+            CartId::new(id)?,
+            row.get("token"),
+            self.find_lines(id).await?,
+            created_at,
+            updated_at,
+        )))
+    }
+
+    async fn save(&self, cart: Cart) -> Result<Cart, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        let id: i64 = sqlx::query(
+            "INSERT INTO carts (token, created_at, updated_at)
+             VALUES (?, ?, ?)
+             ON CONFLICT (token) DO UPDATE SET updated_at = excluded.updated_at
+             RETURNING id"
+        )
+        .bind(cart.token())
+        .bind(format_timestamp(&cart.created_at()))
+        .bind(format_timestamp(&cart.updated_at()))
+        .fetch_one(&mut *tx)
+        .await?
+        .get("id");
+
+        sqlx::query("DELETE FROM cart_items WHERE cart_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        for line in cart.lines() {
+            sqlx::query(
+                "INSERT INTO cart_items (cart_id, product_id, quantity, unit_price_minor, currency, added_at)
+                 VALUES (?, ?, ?, ?, ?, ?)"
+            )
+            .bind(id)
+            .bind(line.product_id().value())
+            .bind(line.quantity())
+            .bind(line.unit_price().amount_minor())
+            .bind(line.unit_price().currency().code())
+            .bind(format_timestamp(&line.added_at()))
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        self.find_by_token(cart.token())
+            .await?
+            .ok_or(RepositoryError::Internal("Failed to retrieve saved cart".to_string()))
+    }
+
+    async fn next_id(&self) -> Result<CartId, RepositoryError> {
+        // Placeholder; the real ID is assigned by auto-increment on insert
+        Ok(CartId::new(1)?)
+    }
+}
@@ -0,0 +1,166 @@
+use async_trait::async_trait;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::domain::{
+    Category, CategoryId, CategoryRepository, DomainError, ProductId, RepositoryError, Slug,
+};
+use crate::infrastructure::repositories::{format_timestamp, parse_timestamp};
+
+const CATEGORY_COLUMNS: &str = "id, name, slug, parent_id, created_at, updated_at";
+
+pub struct SqliteCategoryRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteCategoryRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+fn row_to_category(row: &SqliteRow) -> Result<Category, RepositoryError> {
+    let timestamp = |column: &str| {
+        parse_timestamp(&row.get::<String, _>(column))
+            .map_err(|e| RepositoryError::Internal(format!("Invalid {}: {}", column, e)))
+    };
+
+    Ok(Category::reconstitute(
+        CategoryId::new(row.get("id"))?,
+        row.get("name"),
+        Slug::new(&row.get::<String, _>("slug"))?,
+        row.get::<Option<i64>, _>("parent_id").map(CategoryId::new).transpose()?,
+        timestamp("created_at")?,
+        timestamp("updated_at")?,
+    ))
+}
+
+#[async_trait]
+impl CategoryRepository for SqliteCategoryRepository {
+    async fn find_all(&self) -> Result<Vec<Category>, RepositoryError> {
+        let rows = sqlx::query(&format!("SELECT {} FROM categories ORDER BY id", CATEGORY_COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(row_to_category).collect()
+    }
+
+    async fn find_by_id(&self, id: &CategoryId) -> Result<Option<Category>, RepositoryError> {
+        let row = sqlx::query(&format!("SELECT {} FROM categories WHERE id = ?", CATEGORY_COLUMNS))
+            .bind(id.value())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(row_to_category).transpose()
+    }
+
+    async fn find_by_slug(&self, slug: &Slug) -> Result<Option<Category>, RepositoryError> {
+        let row = sqlx::query(&format!("SELECT {} FROM categories WHERE slug = ?", CATEGORY_COLUMNS))
+            .bind(slug.value())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(row_to_category).transpose()
+    }
+
+    async fn save(&self, category: Category) -> Result<Category, RepositoryError> {
+        let id: i64 = sqlx::query(
+            "INSERT INTO categories (name, slug, parent_id, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?)
+             RETURNING id"
+        )
+        .bind(category.name())
+        .bind(category.slug().value())
+        .bind(category.parent_id().map(CategoryId::value))
+        .bind(format_timestamp(&category.created_at()))
+        .bind(format_timestamp(&category.updated_at()))
+        .fetch_one(&self.pool)
+        .await?
+        .get("id");
+
+        self.find_by_id(&CategoryId::new(id)?)
+            .await?
+            .ok_or(RepositoryError::Internal("Failed to retrieve created category".to_string()))
+    }
+
+    async fn update(&self, category: Category) -> Result<Category, RepositoryError> {
+        let result = sqlx::query(
+            "UPDATE categories SET name = ?, slug = ?, parent_id = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(category.name())
+        .bind(category.slug().value())
+        .bind(category.parent_id().map(CategoryId::value))
+        .bind(format_timestamp(&category.updated_at()))
+        .bind(category.id().value())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(category)
+    }
+
+    async fn delete(&self, id: &CategoryId) -> Result<bool, RepositoryError> {
+        // Counted in the same transaction as the delete, so the error names what blocked it
+        let mut tx = self.pool.begin().await?;
+        let products: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM product_categories WHERE category_id = ?")
+            .bind(id.value())
+            .fetch_one(&mut *tx)
+            .await?;
+        let subcategories: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM categories WHERE parent_id = ?")
+            .bind(id.value())
+            .fetch_one(&mut *tx)
+            .await?;
+        if products > 0 || subcategories > 0 {
+            return Err(DomainError::CategoryNotEmpty {
+                products: products as usize,
+                subcategories: subcategories as usize,
+            }
+            .into());
+        }
+
+        let result = sqlx::query("DELETE FROM categories WHERE id = ?")
+            .bind(id.value())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn find_for_product(&self, product_id: &ProductId) -> Result<Vec<Category>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT c.id, c.name, c.slug, c.parent_id, c.created_at, c.updated_at
+             FROM categories c
+             JOIN product_categories pc ON pc.category_id = c.id
+             WHERE pc.product_id = ?
+             ORDER BY c.name COLLATE NOCASE, c.id"
+        )
+        .bind(product_id.value())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_category).collect()
+    }
+
+    async fn assign(&self, product_id: &ProductId, categories: &[CategoryId]) -> Result<(), RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM product_categories WHERE product_id = ?")
+            .bind(product_id.value())
+            .execute(&mut *tx)
+            .await?;
+        for category in categories {
+            sqlx::query("INSERT OR IGNORE INTO product_categories (product_id, category_id) VALUES (?, ?)")
+                .bind(product_id.value())
+                .bind(category.value())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn next_id(&self) -> Result<CategoryId, RepositoryError> {
+        // Placeholder; the real ID is assigned by auto-increment on insert
+        Ok(CategoryId::new(1)?)
+    }
+}
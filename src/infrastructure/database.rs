@@ -1,18 +1,51 @@
-use sqlx::SqlitePool;
+use std::time::Duration;
+
+use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
 use anyhow::Result;
 
-pub async fn create_connection_pool() -> Result<SqlitePool> {
-    let database_url = "sqlite:products.db";
-    let pool = SqlitePool::connect(database_url).await?;
-    Ok(pool)
+/// Connection pool sizing
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    pub max_connections: u32,
+    /// Connections kept open even when idle
+    pub min_connections: u32,
+    /// How long a query waits for a free connection before failing
+    pub acquire_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(30),
+        }
+    }
 }
 
-#[allow(dead_code)]
-pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
-    // Create tables if they don't exist
-    sqlx::query(include_str!("../../migrations/001_create_products.sql"))
-        .execute(pool)
+pub async fn create_connection_pool(database_url: &str, config: &PoolConfig) -> Result<SqlitePool> {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout)
+        .connect(database_url)
         .await?;
-    
-    Ok(())
-}
\ No newline at end of file
+    Ok(pool)
+}
+
+/// A fresh in-memory database with every migration applied, for tests
+#[cfg(test)]
+pub(crate) async fn migrated_pool() -> SqlitePool {
+    // A single connection keeps every query on the same in-memory database
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("in-memory database");
+    super::Migrator::from_dir(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(super::MIGRATIONS_DIR))
+        .expect("migration files")
+        .run(&pool)
+        .await
+        .expect("schema");
+    pool
+}
@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+
+use crate::application::{ApplicationError, EventHandler};
+use crate::domain::ProductEvent;
+
+/// Writes every product event to the application log
+pub struct LoggingEventHandler;
+
+#[async_trait]
+impl EventHandler for LoggingEventHandler {
+    fn name(&self) -> &'static str {
+        "logging"
+    }
+
+    async fn handle(&self, event: &ProductEvent) -> Result<(), ApplicationError> {
+        match event {
+            ProductEvent::ProductCreated { product_id, name, price } => {
+                tracing::info!(product_id = product_id.value(), name = name.value(), price = %price, "product created");
+            }
+            ProductEvent::ProductUpdated { product_id, changes } => {
+                tracing::info!(product_id = product_id.value(), changes = ?changes, "product updated");
+            }
+            ProductEvent::ProductDeleted { product_id } => {
+                tracing::info!(product_id = product_id.value(), "product deleted");
+            }
+            ProductEvent::StockChanged { product_id, old_stock, new_stock } => {
+                tracing::info!(
+                    product_id = product_id.value(),
+                    old_stock = old_stock.value(),
+                    new_stock = new_stock.value(),
+                    "stock changed"
+                );
+            }
+        }
+        Ok(())
+    }
+}
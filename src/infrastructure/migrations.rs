@@ -0,0 +1,490 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{SecondsFormat, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{Row, SqlitePool};
+
+/// Directory scanned for migration files, relative to the working directory
+pub const MIGRATIONS_DIR: &str = "migrations";
+
+const CREATE_TRACKING_TABLE: &str = "CREATE TABLE IF NOT EXISTS schema_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    checksum TEXT NOT NULL,
+    applied_at TEXT NOT NULL
+)";
+
+/// A single versioned schema change.
+///
+/// Loaded from `NNN_name.sql`, with an optional `NNN_name.down.sql` rollback script.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    version: i64,
+    name: String,
+    up_sql: String,
+    down_sql: Option<String>,
+    checksum: String,
+}
+
+impl Migration {
+    pub fn new(version: i64, name: impl Into<String>, up_sql: impl Into<String>, down_sql: Option<String>) -> Self {
+        let up_sql = up_sql.into();
+        let mut hasher = Sha256::new();
+        hasher.update(up_sql.as_bytes());
+        if let Some(down_sql) = &down_sql {
+            hasher.update(b"\0");
+            hasher.update(down_sql.as_bytes());
+        }
+        let checksum = hex::encode(hasher.finalize());
+        Self {
+            version,
+            name: name.into(),
+            up_sql,
+            down_sql,
+            checksum,
+        }
+    }
+}
+
+/// State of one migration relative to the database
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationState {
+    Applied { applied_at: String },
+    Pending,
+    /// Applied, but the file changed afterwards
+    Modified,
+    /// Recorded as applied, but no longer present on disk
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    pub state: MigrationState,
+}
+
+/// Migration subsystem errors
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error("Could not read migrations from {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Invalid migration file name '{0}', expected NNN_name.sql or NNN_name.down.sql")]
+    InvalidFileName(String),
+    #[error("Duplicate migration version {0}")]
+    DuplicateVersion(i64),
+    #[error("Rollback script found for version {0} without a matching migration")]
+    OrphanRollback(i64),
+    #[error("Migration {version} ({name}) was modified after it was applied")]
+    ChecksumMismatch { version: i64, name: String },
+    #[error("Migration {version} ({name}) was applied but its file is missing")]
+    MissingMigration { version: i64, name: String },
+    #[error("Migration {0} has no rollback script")]
+    NoRollbackScript(i64),
+    #[error("Migration {version} failed: {source}")]
+    Failed { version: i64, source: sqlx::Error },
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Applies, verifies and rolls back versioned SQL migrations.
+///
+/// Every applied migration is recorded in `schema_migrations` together with the
+/// SHA-256 checksum of its up and down scripts, so edits to applied files are detected.
+pub struct Migrator {
+    migrations: Vec<Migration>,
+}
+
+impl Migrator {
+    pub fn new(mut migrations: Vec<Migration>) -> Result<Self, MigrationError> {
+        migrations.sort_by_key(|m| m.version);
+        if let Some(pair) = migrations.windows(2).find(|pair| pair[0].version == pair[1].version) {
+            return Err(MigrationError::DuplicateVersion(pair[0].version));
+        }
+        Ok(Self { migrations })
+    }
+
+    /// Discover numbered migration files in `dir`
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, MigrationError> {
+        let dir = dir.as_ref();
+        let io_error = |source| MigrationError::Io { path: dir.to_path_buf(), source };
+
+        let mut ups: Vec<(i64, String, String)> = Vec::new();
+        let mut downs: HashMap<i64, String> = HashMap::new();
+
+        for entry in fs::read_dir(dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let Some(stem) = file_name.strip_suffix(".sql") else {
+                continue;
+            };
+
+            let (stem, is_down) = match stem.strip_suffix(".down") {
+                Some(stem) => (stem, true),
+                None => (stem, false),
+            };
+            let (version, name) = stem
+                .split_once('_')
+                .and_then(|(version, name)| Some((version.parse::<i64>().ok()?, name)))
+                .filter(|(_, name)| !name.is_empty())
+                .ok_or_else(|| MigrationError::InvalidFileName(file_name.to_string()))?;
+
+            let sql = fs::read_to_string(&path)
+                .map_err(|source| MigrationError::Io { path: path.clone(), source })?;
+
+            if is_down {
+                if downs.insert(version, sql).is_some() {
+                    return Err(MigrationError::DuplicateVersion(version));
+                }
+            } else {
+                ups.push((version, name.to_string(), sql));
+            }
+        }
+
+        let mut migrations = Vec::with_capacity(ups.len());
+        for (version, name, up_sql) in ups {
+            let down_sql = downs.remove(&version);
+            migrations.push(Migration::new(version, name, up_sql, down_sql));
+        }
+        if let Some(version) = downs.keys().min() {
+            return Err(MigrationError::OrphanRollback(*version));
+        }
+
+        Self::new(migrations)
+    }
+
+    #[allow(dead_code)]
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Compare the migrations on disk with what the database has recorded.
+    /// Read-only: a database without `schema_migrations` reports every migration as pending.
+    pub async fn status(&self, pool: &SqlitePool) -> Result<Vec<MigrationStatus>, MigrationError> {
+        let applied = self.applied(pool).await?;
+
+        let mut statuses: Vec<MigrationStatus> = self
+            .migrations
+            .iter()
+            .map(|migration| {
+                let state = match applied.get(&migration.version) {
+                    Some(record) if record.checksum != migration.checksum => MigrationState::Modified,
+                    Some(record) => MigrationState::Applied { applied_at: record.applied_at.clone() },
+                    None => MigrationState::Pending,
+                };
+                MigrationStatus {
+                    version: migration.version,
+                    name: migration.name.clone(),
+                    state,
+                }
+            })
+            .collect();
+
+        for (version, record) in &applied {
+            if !self.migrations.iter().any(|m| m.version == *version) {
+                statuses.push(MigrationStatus {
+                    version: *version,
+                    name: record.name.clone(),
+                    state: MigrationState::Missing,
+                });
+            }
+        }
+
+        statuses.sort_by_key(|s| s.version);
+        Ok(statuses)
+    }
+
+    /// Fail if an applied migration was edited or removed
+    pub async fn verify(&self, pool: &SqlitePool) -> Result<(), MigrationError> {
+        for status in self.status(pool).await? {
+            match status.state {
+                MigrationState::Modified => {
+                    return Err(MigrationError::ChecksumMismatch { version: status.version, name: status.name });
+                }
+                MigrationState::Missing => {
+                    return Err(MigrationError::MissingMigration { version: status.version, name: status.name });
+                }
+                MigrationState::Applied { .. } | MigrationState::Pending => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Verify applied migrations, then apply pending ones in version order.
+    /// Returns the versions that were applied.
+    pub async fn run(&self, pool: &SqlitePool) -> Result<Vec<i64>, MigrationError> {
+        sqlx::query(CREATE_TRACKING_TABLE).execute(pool).await?;
+        self.verify(pool).await?;
+        let applied = self.applied(pool).await?;
+
+        let mut newly_applied = Vec::new();
+        for migration in self.migrations.iter().filter(|m| !applied.contains_key(&m.version)) {
+            let mut tx = pool.begin().await?;
+
+            sqlx::query(&migration.up_sql)
+                .execute(&mut *tx)
+                .await
+                .map_err(|source| MigrationError::Failed { version: migration.version, source })?;
+
+            sqlx::query(
+                "INSERT INTO schema_migrations (version, name, checksum, applied_at) VALUES (?, ?, ?, ?)"
+            )
+            .bind(migration.version)
+            .bind(&migration.name)
+            .bind(&migration.checksum)
+            .bind(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true))
+            .execute(&mut *tx)
+            .await?;
+
+            tx.commit().await?;
+            tracing::info!(version = migration.version, name = %migration.name, "applied migration");
+            newly_applied.push(migration.version);
+        }
+
+        Ok(newly_applied)
+    }
+
+    /// Roll back the `steps` most recently applied migrations using their down scripts.
+    /// Returns the versions that were rolled back.
+    pub async fn rollback(&self, pool: &SqlitePool, steps: usize) -> Result<Vec<i64>, MigrationError> {
+        sqlx::query(CREATE_TRACKING_TABLE).execute(pool).await?;
+        self.verify(pool).await?;
+        let applied = self.applied(pool).await?;
+
+        let mut versions: Vec<i64> = applied.keys().copied().collect();
+        versions.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut rolled_back = Vec::new();
+        for version in versions.into_iter().take(steps) {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|m| m.version == version)
+                .ok_or_else(|| MigrationError::MissingMigration {
+                    version,
+                    name: applied[&version].name.clone(),
+                })?;
+            let down_sql = migration
+                .down_sql
+                .as_deref()
+                .ok_or(MigrationError::NoRollbackScript(version))?;
+
+            let mut tx = pool.begin().await?;
+
+            sqlx::query(down_sql)
+                .execute(&mut *tx)
+                .await
+                .map_err(|source| MigrationError::Failed { version, source })?;
+
+            sqlx::query("DELETE FROM schema_migrations WHERE version = ?")
+                .bind(version)
+                .execute(&mut *tx)
+                .await?;
+
+            tx.commit().await?;
+            tracing::info!(version, name = %migration.name, "rolled back migration");
+            rolled_back.push(version);
+        }
+
+        Ok(rolled_back)
+    }
+
+    async fn applied(&self, pool: &SqlitePool) -> Result<HashMap<i64, AppliedMigration>, MigrationError> {
+        let tracked = sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'")
+            .fetch_optional(pool)
+            .await?;
+        if tracked.is_none() {
+            return Ok(HashMap::new());
+        }
+
+        let rows = sqlx::query("SELECT version, name, checksum, applied_at FROM schema_migrations")
+            .fetch_all(pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let record = AppliedMigration {
+                    name: row.get("name"),
+                    checksum: row.get("checksum"),
+                    applied_at: row.get("applied_at"),
+                };
+                (row.get("version"), record)
+            })
+            .collect())
+    }
+}
+
+struct AppliedMigration {
+    name: String,
+    checksum: String,
+    applied_at: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn pool() -> SqlitePool {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory database")
+    }
+
+    fn widgets(up_sql: &str) -> Migrator {
+        Migrator::new(vec![
+            Migration::new(1, "create_widgets", up_sql, Some("DROP TABLE widgets;".to_string())),
+            Migration::new(2, "seed_widgets", "INSERT INTO widgets (id) VALUES (1);", None),
+        ])
+        .unwrap()
+    }
+
+    async fn table_exists(pool: &SqlitePool, table: &str) -> bool {
+        sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(table)
+            .fetch_optional(pool)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    #[tokio::test]
+    async fn run_applies_pending_migrations_once() {
+        let pool = pool().await;
+        let migrator = widgets("CREATE TABLE widgets (id INTEGER PRIMARY KEY);");
+
+        assert_eq!(migrator.run(&pool).await.unwrap(), vec![1, 2]);
+        assert_eq!(migrator.run(&pool).await.unwrap(), Vec::<i64>::new());
+
+        let states: Vec<_> = migrator.status(&pool).await.unwrap().into_iter().map(|s| s.state).collect();
+        assert!(states.iter().all(|s| matches!(s, MigrationState::Applied { .. })));
+    }
+
+    #[tokio::test]
+    async fn failed_migration_leaves_no_trace() {
+        let pool = pool().await;
+        let migrator = Migrator::new(vec![Migration::new(
+            1,
+            "broken",
+            "CREATE TABLE widgets (id INTEGER); INSERT INTO missing_table VALUES (1);",
+            None,
+        )])
+        .unwrap();
+
+        assert!(matches!(migrator.run(&pool).await, Err(MigrationError::Failed { version: 1, .. })));
+        assert!(!table_exists(&pool, "widgets").await);
+        assert_eq!(migrator.status(&pool).await.unwrap()[0].state, MigrationState::Pending);
+    }
+
+    #[tokio::test]
+    async fn edited_migration_is_refused() {
+        let pool = pool().await;
+        widgets("CREATE TABLE widgets (id INTEGER PRIMARY KEY);").run(&pool).await.unwrap();
+
+        let edited = widgets("CREATE TABLE widgets (id INTEGER PRIMARY KEY, name TEXT);");
+
+        assert_eq!(edited.status(&pool).await.unwrap()[0].state, MigrationState::Modified);
+        assert!(matches!(
+            edited.run(&pool).await,
+            Err(MigrationError::ChecksumMismatch { version: 1, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn status_does_not_touch_a_fresh_database() {
+        let pool = pool().await;
+        let migrator = widgets("CREATE TABLE widgets (id INTEGER PRIMARY KEY);");
+
+        let states: Vec<_> = migrator.status(&pool).await.unwrap().into_iter().map(|s| s.state).collect();
+        assert_eq!(states, vec![MigrationState::Pending, MigrationState::Pending]);
+        assert!(!table_exists(&pool, "schema_migrations").await);
+    }
+
+    #[tokio::test]
+    async fn edited_rollback_script_is_refused() {
+        let pool = pool().await;
+        widgets("CREATE TABLE widgets (id INTEGER PRIMARY KEY);").run(&pool).await.unwrap();
+
+        let edited = Migrator::new(vec![
+            Migration::new(1, "create_widgets", "CREATE TABLE widgets (id INTEGER PRIMARY KEY);", Some("DELETE FROM widgets;".to_string())),
+            Migration::new(2, "seed_widgets", "INSERT INTO widgets (id) VALUES (1);", Some("DELETE FROM widgets;".to_string())),
+        ])
+        .unwrap();
+
+        let states: Vec<_> = edited.status(&pool).await.unwrap().into_iter().map(|s| s.state).collect();
+        assert_eq!(states, vec![MigrationState::Modified, MigrationState::Modified]);
+        assert!(matches!(
+            edited.run(&pool).await,
+            Err(MigrationError::ChecksumMismatch { version: 1, .. })
+        ));
+        assert!(matches!(
+            edited.rollback(&pool, 2).await,
+            Err(MigrationError::ChecksumMismatch { version: 1, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn rollback_runs_down_scripts_newest_first() {
+        let pool = pool().await;
+        let migrator = Migrator::new(vec![
+            Migration::new(1, "create_widgets", "CREATE TABLE widgets (id INTEGER);", Some("DROP TABLE widgets;".to_string())),
+            Migration::new(2, "create_gadgets", "CREATE TABLE gadgets (id INTEGER);", Some("DROP TABLE gadgets;".to_string())),
+        ])
+        .unwrap();
+        migrator.run(&pool).await.unwrap();
+
+        assert_eq!(migrator.rollback(&pool, 1).await.unwrap(), vec![2]);
+        assert!(!table_exists(&pool, "gadgets").await);
+        assert!(table_exists(&pool, "widgets").await);
+        assert_eq!(migrator.status(&pool).await.unwrap()[1].state, MigrationState::Pending);
+    }
+
+    #[tokio::test]
+    async fn rollback_without_down_script_is_an_error() {
+        let pool = pool().await;
+        let migrator = widgets("CREATE TABLE widgets (id INTEGER PRIMARY KEY);");
+        migrator.run(&pool).await.unwrap();
+
+        assert!(matches!(migrator.rollback(&pool, 1).await, Err(MigrationError::NoRollbackScript(2))));
+    }
+
+    #[tokio::test]
+    async fn project_migrations_apply_and_roll_back_cleanly() {
+        let pool = pool().await;
+        let migrator = Migrator::from_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join(MIGRATIONS_DIR)).unwrap();
+        let count = migrator.migrations().len();
+
+        assert_eq!(migrator.run(&pool).await.unwrap().len(), count);
+        assert_eq!(migrator.rollback(&pool, count).await.unwrap().len(), count);
+        assert!(!table_exists(&pool, "products").await);
+    }
+
+    #[tokio::test]
+    async fn money_rollback_is_refused_for_currencies_not_in_cents() {
+        let pool = pool().await;
+        let migrator = Migrator::from_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join(MIGRATIONS_DIR)).unwrap();
+        let count = migrator.migrations().len();
+        migrator.run(&pool).await.unwrap();
+        sqlx::query("INSERT INTO products (name, price_minor, currency) VALUES ('Ramen', 900, 'JPY')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(matches!(migrator.rollback(&pool, count).await, Err(MigrationError::Failed { version: 2, .. })));
+        let price: i64 = sqlx::query_scalar("SELECT price_minor FROM products WHERE name = 'Ramen'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(price, 900);
+    }
+}
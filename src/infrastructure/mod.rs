@@ -1,5 +1,23 @@
+pub mod api_keys;
 pub mod repositories;
+pub mod carts;
+pub mod categories;
 pub mod database;
+pub mod migrations;
+pub mod event_handlers;
+pub mod orders;
+pub mod outbox;
+pub mod users;
+pub mod webhooks;
 
+pub use api_keys::*;
 pub use repositories::*;
-pub use database::*;
\ No newline at end of file
+pub use carts::*;
+pub use categories::*;
+pub use database::*;
+pub use migrations::*;
+pub use event_handlers::*;
+pub use orders::*;
+pub use outbox::*;
+pub use users::*;
+pub use webhooks::*;
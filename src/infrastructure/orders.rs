@@ -0,0 +1,222 @@
+use async_trait::async_trait;
+use sqlx::{sqlite::SqliteRow, Row, Sqlite, SqlitePool, Transaction};
+
+use crate::domain::{
+    Cart, Currency, Money, Order, OrderId, OrderLine, OrderOwner, OrderRepository, OrderStatus, Product,
+    ProductEvent, ProductId, RepositoryError, UserId,
+};
+use crate::infrastructure::outbox::enqueue_event;
+use crate::infrastructure::repositories::{format_timestamp, parse_timestamp, write_product};
+
+pub struct SqliteOrderRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteOrderRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    async fn row_to_order(&self, row: &SqliteRow) -> Result<Order, RepositoryError> {
+        let id: i64 = row.get("id");
+        let currency = Currency::new(&row.get::<String, _>("currency"))?;
+        let created_at = parse_timestamp(&row.get::<String, _>("created_at"))
+            .map_err(|e| RepositoryError::Internal(format!("Invalid created_at: {}", e)))?;
+        let updated_at = parse_timestamp(&row.get::<String, _>("updated_at"))
+            .map_err(|e| RepositoryError::Internal(format!("Invalid updated_at: {}", e)))?;
+
+        let lines = sqlx::query(
+            "SELECT product_id, product_name, quantity, unit_price_minor
+             FROM order_items
+             WHERE order_id = ?
+             ORDER BY rowid"
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|line| {
+            Ok(OrderLine::reconstitute(
+                ProductId::new(line.get("product_id"))?,
+                line.get("product_name"),
+                line.get("quantity"),
+                Money::new(line.get("unit_price_minor"), currency)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, RepositoryError>>()?;
+
+        Ok(Order::reconstitute(
+            OrderId::new(id)?,
+            row.get("session_token"),
+            row.get::<Option<i64>, _>("user_id").map(UserId::new).transpose()?,
+            OrderStatus::try_from(row.get::<String, _>("status").as_str())?,
+            lines,
+            created_at,
+            updated_at,
+        ))
+    }
+
+    async fn reload(&self, id: &OrderId) -> Result<Order, RepositoryError> {
+        self.find_by_id(id)
+            .await?
+            .ok_or(RepositoryError::Internal("Failed to retrieve saved order".to_string()))
+    }
+}
+
+/// Write the products an order touched, plus their events. Any product that changed since
+/// it was read fails the whole write.
+async fn write_stock(
+    tx: &mut Transaction<'_, Sqlite>,
+    products: &[Product],
+    events: &[ProductEvent],
+) -> Result<bool, RepositoryError> {
+    for product in products {
+        if !write_product(tx, product).await? {
+            return Ok(false);
+        }
+    }
+    for event in events {
+        enqueue_event(tx, event).await?;
+    }
+    Ok(true)
+}
+
+/// Empty the stored cart, provided it still holds exactly the lines of `cart`
+async fn take_cart(tx: &mut Transaction<'_, Sqlite>, cart: &Cart) -> Result<bool, RepositoryError> {
+    for line in cart.lines() {
+        let result = sqlx::query(
+            "DELETE FROM cart_items
+             WHERE cart_id = ? AND product_id = ? AND quantity = ? AND unit_price_minor = ?"
+        )
+        .bind(cart.id().value())
+        .bind(line.product_id().value())
+        .bind(line.quantity())
+        .bind(line.unit_price().amount_minor())
+        .execute(&mut **tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+    }
+
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM cart_items WHERE cart_id = ?")
+        .bind(cart.id().value())
+        .fetch_one(&mut **tx)
+        .await?;
+    Ok(remaining == 0)
+}
+
+#[async_trait]
+impl OrderRepository for SqliteOrderRepository {
+    async fn find_by_id(&self, id: &OrderId) -> Result<Option<Order>, RepositoryError> {
+        let row = sqlx::query(
+            "SELECT id, session_token, user_id, status, currency, created_at, updated_at FROM orders WHERE id = ?"
+        )
+        .bind(id.value())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(self.row_to_order(&row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn find_by_owner(&self, owner: &OrderOwner, limit: usize) -> Result<Vec<Order>, RepositoryError> {
+        let user_id = owner.user_id.as_ref().map(UserId::value);
+        let rows = sqlx::query(
+            "SELECT id, session_token, user_id, status, currency, created_at, updated_at
+             FROM orders
+             WHERE session_token = ? OR (? IS NOT NULL AND user_id = ?)
+             ORDER BY id DESC
+             LIMIT ?"
+        )
+        .bind(&owner.session_token)
+        .bind(user_id)
+        .bind(user_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut orders = Vec::with_capacity(rows.len());
+        for row in &rows {
+            orders.push(self.row_to_order(row).await?);
+        }
+        Ok(orders)
+    }
+
+    async fn place(
+        &self,
+        order: Order,
+        cart: &Cart,
+        products: &[Product],
+        events: &[ProductEvent],
+    ) -> Result<Order, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        if !take_cart(&mut tx, cart).await? || !write_stock(&mut tx, products, events).await? {
+            tx.rollback().await?;
+            return Err(RepositoryError::ConcurrentModification);
+        }
+
+        let id: i64 = sqlx::query(
+            "INSERT INTO orders (session_token, user_id, status, currency, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?)
+             RETURNING id"
+        )
+        .bind(order.session_token())
+        .bind(order.user_id().map(UserId::value))
+        .bind(order.status().as_str())
+        .bind(order.currency().code())
+        .bind(format_timestamp(&order.created_at()))
+        .bind(format_timestamp(&order.updated_at()))
+        .fetch_one(&mut *tx)
+        .await?
+        .get("id");
+
+        for line in order.lines() {
+            sqlx::query(
+                "INSERT INTO order_items (order_id, product_id, product_name, quantity, unit_price_minor)
+                 VALUES (?, ?, ?, ?, ?)"
+            )
+            .bind(id)
+            .bind(line.product_id().value())
+            .bind(line.product_name())
+            .bind(line.quantity())
+            .bind(line.unit_price().amount_minor())
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        self.reload(&OrderId::new(id)?).await
+    }
+
+    async fn update_status(
+        &self,
+        order: Order,
+        expected: OrderStatus,
+        products: &[Product],
+        events: &[ProductEvent],
+    ) -> Result<Order, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query("UPDATE orders SET status = ?, updated_at = ? WHERE id = ? AND status = ?")
+            .bind(order.status().as_str())
+            .bind(format_timestamp(&order.updated_at()))
+            .bind(order.id().value())
+            .bind(expected.as_str())
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 || !write_stock(&mut tx, products, events).await? {
+            tx.rollback().await?;
+            return Err(RepositoryError::ConcurrentModification);
+        }
+        tx.commit().await?;
+
+        self.reload(order.id()).await
+    }
+
+    async fn next_id(&self) -> Result<OrderId, RepositoryError> {
+        // Placeholder; the real ID is assigned by auto-increment on insert
+        Ok(OrderId::new(1)?)
+    }
+}
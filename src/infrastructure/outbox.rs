@@ -0,0 +1,486 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use tokio::task::JoinHandle;
+
+use crate::application::EventBus;
+use crate::domain::{
+    Currency, Money, ProductEvent, ProductId, ProductName, RepositoryError, StockQuantity,
+};
+
+/// JSON form of a [`ProductEvent`], as stored in the outbox
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ProductEventPayload {
+    #[serde(rename = "product.created")]
+    Created {
+        product_id: i64,
+        name: String,
+        price_minor: i64,
+        currency: String,
+    },
+    #[serde(rename = "product.updated")]
+    Updated { product_id: i64, changes: Vec<String> },
+    #[serde(rename = "product.deleted")]
+    Deleted { product_id: i64 },
+    #[serde(rename = "product.stock_changed")]
+    StockChanged {
+        product_id: i64,
+        old_stock: i32,
+        new_stock: i32,
+    },
+}
+
+impl From<&ProductEvent> for ProductEventPayload {
+    fn from(event: &ProductEvent) -> Self {
+        match event {
+            ProductEvent::ProductCreated { product_id, name, price } => Self::Created {
+                product_id: product_id.value(),
+                name: name.value().to_string(),
+                price_minor: price.amount_minor(),
+                currency: price.currency().code().to_string(),
+            },
+            ProductEvent::ProductUpdated { product_id, changes } => Self::Updated {
+                product_id: product_id.value(),
+                changes: changes.clone(),
+            },
+            ProductEvent::ProductDeleted { product_id } => Self::Deleted {
+                product_id: product_id.value(),
+            },
+            ProductEvent::StockChanged { product_id, old_stock, new_stock } => Self::StockChanged {
+                product_id: product_id.value(),
+                old_stock: old_stock.value(),
+                new_stock: new_stock.value(),
+            },
+        }
+    }
+}
+
+impl TryFrom<ProductEventPayload> for ProductEvent {
+    type Error = RepositoryError;
+
+    fn try_from(payload: ProductEventPayload) -> Result<Self, Self::Error> {
+        Ok(match payload {
+            ProductEventPayload::Created { product_id, name, price_minor, currency } => {
+                ProductEvent::ProductCreated {
+                    product_id: ProductId::new(product_id)?,
+                    name: ProductName::new(name)?,
+                    price: Money::new(price_minor, Currency::new(&currency)?)?,
+                }
+            }
+            ProductEventPayload::Updated { product_id, changes } => ProductEvent::ProductUpdated {
+                product_id: ProductId::new(product_id)?,
+                changes,
+            },
+            ProductEventPayload::Deleted { product_id } => ProductEvent::ProductDeleted {
+                product_id: ProductId::new(product_id)?,
+            },
+            ProductEventPayload::StockChanged { product_id, old_stock, new_stock } => {
+                ProductEvent::StockChanged {
+                    product_id: ProductId::new(product_id)?,
+                    old_stock: StockQuantity::new(old_stock)?,
+                    new_stock: StockQuantity::new(new_stock)?,
+                }
+            }
+        })
+    }
+}
+
+/// Queue timestamps use a fixed-width format so they compare correctly as text
+pub(crate) fn queue_timestamp(value: DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Record an event in the outbox. Call this on the connection of the transaction
+/// that makes the corresponding product change.
+pub async fn enqueue_event(
+    conn: &mut SqliteConnection,
+    event: &ProductEvent,
+) -> Result<(), RepositoryError> {
+    let payload = serde_json::to_string(&ProductEventPayload::from(event))
+        .map_err(|e| RepositoryError::Internal(format!("Failed to serialize event: {}", e)))?;
+    let now = queue_timestamp(Utc::now());
+
+    sqlx::query(
+        "INSERT INTO outbox (event_type, aggregate_id, payload, status, attempts, next_attempt_at, created_at)
+         VALUES (?, ?, ?, 'pending', 0, ?, ?)"
+    )
+    .bind(event.event_type())
+    .bind(event.product_id().value())
+    .bind(payload)
+    .bind(&now)
+    .bind(&now)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Tuning for the outbox relay
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+    /// Pause between polls when the outbox has nothing due
+    pub poll_interval: Duration,
+    /// Maximum rows delivered per poll
+    pub batch_size: i64,
+    /// Deliveries attempted before a row is dead-lettered
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles with every further attempt
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RelayConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(1),
+            batch_size: 50,
+            max_attempts: 8,
+            base_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(300),
+        }
+    }
+}
+
+impl RelayConfig {
+    /// Exponential backoff after the given number of failed attempts
+    pub(crate) fn backoff(&self, attempts: u32) -> chrono::Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        let delay = self.base_backoff.saturating_mul(factor).min(self.max_backoff);
+        chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::MAX)
+    }
+}
+
+/// Outcome of one relay pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RelayStats {
+    pub delivered: usize,
+    pub retried: usize,
+    pub dead_lettered: usize,
+}
+
+/// Background relay that delivers pending outbox rows to the [`EventBus`] subscribers.
+///
+/// Each subscriber that takes an event is recorded in `outbox_deliveries`, and a retry
+/// only goes to the subscribers still missing. Delivery is at-least-once all the same:
+/// a subscriber must tolerate seeing an event twice if the relay stops between handing
+/// it over and recording it.
+pub struct OutboxRelay {
+    pool: SqlitePool,
+    consumers: Arc<EventBus>,
+    config: RelayConfig,
+}
+
+impl OutboxRelay {
+    pub fn new(pool: SqlitePool, consumers: Arc<EventBus>, config: RelayConfig) -> Self {
+        Self { pool, consumers, config }
+    }
+
+    /// Run the relay until the task is aborted
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match self.relay_once().await {
+                    // Keep draining without waiting while full batches come back
+                    Ok(stats) if stats.delivered + stats.retried + stats.dead_lettered
+                        >= self.config.batch_size as usize => continue,
+                    Ok(_) => {}
+                    Err(err) => tracing::error!("outbox relay failed: {}", err),
+                }
+                tokio::time::sleep(self.config.poll_interval).await;
+            }
+        })
+    }
+
+    /// Deliver every pending row that is due, oldest first
+    pub async fn relay_once(&self) -> Result<RelayStats, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT id, payload, attempts FROM outbox
+             WHERE status = 'pending' AND next_attempt_at <= ?
+             ORDER BY id
+             LIMIT ?"
+        )
+        .bind(queue_timestamp(Utc::now()))
+        .bind(self.config.batch_size)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut stats = RelayStats::default();
+        for row in rows {
+            let id: i64 = row.get("id");
+            let payload: String = row.get("payload");
+            let attempts = row.get::<i64, _>("attempts") as u32 + 1;
+
+            let delivery = match serde_json::from_str::<ProductEventPayload>(&payload)
+                .map_err(|e| format!("Unreadable payload: {}", e))
+                .and_then(|payload| ProductEvent::try_from(payload).map_err(|e| e.to_string()))
+            {
+                Ok(event) => self.publish(id, &event).await?,
+                Err(err) => Err(err),
+            };
+
+            match delivery {
+                Ok(()) => {
+                    sqlx::query(
+                        "UPDATE outbox SET status = 'delivered', attempts = ?, delivered_at = ?, last_error = NULL
+                         WHERE id = ?"
+                    )
+                    .bind(attempts)
+                    .bind(queue_timestamp(Utc::now()))
+                    .bind(id)
+                    .execute(&self.pool)
+                    .await?;
+                    stats.delivered += 1;
+                }
+                Err(err) if attempts >= self.config.max_attempts => {
+                    sqlx::query("UPDATE outbox SET status = 'dead', attempts = ?, last_error = ? WHERE id = ?")
+                        .bind(attempts)
+                        .bind(&err)
+                        .bind(id)
+                        .execute(&self.pool)
+                        .await?;
+                    tracing::error!(outbox_id = id, attempts, "outbox event dead-lettered: {}", err);
+                    stats.dead_lettered += 1;
+                }
+                Err(err) => {
+                    let next_attempt_at = Utc::now() + self.config.backoff(attempts);
+                    sqlx::query("UPDATE outbox SET attempts = ?, next_attempt_at = ?, last_error = ? WHERE id = ?")
+                        .bind(attempts)
+                        .bind(queue_timestamp(next_attempt_at))
+                        .bind(&err)
+                        .bind(id)
+                        .execute(&self.pool)
+                        .await?;
+                    tracing::warn!(outbox_id = id, attempts, "outbox delivery failed, will retry: {}", err);
+                    stats.retried += 1;
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Hand an event to the subscribers that have not taken it yet and record those that do
+    async fn publish(&self, id: i64, event: &ProductEvent) -> Result<Result<(), String>, RepositoryError> {
+        let delivered: Vec<String> = sqlx::query_scalar("SELECT handler FROM outbox_deliveries WHERE event_id = ?")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let published = self.consumers.publish(event, &delivered).await;
+        for handler in &published.handled {
+            sqlx::query("INSERT INTO outbox_deliveries (event_id, handler, delivered_at) VALUES (?, ?, ?)")
+                .bind(id)
+                .bind(handler)
+                .bind(queue_timestamp(Utc::now()))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        if published.failures.is_empty() {
+            Ok(Ok(()))
+        } else {
+            Ok(Err(published.failures.join("; ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use async_trait::async_trait;
+
+    use crate::application::{ApplicationError, EventHandler};
+    use crate::domain::{Product, ProductRepository};
+    use crate::infrastructure::{migrated_pool, SqliteProductRepository};
+
+    /// In-process consumer that records event types and can be told to fail
+    struct Consumer {
+        name: &'static str,
+        received: Mutex<Vec<&'static str>>,
+        failing: Mutex<bool>,
+    }
+
+    impl Consumer {
+        fn new(name: &'static str) -> Arc<Self> {
+            Arc::new(Consumer { name, received: Mutex::new(Vec::new()), failing: Mutex::new(false) })
+        }
+    }
+
+    #[async_trait]
+    impl EventHandler for Consumer {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn handle(&self, event: &ProductEvent) -> Result<(), ApplicationError> {
+            if *self.failing.lock().unwrap() {
+                return Err(ApplicationError::internal("consumer unavailable"));
+            }
+            self.received.lock().unwrap().push(event.event_type());
+            Ok(())
+        }
+    }
+
+    async fn setup(config: RelayConfig) -> (SqliteProductRepository, OutboxRelay, Arc<Consumer>, SqlitePool) {
+        let pool = migrated_pool().await;
+
+        let consumer = Consumer::new("test-consumer");
+        let bus = Arc::new(EventBus::new());
+        bus.subscribe(consumer.clone());
+
+        let relay = OutboxRelay::new(pool.clone(), bus, config);
+        (SqliteProductRepository::new(pool.clone()), relay, consumer, pool)
+    }
+
+    fn new_product() -> Product {
+        Product::new(
+            ProductId::new(1).unwrap(),
+            ProductName::new("Desk Lamp".to_string()).unwrap(),
+            None,
+            Money::new(2_500, Currency::USD).unwrap(),
+            StockQuantity::new(4).unwrap(),
+            "test",
+        )
+    }
+
+    /// Save a new product the way `ProductService` does, with its `ProductCreated`
+    async fn save_new(repository: &SqliteProductRepository) -> Product {
+        let product = new_product();
+        let created = ProductEvent::ProductCreated {
+            product_id: product.id().clone(),
+            name: product.name().clone(),
+            price: product.price().clone(),
+        };
+        repository.save(product, &[created]).await.unwrap()
+    }
+
+    async fn statuses(pool: &SqlitePool) -> Vec<(String, String, i64)> {
+        sqlx::query("SELECT event_type, status, attempts FROM outbox ORDER BY id")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| (row.get("event_type"), row.get("status"), row.get("attempts")))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn product_changes_are_enqueued_and_relayed_to_consumers() {
+        let (repository, relay, consumer, pool) = setup(RelayConfig::default()).await;
+
+        let mut product = save_new(&repository).await;
+        let old_stock = product.stock().clone();
+        let changes = product.update(None, None, None, Some(StockQuantity::new(9).unwrap()), "test").unwrap();
+        let events = vec![
+            ProductEvent::ProductUpdated { product_id: product.id().clone(), changes },
+            ProductEvent::StockChanged {
+                product_id: product.id().clone(),
+                old_stock,
+                new_stock: product.stock().clone(),
+            },
+        ];
+        let product = repository.update(product, &events).await.unwrap();
+        let deleted = ProductEvent::ProductDeleted { product_id: product.id().clone() };
+        repository.delete(product.id(), None, &[deleted]).await.unwrap();
+
+        let stats = relay.relay_once().await.unwrap();
+
+        assert_eq!(stats, RelayStats { delivered: 4, retried: 0, dead_lettered: 0 });
+        assert_eq!(
+            *consumer.received.lock().unwrap(),
+            vec!["product.created", "product.updated", "product.stock_changed", "product.deleted"]
+        );
+        assert!(statuses(&pool).await.iter().all(|(_, status, _)| status == "delivered"));
+    }
+
+    #[tokio::test]
+    async fn created_events_are_recorded_against_the_assigned_id() {
+        let (repository, _relay, _consumer, pool) = setup(RelayConfig::default()).await;
+        save_new(&repository).await;
+        let second = save_new(&repository).await;
+
+        let ids: Vec<i64> = sqlx::query_scalar("SELECT aggregate_id FROM outbox ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(ids, vec![1, second.id().value()]);
+        assert_ne!(second.id().value(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejected_writes_leave_nothing_in_the_outbox() {
+        let (repository, _relay, _consumer, pool) = setup(RelayConfig::default()).await;
+        let saved = save_new(&repository).await;
+
+        let mut stale = saved.clone();
+        let mut winner = saved.clone();
+        winner.update(None, Some(Some("first".to_string())), None, None, "test").unwrap();
+        stale.update(None, Some(Some("second".to_string())), None, None, "test").unwrap();
+        let updated = ProductEvent::ProductUpdated {
+            product_id: saved.id().clone(),
+            changes: vec!["description".to_string()],
+        };
+        repository.update(winner, std::slice::from_ref(&updated)).await.unwrap();
+
+        assert!(repository.update(stale, &[updated]).await.is_err());
+        assert_eq!(statuses(&pool).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_deliveries_back_off_then_dead_letter() {
+        let config = RelayConfig { max_attempts: 2, base_backoff: Duration::ZERO, ..RelayConfig::default() };
+        let (repository, relay, consumer, pool) = setup(config).await;
+        save_new(&repository).await;
+        *consumer.failing.lock().unwrap() = true;
+
+        assert_eq!(relay.relay_once().await.unwrap().retried, 1);
+        assert_eq!(statuses(&pool).await, vec![("product.created".to_string(), "pending".to_string(), 1)]);
+
+        assert_eq!(relay.relay_once().await.unwrap().dead_lettered, 1);
+        assert_eq!(statuses(&pool).await, vec![("product.created".to_string(), "dead".to_string(), 2)]);
+
+        // Dead rows are never picked up again
+        *consumer.failing.lock().unwrap() = false;
+        assert_eq!(relay.relay_once().await.unwrap(), RelayStats::default());
+        assert!(consumer.received.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn retries_only_go_to_the_consumers_that_failed() {
+        let config = RelayConfig { base_backoff: Duration::ZERO, ..RelayConfig::default() };
+        let (repository, relay, flaky, pool) = setup(config).await;
+        let steady = Consumer::new("steady");
+        relay.consumers.subscribe(steady.clone());
+        save_new(&repository).await;
+
+        *flaky.failing.lock().unwrap() = true;
+        assert_eq!(relay.relay_once().await.unwrap().retried, 1);
+        *flaky.failing.lock().unwrap() = false;
+        assert_eq!(relay.relay_once().await.unwrap().delivered, 1);
+
+        assert_eq!(*flaky.received.lock().unwrap(), vec!["product.created"]);
+        assert_eq!(*steady.received.lock().unwrap(), vec!["product.created"]);
+        let handlers: Vec<String> = sqlx::query_scalar("SELECT handler FROM outbox_deliveries ORDER BY handler")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(handlers, vec!["steady", "test-consumer"]);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let config = RelayConfig {
+            base_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(10),
+            ..RelayConfig::default()
+        };
+
+        let delays: Vec<i64> = (1..=4).map(|attempt| config.backoff(attempt).num_seconds()).collect();
+        assert_eq!(delays, vec![2, 4, 8, 10]);
+    }
+}
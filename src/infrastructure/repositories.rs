@@ -1,28 +1,113 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use async_trait::async_trait;
-use sqlx::{SqlitePool, Row};
-use chrono::Utc;
+use sqlx::{sqlite::SqliteRow, QueryBuilder, Row, Sqlite, SqliteConnection, SqlitePool};
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
 
 use crate::domain::{
-    Product, ProductId, ProductName, Money, StockQuantity,
-    ProductRepository, RepositoryError
+    Product, ProductId, ProductName, Money, Currency, StockQuantity,
+    ProductRepository, RepositoryError, ProductQuery, ProductPage, ProductSortField, SortDirection,
+    SearchHighlight, Facets, FacetCount, Tag, ProductAttribute,
+    ProductEvent, InventoryRepository, ReservationId, ReservationStatus, StockReservation,
+    StockMovement, StockMovementReason, StockDiscrepancy,
 };
+use crate::infrastructure::outbox::enqueue_event;
+
+/// Full-text index over product names and descriptions. It stores no text of its own
+/// and reads it from `products` when building highlights.
+const CREATE_SEARCH_INDEX: &str = "
+    CREATE VIRTUAL TABLE IF NOT EXISTS products_fts USING fts5(
+        name, description,
+        content = 'products', content_rowid = 'id',
+        tokenize = 'unicode61 remove_diacritics 2'
+    )";
+
+/// Keep `products_fts` in step with every write to `products`, then index what is there now
+const SYNC_SEARCH_INDEX: &str = "
+    CREATE TRIGGER IF NOT EXISTS products_fts_insert AFTER INSERT ON products BEGIN
+        INSERT INTO products_fts (rowid, name, description) VALUES (new.id, new.name, new.description);
+    END;
+    CREATE TRIGGER IF NOT EXISTS products_fts_delete AFTER DELETE ON products BEGIN
+        INSERT INTO products_fts (products_fts, rowid, name, description)
+        VALUES ('delete', old.id, old.name, old.description);
+    END;
+    CREATE TRIGGER IF NOT EXISTS products_fts_update AFTER UPDATE OF name, description ON products BEGIN
+        INSERT INTO products_fts (products_fts, rowid, name, description)
+        VALUES ('delete', old.id, old.name, old.description);
+        INSERT INTO products_fts (rowid, name, description) VALUES (new.id, new.name, new.description);
+    END;
+    INSERT INTO products_fts (products_fts) VALUES ('rebuild');";
+
+const DROP_SEARCH_TRIGGERS: &str = "
+    DROP TRIGGER IF EXISTS products_fts_insert;
+    DROP TRIGGER IF EXISTS products_fts_delete;
+    DROP TRIGGER IF EXISTS products_fts_update;";
+
+/// Matches joined onto `products` for a full-text search, best first by bm25 with
+/// name matches weighted above description matches
+const SEARCH_MATCHES: &str = " JOIN (
+        SELECT rowid AS match_id,
+               bm25(products_fts, 10.0, 1.0) AS rank,
+               highlight(products_fts, 0, char(2), char(3)) AS name_highlight,
+               snippet(products_fts, 1, char(2), char(3), '…', 16) AS description_highlight
+        FROM products_fts
+        WHERE products_fts MATCH ";
 
 pub struct SqliteProductRepository {
     pool: SqlitePool,
+    full_text: AtomicBool,
 }
 
 impl SqliteProductRepository {
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self { pool, full_text: AtomicBool::new(false) }
     }
 
-    /// Initialize database tables and seed data
-    pub async fn initialize(&self) -> Result<(), RepositoryError> {
-        // Create table
-        sqlx::query(include_str!("../../migrations/001_create_products.sql"))
-            .execute(&self.pool)
-            .await?;
+    /// Set up the full-text search index and the triggers that keep it current, when this
+    /// SQLite build has FTS5. Returns whether searches use the index; until this succeeds
+    /// they fall back to substring matching.
+    ///
+    /// The index only holds derived data, so it is created here rather than by a migration,
+    /// which could not be skipped on builds without FTS5. It is rebuilt on every start in
+    /// case products changed while the triggers were missing.
+    pub async fn enable_full_text_search(&self) -> Result<bool, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        if let Err(err) = sqlx::query(CREATE_SEARCH_INDEX).execute(&mut *tx).await {
+            if !err.to_string().contains("no such module") {
+                return Err(err.into());
+            }
+            tx.rollback().await?;
+            // Triggers left by a build with FTS5 would make every product write fail
+            sqlx::query(DROP_SEARCH_TRIGGERS).execute(&self.pool).await?;
+            self.full_text.store(false, Ordering::Relaxed);
+            return Ok(false);
+        }
+        sqlx::query(SYNC_SEARCH_INDEX).execute(&mut *tx).await?;
+        tx.commit().await?;
 
+        self.full_text.store(true, Ordering::Relaxed);
+        Ok(true)
+    }
+
+    /// How the search text of `query`, if any, is matched
+    fn text_search(&self, query: &ProductQuery) -> TextSearch {
+        let Some(search) = query.search.as_deref().map(str::trim).filter(|s| !s.is_empty()) else {
+            return TextSearch::All;
+        };
+        if !self.full_text.load(Ordering::Relaxed) {
+            return TextSearch::Substring(format!("%{}%", search));
+        }
+        match full_text_query(search) {
+            Some(expression) => TextSearch::FullText(expression),
+            // Nothing but punctuation: there is no word to look for
+            None => TextSearch::All,
+        }
+    }
+
+    /// Seed the catalog when the products table is empty.
+    /// The schema itself is created by the migration runner.
+    pub async fn initialize(&self) -> Result<(), RepositoryError> {
         // Check if we need to seed data
         let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM products")
             .fetch_one(&self.pool)
@@ -37,70 +122,420 @@ impl SqliteProductRepository {
     }
 
     async fn seed_data(&self) -> Result<(), RepositoryError> {
-        let products = vec![
-            ("MacBook Pro 16\"", Some("Apple MacBook Pro with M2 chip"), 2499.99, 10),
-            ("iPhone 15 Pro", Some("Latest iPhone with titanium design"), 999.99, 25),
-            ("AirPods Pro", Some("Wireless earbuds with noise cancellation"), 249.99, 50),
-            ("iPad Air", Some("Lightweight tablet for creativity"), 599.99, 15),
-            ("Apple Watch Ultra", Some("Adventure-ready smartwatch"), 799.99, 8),
+        // Name, description, price in minor units, stock, tags and attributes
+        type Seed = (&'static str, Option<&'static str>, i64, i32, &'static [&'static str], &'static [(&'static str, &'static str)]);
+        let products: Vec<Seed> = vec![
+            ("MacBook Pro 16\"", Some("Apple MacBook Pro with M2 chip"), 249999, 10,
+                &["laptop"], &[("brand", "Apple"), ("color", "Space Gray"), ("storage", "512GB")]),
+            ("iPhone 15 Pro", Some("Latest iPhone with titanium design"), 99999, 25,
+                &["phone", "5g"], &[("brand", "Apple"), ("color", "Natural Titanium"), ("storage", "128GB")]),
+            ("AirPods Pro", Some("Wireless earbuds with noise cancellation"), 24999, 50,
+                &["audio", "wireless"], &[("brand", "Apple"), ("color", "White")]),
+            ("iPad Air", Some("Lightweight tablet for creativity"), 59999, 15,
+                &["tablet"], &[("brand", "Apple"), ("color", "Space Gray"), ("storage", "64GB")]),
+            ("Apple Watch Ultra", Some("Adventure-ready smartwatch"), 79999, 8,
+                &["wearable", "wireless"], &[("brand", "Apple"), ("color", "Natural Titanium")]),
         ];
 
-        for (name, description, price, stock) in products {
-            let now = Utc::now().to_rfc3339();
-            sqlx::query(
-                "INSERT INTO products (name, description, price, stock, created_at, updated_at) 
-                 VALUES (?, ?, ?, ?, ?, ?)"
+        let mut tx = self.pool.begin().await?;
+        for (name, description, price_minor, stock, tags, attributes) in products {
+            let now = Utc::now();
+            let id: i64 = sqlx::query(
+                "INSERT INTO products (name, description, price_minor, currency, stock, created_at, updated_at) 
+                 VALUES (?, ?, ?, ?, ?, ?, ?) 
+                 RETURNING id"
             )
             .bind(name)
             .bind(description)
-            .bind(price)
+            .bind(price_minor)
+            .bind(Currency::USD.code())
             .bind(stock)
-            .bind(&now)
-            .bind(&now)
-            .execute(&self.pool)
+            .bind(format_timestamp(&now))
+            .bind(format_timestamp(&now))
+            .fetch_one(&mut *tx)
+            .await?
+            .get("id");
+
+            for tag in tags {
+                sqlx::query("INSERT INTO product_tags (product_id, tag) VALUES (?, ?)")
+                    .bind(id)
+                    .bind(tag)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            for (key, value) in attributes {
+                sqlx::query("INSERT INTO product_attributes (product_id, key, value) VALUES (?, ?, ?)")
+                    .bind(id)
+                    .bind(key)
+                    .bind(value)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            insert_stock_movement(&mut tx, &ProductId::new(id)?, &StockMovement {
+                delta: stock,
+                reason: StockMovementReason::Restock,
+                actor: "system".to_string(),
+                note: Some("Seed data".to_string()),
+                stock_after: stock,
+                created_at: now,
+            })
             .await?;
         }
+        tx.commit().await?;
 
         Ok(())
     }
 
-    fn row_to_product(&self, row: &sqlx::sqlite::SqliteRow) -> Result<Product, RepositoryError> {
+    fn row_to_product(&self, row: &SqliteRow) -> Result<Product, RepositoryError> {
         let id: i64 = row.get("id");
         let name: String = row.get("name");
         let description: Option<String> = row.get("description");
-        let price: f64 = row.get("price");
+        let price_minor: i64 = row.get("price_minor");
+        let currency: String = row.get("currency");
         let stock: i32 = row.get("stock");
+        let reserved: i32 = row.get("reserved");
         let created_at: String = row.get("created_at");
         let updated_at: String = row.get("updated_at");
+        let version: i64 = row.get("version");
 
         // Parse timestamps
-        let _created_at = created_at.parse::<chrono::DateTime<Utc>>()
+        let created_at = parse_timestamp(&created_at)
             .map_err(|e| RepositoryError::Internal(format!("Invalid created_at: {}", e)))?;
-        let _updated_at = updated_at.parse::<chrono::DateTime<Utc>>()
+        let updated_at = parse_timestamp(&updated_at)
             .map_err(|e| RepositoryError::Internal(format!("Invalid updated_at: {}", e)))?;
 
         // Create value objects
         let product_id = ProductId::new(id)?;
         let product_name = ProductName::new(name)?;
-        let money = Money::new(price)?;
+        let money = Money::new(price_minor, Currency::new(&currency)?)?;
         let stock_quantity = StockQuantity::new(stock)?;
+        let reserved_quantity = StockQuantity::new(reserved)?;
+
+        // Rehydrate the product with its stored timestamps
+        let product = Product::reconstitute(
+            product_id,
+            product_name,
+            description,
+            money,
+            stock_quantity,
+            reserved_quantity,
+            created_at,
+            updated_at,
+            version,
+        );
 
-        // Create product with correct timestamps
-        let product = Product::new(product_id, product_name, description, money, stock_quantity);
-        
-        // We need to set the actual timestamps from DB
-        // For now, we'll create a new Product and trust the timestamps from the constructor
-        // In a real implementation, you'd want to have setters or a factory method
-        
         Ok(product)
     }
+
+    /// Explain why a versioned write matched no row: the product is gone,
+    /// or it exists with a different version
+    async fn missing_or_conflict(&self, id: &ProductId) -> Result<RepositoryError, RepositoryError> {
+        if self.exists(id).await? {
+            Ok(RepositoryError::ConcurrentModification)
+        } else {
+            Ok(RepositoryError::NotFound)
+        }
+    }
+
+    /// Attach the tags and attributes of a batch of products, with one query for each
+    async fn with_details(&self, products: Vec<Product>) -> Result<Vec<Product>, RepositoryError> {
+        if products.is_empty() {
+            return Ok(products);
+        }
+        let ids: Vec<i64> = products.iter().map(|product| product.id().value()).collect();
+
+        let mut select = QueryBuilder::new("SELECT product_id, tag FROM product_tags WHERE product_id IN (");
+        push_list(&mut select, &ids);
+        let mut tags: HashMap<i64, Vec<Tag>> = HashMap::new();
+        for row in select.build().fetch_all(&self.pool).await? {
+            tags.entry(row.get("product_id")).or_default().push(Tag::new(row.get("tag"))?);
+        }
+
+        let mut select = QueryBuilder::new("SELECT product_id, key, value FROM product_attributes WHERE product_id IN (");
+        push_list(&mut select, &ids);
+        let mut attributes: HashMap<i64, Vec<ProductAttribute>> = HashMap::new();
+        for row in select.build().fetch_all(&self.pool).await? {
+            attributes
+                .entry(row.get("product_id"))
+                .or_default()
+                .push(ProductAttribute::new(row.get("key"), row.get("value"))?);
+        }
+
+        Ok(products
+            .into_iter()
+            .map(|product| {
+                let id = product.id().value();
+                let mut tags = tags.remove(&id).unwrap_or_default();
+                let mut attributes = attributes.remove(&id).unwrap_or_default();
+                tags.sort();
+                attributes.sort();
+                product.with_details(tags, attributes)
+            })
+            .collect())
+    }
+
+    /// Count the values in `source` (`product_tags` or `product_attributes`) across the
+    /// products matching `query`, grouped by `key` and `value` columns. `narrow` can add
+    /// conditions on the rows counted, e.g. to a single attribute key.
+    async fn count_values<'a>(
+        &self,
+        source: &str,
+        key: &str,
+        value: &str,
+        query: &'a ProductQuery,
+        narrow: impl FnOnce(&mut QueryBuilder<'a, Sqlite>),
+    ) -> Result<Vec<(String, FacetCount)>, RepositoryError> {
+        let search = self.text_search(query);
+        let mut select = QueryBuilder::new(format!(
+            "SELECT {} AS facet_key, {} AS facet_value, COUNT(*) AS count FROM {} WHERE product_id IN (SELECT products.id",
+            key, value, source
+        ));
+        push_source(&mut select, &search);
+        push_filters(&mut select, query, &search);
+        select.push(")");
+        narrow(&mut select);
+        select.push(" GROUP BY facet_key, facet_value ORDER BY count DESC, facet_value");
+
+        let rows = select.build().fetch_all(&self.pool).await?;
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let count = FacetCount { value: row.get("facet_value"), count: row.get::<i64, _>("count") as usize };
+                (row.get("facet_key"), count)
+            })
+            .collect())
+    }
+}
+
+/// Write a product's mutable fields if its stored version still matches, bumping the version,
+/// and append its pending stock movements to the ledger. Returns false when no row matched.
+pub(crate) async fn write_product(conn: &mut SqliteConnection, product: &Product) -> Result<bool, RepositoryError> {
+    let result = sqlx::query(
+        "UPDATE products 
+         SET name = ?, description = ?, price_minor = ?, currency = ?, stock = ?, reserved = ?, updated_at = ?, 
+             version = version + 1 
+         WHERE id = ? AND version = ?"
+    )
+    .bind(product.name().value())
+    .bind(product.description())
+    .bind(product.price().amount_minor())
+    .bind(product.price().currency().code())
+    .bind(product.stock().value())
+    .bind(product.reserved().value())
+    .bind(format_timestamp(product.updated_at()))
+    .bind(product.id().value())
+    .bind(product.version())
+    .execute(&mut *conn)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    for movement in product.pending_stock_movements() {
+        insert_stock_movement(conn, product.id(), movement).await?;
+    }
+    if product.details_changed() {
+        write_details(conn, product.id(), product).await?;
+    }
+    Ok(true)
+}
+
+/// Replace the stored tags and attributes of product `id` with `product`'s
+async fn write_details(conn: &mut SqliteConnection, id: &ProductId, product: &Product) -> Result<(), RepositoryError> {
+    sqlx::query("DELETE FROM product_tags WHERE product_id = ?")
+        .bind(id.value())
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM product_attributes WHERE product_id = ?")
+        .bind(id.value())
+        .execute(&mut *conn)
+        .await?;
+    for tag in product.tags() {
+        sqlx::query("INSERT INTO product_tags (product_id, tag) VALUES (?, ?)")
+            .bind(id.value())
+            .bind(tag.value())
+            .execute(&mut *conn)
+            .await?;
+    }
+    for attribute in product.attributes() {
+        sqlx::query("INSERT INTO product_attributes (product_id, key, value) VALUES (?, ?, ?)")
+            .bind(id.value())
+            .bind(attribute.key())
+            .bind(attribute.value())
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn insert_stock_movement(
+    conn: &mut SqliteConnection,
+    product_id: &ProductId,
+    movement: &StockMovement,
+) -> Result<(), RepositoryError> {
+    sqlx::query(
+        "INSERT INTO stock_movements (product_id, delta, reason, actor, note, stock_after, created_at) 
+         VALUES (?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(product_id.value())
+    .bind(movement.delta)
+    .bind(movement.reason.as_str())
+    .bind(&movement.actor)
+    .bind(&movement.note)
+    .bind(movement.stock_after)
+    .bind(format_timestamp(&movement.created_at))
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+fn row_to_stock_movement(row: &SqliteRow) -> Result<StockMovement, RepositoryError> {
+    let created_at = parse_timestamp(&row.get::<String, _>("created_at"))
+        .map_err(|e| RepositoryError::Internal(format!("Invalid created_at: {}", e)))?;
+
+    Ok(StockMovement {
+        delta: row.get("delta"),
+        reason: StockMovementReason::try_from(row.get::<String, _>("reason").as_str())?,
+        actor: row.get("actor"),
+        note: row.get("note"),
+        stock_after: row.get("stock_after"),
+        created_at,
+    })
+}
+
+/// How a listing matches its search text
+enum TextSearch {
+    All,
+    /// An FTS5 expression, from `full_text_query`
+    FullText(String),
+    /// A `LIKE` pattern, used when SQLite has no FTS5
+    Substring(String),
+}
+
+/// Translate search box text into an FTS5 expression. Double-quoted text matches as a phrase;
+/// every other word matches as a prefix, so `mac pro` finds "MacBook Pro". Only letters and
+/// digits are passed on, so the text can never form FTS5 operators or syntax errors.
+fn full_text_query(search: &str) -> Option<String> {
+    let mut terms = Vec::new();
+    // Odd parts lie between quotes; an unclosed quote runs to the end
+    for (index, part) in search.split('"').enumerate() {
+        let words: Vec<&str> = part.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
+        if words.is_empty() {
+            continue;
+        }
+        if index % 2 == 1 {
+            terms.push(format!("\"{}\"", words.join(" ")));
+        } else {
+            terms.extend(words.iter().map(|word| format!("\"{}\"*", word)));
+        }
+    }
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// `FROM products`, joined with the full-text matches when searching the index
+fn push_source(builder: &mut QueryBuilder<'_, Sqlite>, search: &TextSearch) {
+    builder.push(" FROM products");
+    if let TextSearch::FullText(expression) = search {
+        builder
+            .push(SEARCH_MATCHES)
+            .push_bind(expression.clone())
+            .push(") AS matches ON matches.match_id = products.id");
+    }
+}
+
+/// Bind each of `values` in a comma-separated list and close the parenthesis before it
+fn push_list<'a, T>(builder: &mut QueryBuilder<'a, Sqlite>, values: &'a [T])
+where
+    T: 'a + sqlx::Encode<'a, Sqlite> + sqlx::Type<Sqlite> + Send + Sync,
+    &'a T: sqlx::Encode<'a, Sqlite> + sqlx::Type<Sqlite>,
+{
+    let mut list = builder.separated(", ");
+    for value in values {
+        list.push_bind(value);
+    }
+    list.push_unseparated(")");
+}
+
+/// Append the WHERE clause for a product query's filters
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, Sqlite>, query: &'a ProductQuery, search: &TextSearch) {
+    builder.push(" WHERE 1 = 1");
+
+    if let TextSearch::Substring(pattern) = search {
+        builder
+            .push(" AND (name LIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR description LIKE ")
+            .push_bind(pattern.clone())
+            .push(")");
+    }
+    if let Some(slug) = &query.category {
+        builder
+            .push(
+                " AND products.id IN (SELECT product_id FROM product_categories WHERE category_id IN (
+                    WITH RECURSIVE tree (id) AS (
+                        SELECT id FROM categories WHERE slug = "
+            )
+            .push_bind(slug.as_str())
+            .push(
+                " UNION SELECT categories.id FROM categories JOIN tree ON categories.parent_id = tree.id
+                    )
+                    SELECT id FROM tree))"
+            );
+    }
+    if !query.tags.is_empty() {
+        builder.push(" AND products.id IN (SELECT product_id FROM product_tags WHERE tag IN (");
+        push_list(builder, &query.tags);
+        builder.push(")");
+    }
+    for (key, values) in &query.attributes {
+        builder
+            .push(" AND products.id IN (SELECT product_id FROM product_attributes WHERE key = ")
+            .push_bind(key.as_str())
+            .push(" AND value IN (");
+        push_list(builder, values);
+        builder.push(")");
+    }
+    if let Some(price) = &query.price {
+        builder.push(" AND currency = ").push_bind(price.currency.code());
+        if let Some(min) = price.min_minor {
+            builder.push(" AND price_minor >= ").push_bind(min);
+        }
+        if let Some(max) = price.max_minor {
+            builder.push(" AND price_minor <= ").push_bind(max);
+        }
+    }
+    if query.in_stock_only {
+        builder.push(" AND stock > 0");
+    }
+    if let Some(created_after) = &query.created_after {
+        builder
+            .push(" AND julianday(created_at) > julianday(")
+            .push_bind(format_timestamp(created_after))
+            .push(")");
+    }
+}
+
+/// Format a timestamp for storage, keeping full sub-second precision
+pub(crate) fn format_timestamp(value: &DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Parse a stored timestamp. Rows written by the application use RFC 3339;
+/// rows that fell back to the column default use SQLite's `datetime('now')` format.
+pub(crate) fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
+    value.parse::<DateTime<Utc>>().or_else(|err| {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+            .map(|naive| naive.and_utc())
+            .map_err(|_| err)
+    })
 }
 
 #[async_trait]
 impl ProductRepository for SqliteProductRepository {
     async fn find_all(&self) -> Result<Vec<Product>, RepositoryError> {
         let rows = sqlx::query(
-            "SELECT id, name, description, price, stock, created_at, updated_at 
+            "SELECT id, name, description, price_minor, currency, stock, reserved, created_at, updated_at, version 
              FROM products 
              ORDER BY created_at DESC"
         )
@@ -112,12 +547,12 @@ impl ProductRepository for SqliteProductRepository {
             products.push(self.row_to_product(&row)?);
         }
 
-        Ok(products)
+        self.with_details(products).await
     }
 
     async fn find_by_id(&self, id: &ProductId) -> Result<Option<Product>, RepositoryError> {
         let row = sqlx::query(
-            "SELECT id, name, description, price, stock, created_at, updated_at 
+            "SELECT id, name, description, price_minor, currency, stock, reserved, created_at, updated_at, version 
              FROM products 
              WHERE id = ?"
         )
@@ -126,7 +561,7 @@ impl ProductRepository for SqliteProductRepository {
         .await?;
 
         match row {
-            Some(row) => Ok(Some(self.row_to_product(&row)?)),
+            Some(row) => Ok(self.with_details(vec![self.row_to_product(&row)?]).await?.pop()),
             None => Ok(None),
         }
     }
@@ -134,7 +569,7 @@ impl ProductRepository for SqliteProductRepository {
     async fn search_by_name(&self, query: &str) -> Result<Vec<Product>, RepositoryError> {
         let search_term = format!("%{}%", query);
         let rows = sqlx::query(
-            "SELECT id, name, description, price, stock, created_at, updated_at 
+            "SELECT id, name, description, price_minor, currency, stock, reserved, created_at, updated_at, version 
              FROM products 
              WHERE name LIKE ? OR description LIKE ? 
              ORDER BY created_at DESC"
@@ -149,54 +584,152 @@ impl ProductRepository for SqliteProductRepository {
             products.push(self.row_to_product(&row)?);
         }
 
-        Ok(products)
+        self.with_details(products).await
     }
 
-    async fn save(&self, product: Product) -> Result<Product, RepositoryError> {
-        let now = Utc::now().to_rfc3339();
-        
+    async fn find_page(&self, query: &ProductQuery) -> Result<ProductPage, RepositoryError> {
+        let search = self.text_search(query);
+        let full_text = matches!(search, TextSearch::FullText(_));
+
+        let mut count = QueryBuilder::new("SELECT COUNT(*) as count");
+        push_source(&mut count, &search);
+        push_filters(&mut count, query, &search);
+        let total: i64 = count.build().fetch_one(&self.pool).await?.get("count");
+
+        let direction = match query.direction {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        };
+        // Sort columns come from a closed enum, never from user input
+        let (sort_column, sort_direction) = match query.sort_by {
+            // A lower bm25 rank is a better match, so "descending relevance" sorts it ascending
+            ProductSortField::Relevance if full_text => {
+                ("rank", if query.direction == SortDirection::Desc { "ASC" } else { "DESC" })
+            }
+            ProductSortField::CreatedAt | ProductSortField::Relevance => ("julianday(created_at)", direction),
+            ProductSortField::Name => ("name COLLATE NOCASE", direction),
+            ProductSortField::Price => ("price_minor", direction),
+            ProductSortField::Stock => ("stock", direction),
+        };
+
+        let mut select = QueryBuilder::new(
+            "SELECT id, name, description, price_minor, currency, stock, reserved, created_at, updated_at, version"
+        );
+        if full_text {
+            select.push(", name_highlight, description_highlight");
+        }
+        push_source(&mut select, &search);
+        push_filters(&mut select, query, &search);
+        select
+            .push(format!(" ORDER BY {} {}, id {}", sort_column, sort_direction, direction))
+            .push(" LIMIT ")
+            .push_bind(query.limit as i64)
+            .push(" OFFSET ")
+            .push_bind(query.offset as i64);
+
+        let rows = select.build().fetch_all(&self.pool).await?;
+
+        let mut products = Vec::new();
+        let mut highlights = Vec::new();
+        for row in rows {
+            if full_text {
+                highlights.push(SearchHighlight {
+                    product_id: row.get("id"),
+                    name: row.get("name_highlight"),
+                    snippet: row.get::<Option<String>, _>("description_highlight").unwrap_or_default(),
+                });
+            }
+            products.push(self.row_to_product(&row)?);
+        }
+
+        Ok(ProductPage {
+            products: self.with_details(products).await?,
+            total: total as usize,
+            highlights,
+        })
+    }
+
+    async fn find_facets(&self, query: &ProductQuery) -> Result<Facets, RepositoryError> {
+        let untagged = ProductQuery { tags: Vec::new(), ..query.clone() };
+        let tags = self.count_values("product_tags", "''", "tag", &untagged, |_| {}).await?;
+
+        // Keys nobody filters on are counted in one go with every filter applied...
+        let selected: Vec<&str> = query.attributes.keys().map(String::as_str).collect();
+        let mut attributes = self
+            .count_values("product_attributes", "key", "value", query, |select| {
+                if !selected.is_empty() {
+                    select.push(" AND key NOT IN (");
+                    push_list(select, &selected);
+                }
+            })
+            .await?;
+        // ...and each filtered key with every filter but its own
+        for key in query.attributes.keys() {
+            let mut relaxed = query.clone();
+            relaxed.attributes.remove(key);
+            let counts = self
+                .count_values("product_attributes", "key", "value", &relaxed, |select| {
+                    select.push(" AND key = ").push_bind(key.as_str());
+                })
+                .await?;
+            attributes.extend(counts);
+        }
+
+        let mut by_key: BTreeMap<String, Vec<FacetCount>> = BTreeMap::new();
+        for (key, count) in attributes {
+            by_key.entry(key).or_default().push(count);
+        }
+        Ok(Facets {
+            tags: tags.into_iter().map(|(_, count)| count).collect(),
+            attributes: by_key.into_iter().collect(),
+        })
+    }
+
+    async fn save(&self, product: Product, events: &[ProductEvent]) -> Result<Product, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
         let result = sqlx::query(
-            "INSERT INTO products (name, description, price, stock, created_at, updated_at) 
-             VALUES (?, ?, ?, ?, ?, ?) 
+            "INSERT INTO products (name, description, price_minor, currency, stock, created_at, updated_at, version) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?) 
              RETURNING id"
         )
         .bind(product.name().value())
         .bind(product.description())
-        .bind(product.price().value())
+        .bind(product.price().amount_minor())
+        .bind(product.price().currency().code())
         .bind(product.stock().value())
-        .bind(&now)
-        .bind(&now)
-        .fetch_one(&self.pool)
+        .bind(format_timestamp(product.created_at()))
+        .bind(format_timestamp(product.updated_at()))
+        .bind(product.version())
+        .fetch_one(&mut *tx)
         .await?;
 
-        let id: i64 = result.get("id");
+        let id = ProductId::new(result.get("id"))?;
+        for movement in product.pending_stock_movements() {
+            insert_stock_movement(&mut tx, &id, movement).await?;
+        }
+        write_details(&mut tx, &id, &product).await?;
+        for event in events {
+            enqueue_event(&mut tx, &event.clone().with_product_id(id.clone())).await?;
+        }
+        tx.commit().await?;
         
         // Return the saved product with the new ID
-        self.find_by_id(&ProductId::new(id)?)
+        self.find_by_id(&id)
             .await?
             .ok_or(RepositoryError::Internal("Failed to retrieve saved product".to_string()))
     }
 
-    async fn update(&self, product: Product) -> Result<Product, RepositoryError> {
-        let now = Utc::now().to_rfc3339();
-        
-        let result = sqlx::query(
-            "UPDATE products 
-             SET name = ?, description = ?, price = ?, stock = ?, updated_at = ? 
-             WHERE id = ?"
-        )
-        .bind(product.name().value())
-        .bind(product.description())
-        .bind(product.price().value())
-        .bind(product.stock().value())
-        .bind(&now)
-        .bind(product.id().value())
-        .execute(&self.pool)
-        .await?;
+    async fn update(&self, product: Product, events: &[ProductEvent]) -> Result<Product, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        if !write_product(&mut tx, &product).await? {
+            tx.rollback().await?;
+            return Err(self.missing_or_conflict(product.id()).await?);
+        }
 
-        if result.rows_affected() == 0 {
-            return Err(RepositoryError::NotFound);
+        for event in events {
+            enqueue_event(&mut tx, event).await?;
         }
+        tx.commit().await?;
 
         // Return the updated product
         self.find_by_id(product.id())
@@ -204,13 +737,37 @@ impl ProductRepository for SqliteProductRepository {
             .ok_or(RepositoryError::Internal("Failed to retrieve updated product".to_string()))
     }
 
-    async fn delete(&self, id: &ProductId) -> Result<bool, RepositoryError> {
-        let result = sqlx::query("DELETE FROM products WHERE id = ?")
+    async fn delete(
+        &self,
+        id: &ProductId,
+        expected_version: Option<i64>,
+        events: &[ProductEvent],
+    ) -> Result<bool, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query("DELETE FROM products WHERE id = ? AND (? IS NULL OR version = ?)")
             .bind(id.value())
-            .execute(&self.pool)
+            .bind(expected_version)
+            .bind(expected_version)
+            .execute(&mut *tx)
             .await?;
 
-        Ok(result.rows_affected() > 0)
+        if result.rows_affected() == 0 {
+            tx.rollback().await?;
+            if expected_version.is_none() {
+                return Ok(false);
+            }
+            return match self.missing_or_conflict(id).await? {
+                RepositoryError::NotFound => Ok(false),
+                conflict => Err(conflict),
+            };
+        }
+
+        for event in events {
+            enqueue_event(&mut tx, event).await?;
+        }
+        tx.commit().await?;
+
+        Ok(true)
     }
 
     async fn exists(&self, id: &ProductId) -> Result<bool, RepositoryError> {
@@ -228,4 +785,478 @@ impl ProductRepository for SqliteProductRepository {
         // The actual ID will be generated during insertion
         Ok(ProductId::new(1)?) // This will be overridden by auto-increment
     }
-}
\ No newline at end of file
+}
+
+fn row_to_reservation(row: &SqliteRow) -> Result<StockReservation, RepositoryError> {
+    let expires_at = parse_timestamp(&row.get::<String, _>("expires_at"))
+        .map_err(|e| RepositoryError::Internal(format!("Invalid expires_at: {}", e)))?;
+    let created_at = parse_timestamp(&row.get::<String, _>("created_at"))
+        .map_err(|e| RepositoryError::Internal(format!("Invalid created_at: {}", e)))?;
+    let updated_at = parse_timestamp(&row.get::<String, _>("updated_at"))
+        .map_err(|e| RepositoryError::Internal(format!("Invalid updated_at: {}", e)))?;
+
+    Ok(StockReservation::reconstitute(
+        ReservationId::new(row.get("id"))?,
+        ProductId::new(row.get("product_id"))?,
+        row.get("quantity"),
+        ReservationStatus::try_from(row.get::<String, _>("status").as_str())?,
+        expires_at,
+        created_at,
+        updated_at,
+    ))
+}
+
+#[async_trait]
+impl InventoryRepository for SqliteProductRepository {
+    async fn find_reservation(&self, id: &ReservationId) -> Result<Option<StockReservation>, RepositoryError> {
+        let row = sqlx::query(
+            "SELECT id, product_id, quantity, status, expires_at, created_at, updated_at 
+             FROM stock_reservations 
+             WHERE id = ?"
+        )
+        .bind(id.value())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| row_to_reservation(&row)).transpose()
+    }
+
+    async fn find_expired_reservations(
+        &self,
+        now: DateTime<Utc>,
+        product_id: Option<&ProductId>,
+        limit: usize,
+    ) -> Result<Vec<StockReservation>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT id, product_id, quantity, status, expires_at, created_at, updated_at 
+             FROM stock_reservations 
+             WHERE status = 'active' AND julianday(expires_at) <= julianday(?) 
+               AND (? IS NULL OR product_id = ?) 
+             ORDER BY id 
+             LIMIT ?"
+        )
+        .bind(format_timestamp(&now))
+        .bind(product_id.map(ProductId::value))
+        .bind(product_id.map(ProductId::value))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_reservation).collect()
+    }
+
+    async fn create_reservation(
+        &self,
+        product: Product,
+        reservation: StockReservation,
+    ) -> Result<(Product, StockReservation), RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        if !write_product(&mut tx, &product).await? {
+            tx.rollback().await?;
+            return Err(self.missing_or_conflict(product.id()).await?);
+        }
+
+        let id: i64 = sqlx::query(
+            "INSERT INTO stock_reservations (product_id, quantity, status, expires_at, created_at, updated_at) 
+             VALUES (?, ?, ?, ?, ?, ?) 
+             RETURNING id"
+        )
+        .bind(reservation.product_id().value())
+        .bind(reservation.quantity())
+        .bind(reservation.status().as_str())
+        .bind(format_timestamp(&reservation.expires_at()))
+        .bind(format_timestamp(&reservation.created_at()))
+        .bind(format_timestamp(&reservation.updated_at()))
+        .fetch_one(&mut *tx)
+        .await?
+        .get("id");
+        tx.commit().await?;
+
+        self.reload(product.id(), &ReservationId::new(id)?).await
+    }
+
+    async fn update_reservation(
+        &self,
+        product: Product,
+        reservation: StockReservation,
+        events: &[ProductEvent],
+    ) -> Result<(Product, StockReservation), RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        if !write_product(&mut tx, &product).await? {
+            tx.rollback().await?;
+            return Err(self.missing_or_conflict(product.id()).await?);
+        }
+
+        // Only an active reservation can move on, and only once
+        let result = sqlx::query(
+            "UPDATE stock_reservations SET status = ?, updated_at = ? WHERE id = ? AND status = 'active'"
+        )
+        .bind(reservation.status().as_str())
+        .bind(format_timestamp(&reservation.updated_at()))
+        .bind(reservation.id().value())
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Err(RepositoryError::ConcurrentModification);
+        }
+
+        for event in events {
+            enqueue_event(&mut tx, event).await?;
+        }
+        tx.commit().await?;
+
+        self.reload(product.id(), reservation.id()).await
+    }
+
+    async fn find_stock_movements(&self, product_id: &ProductId, limit: usize) -> Result<Vec<StockMovement>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT delta, reason, actor, note, stock_after, created_at 
+             FROM stock_movements 
+             WHERE product_id = ? 
+             ORDER BY id DESC 
+             LIMIT ?"
+        )
+        .bind(product_id.value())
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_stock_movement).collect()
+    }
+
+    async fn reconcile_stock(&self) -> Result<Vec<StockDiscrepancy>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT p.id, p.stock, COALESCE(SUM(m.delta), 0) AS ledger_total 
+             FROM products p 
+             LEFT JOIN stock_movements m ON m.product_id = p.id 
+             GROUP BY p.id, p.stock 
+             HAVING p.stock != COALESCE(SUM(m.delta), 0) 
+             ORDER BY p.id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(StockDiscrepancy {
+                    product_id: ProductId::new(row.get("id"))?,
+                    stock: row.get("stock"),
+                    ledger_total: row.get("ledger_total"),
+                })
+            })
+            .collect()
+    }
+
+    async fn next_reservation_id(&self) -> Result<ReservationId, RepositoryError> {
+        // Placeholder; the real ID is assigned by auto-increment on insert
+        Ok(ReservationId::new(1)?)
+    }
+}
+
+impl SqliteProductRepository {
+    async fn reload(
+        &self,
+        product_id: &ProductId,
+        reservation_id: &ReservationId,
+    ) -> Result<(Product, StockReservation), RepositoryError> {
+        let product = self.find_by_id(product_id).await?
+            .ok_or(RepositoryError::Internal("Failed to retrieve reserved product".to_string()))?;
+        let reservation = self.find_reservation(reservation_id).await?
+            .ok_or(RepositoryError::Internal("Failed to retrieve reservation".to_string()))?;
+        Ok((product, reservation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{PriceRange, ProductAttribute, Tag};
+    use crate::infrastructure::migrated_pool;
+    use chrono::TimeZone;
+
+    async fn repository() -> SqliteProductRepository {
+        let repository = SqliteProductRepository::new(migrated_pool().await);
+        repository.initialize().await.expect("seed data");
+        repository
+    }
+
+    fn product(created_at: DateTime<Utc>, updated_at: DateTime<Utc>) -> Product {
+        Product::reconstitute(
+            ProductId::new(1).unwrap(),
+            ProductName::new("Mechanical Keyboard".to_string()).unwrap(),
+            Some("Hot-swappable switches".to_string()),
+            Money::new(12_345, Currency::new("EUR").unwrap()).unwrap(),
+            StockQuantity::new(7).unwrap(),
+            StockQuantity::new(0).unwrap(),
+            created_at,
+            updated_at,
+            1,
+        )
+    }
+
+    fn with_id(product: &Product, id: &ProductId) -> Product {
+        Product::reconstitute(
+            id.clone(),
+            product.name().clone(),
+            product.description().clone(),
+            product.price().clone(),
+            product.stock().clone(),
+            product.reserved().clone(),
+            *product.created_at(),
+            *product.updated_at(),
+            product.version(),
+        )
+    }
+
+    #[tokio::test]
+    async fn save_and_find_by_id_round_trips_every_field() {
+        let repository = repository().await;
+        let created_at = Utc.with_ymd_and_hms(2023, 4, 5, 6, 7, 8).unwrap()
+            + chrono::Duration::nanoseconds(123_456_789);
+        let updated_at = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let original = product(created_at, updated_at);
+
+        let saved = repository.save(original.clone(), &[]).await.unwrap();
+        let loaded = repository.find_by_id(saved.id()).await.unwrap().unwrap();
+
+        assert_eq!(saved, with_id(&original, saved.id()));
+        assert_eq!(loaded, saved);
+        assert_eq!(*loaded.created_at(), created_at);
+        assert_eq!(*loaded.updated_at(), updated_at);
+    }
+
+    #[tokio::test]
+    async fn list_and_search_return_stored_timestamps() {
+        let repository = repository().await;
+        let created_at = Utc.with_ymd_and_hms(2020, 2, 29, 12, 0, 0).unwrap();
+        let saved = repository.save(product(created_at, created_at), &[]).await.unwrap();
+
+        let listed = repository.find_all().await.unwrap();
+        let found = repository.search_by_name("Keyboard").await.unwrap();
+
+        assert_eq!(listed.iter().find(|p| p.id() == saved.id()), Some(&saved));
+        assert_eq!(found, vec![saved]);
+    }
+
+    #[tokio::test]
+    async fn update_keeps_created_at_and_persists_updated_at() {
+        let repository = repository().await;
+        let created_at = Utc.with_ymd_and_hms(2022, 6, 1, 9, 30, 0).unwrap();
+        let mut saved = repository.save(product(created_at, created_at), &[]).await.unwrap();
+
+        saved
+            .update(None, Some(None), None, Some(StockQuantity::new(3).unwrap()), "test")
+            .unwrap();
+        let updated = repository.update(saved.clone(), &[]).await.unwrap();
+
+        assert_eq!(*updated.created_at(), created_at);
+        assert_eq!(*updated.updated_at(), *saved.updated_at());
+        assert!(*updated.updated_at() > created_at);
+        assert_eq!(updated.description(), &None);
+        assert_eq!(updated.stock().value(), 3);
+        assert_eq!(updated.version(), saved.version() + 1);
+    }
+
+    #[tokio::test]
+    async fn stale_writes_are_rejected_as_concurrent_modifications() {
+        let repository = repository().await;
+        let now = Utc::now();
+        let saved = repository.save(product(now, now), &[]).await.unwrap();
+
+        // Two writers load version 1; the first update wins
+        let mut first = saved.clone();
+        let mut second = saved.clone();
+        first.update(None, None, None, Some(StockQuantity::new(1).unwrap()), "test").unwrap();
+        second.update(None, None, None, Some(StockQuantity::new(2).unwrap()), "test").unwrap();
+        repository.update(first, &[]).await.unwrap();
+
+        assert_eq!(repository.update(second, &[]).await, Err(RepositoryError::ConcurrentModification));
+        assert_eq!(
+            repository.delete(saved.id(), Some(saved.version()), &[]).await,
+            Err(RepositoryError::ConcurrentModification)
+        );
+        assert_eq!(repository.delete(saved.id(), Some(saved.version() + 1), &[]).await, Ok(true));
+        assert_eq!(repository.delete(saved.id(), Some(1), &[]).await, Ok(false));
+    }
+
+    #[tokio::test]
+    async fn rows_using_sqlite_default_timestamps_are_readable() {
+        let repository = repository().await;
+        sqlx::query(
+            "INSERT INTO products (name, price_minor, currency, stock, created_at, updated_at)
+             VALUES ('Legacy', 100, 'USD', 1, '2021-03-04 05:06:07', '2021-03-04 05:06:07')"
+        )
+        .execute(&repository.pool)
+        .await
+        .unwrap();
+
+        let found = repository.search_by_name("Legacy").await.unwrap();
+
+        assert_eq!(
+            *found[0].created_at(),
+            Utc.with_ymd_and_hms(2021, 3, 4, 5, 6, 7).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn find_page_filters_sorts_and_counts() {
+        let repository = repository().await;
+        let usd = Currency::USD;
+        let query = ProductQuery {
+            price: Some(PriceRange { currency: usd, min_minor: Some(50_000), max_minor: Some(250_000) }),
+            in_stock_only: true,
+            sort_by: ProductSortField::Price,
+            direction: SortDirection::Asc,
+            offset: 1,
+            limit: 2,
+            ..ProductQuery::default()
+        };
+
+        let page = repository.find_page(&query).await.unwrap();
+
+        // Seeded USD prices in range: 599.99, 799.99, 999.99, 2499.99
+        let prices: Vec<i64> = page.products.iter().map(|p| p.price().amount_minor()).collect();
+        assert_eq!(page.total, 4);
+        assert_eq!(prices, vec![79_999, 99_999]);
+    }
+
+    fn search(text: &str) -> ProductQuery {
+        ProductQuery { search: Some(text.to_string()), sort_by: ProductSortField::Relevance, ..ProductQuery::default() }
+    }
+
+    async fn names(repository: &SqliteProductRepository, text: &str) -> Vec<String> {
+        let page = repository.find_page(&search(text)).await.unwrap();
+        page.products.iter().map(|p| p.name().value().to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn full_text_search_matches_words_phrases_and_prefixes_by_relevance() {
+        let repository = repository().await;
+        assert!(repository.enable_full_text_search().await.unwrap());
+
+        assert_eq!(names(&repository, "ipho").await, vec!["iPhone 15 Pro"]);
+        assert_eq!(names(&repository, "\"noise cancellation\"").await, vec!["AirPods Pro"]);
+        assert!(names(&repository, "\"cancellation noise\"").await.is_empty());
+        // Words match from their start only; "pad" is not a word of "iPad Air"
+        assert!(names(&repository, "pad").await.is_empty());
+        // A name match outranks a description match
+        assert_eq!(names(&repository, "apple").await, vec!["Apple Watch Ultra", "MacBook Pro 16\""]);
+        // Operators and stray quotes are never passed to FTS5
+        assert_eq!(
+            full_text_query(r#"mac "noise cancel" OR *( "pro"#).as_deref(),
+            Some(r#""mac"* "noise cancel" "OR"* "pro""#)
+        );
+        assert!(repository.find_page(&search("NEAR( ^ \"")).await.is_ok());
+
+        let page = repository.find_page(&search("watch")).await.unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.highlights, vec![SearchHighlight {
+            product_id: page.products[0].id().value(),
+            name: "Apple \u{2}Watch\u{3} Ultra".to_string(),
+            // "smartwatch" does not start with "watch"
+            snippet: "Adventure-ready smartwatch".to_string(),
+        }]);
+    }
+
+    #[tokio::test]
+    async fn the_search_index_follows_product_writes() {
+        let repository = repository().await;
+        repository.enable_full_text_search().await.unwrap();
+        let created_at = Utc.with_ymd_and_hms(2021, 3, 1, 0, 0, 0).unwrap();
+
+        let mut keyboard = repository.save(product(created_at, created_at), &[]).await.unwrap();
+        assert_eq!(names(&repository, "hot swap").await, vec!["Mechanical Keyboard"]);
+
+        keyboard
+            .update(Some(ProductName::new("Ergonomic Keyboard".to_string()).unwrap()), None, None, None, "test")
+            .unwrap();
+        let keyboard = repository.update(keyboard, &[]).await.unwrap();
+        assert!(names(&repository, "mechanical").await.is_empty());
+        assert_eq!(names(&repository, "ergo").await, vec!["Ergonomic Keyboard"]);
+
+        repository.delete(keyboard.id(), None, &[]).await.unwrap();
+        assert!(names(&repository, "keyboard").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_falls_back_to_substring_matching_without_the_index() {
+        let repository = repository().await;
+
+        let page = repository.find_page(&search("pad")).await.unwrap();
+
+        assert_eq!(page.products.iter().map(|p| p.name().value()).collect::<Vec<_>>(), vec!["iPad Air"]);
+        assert!(page.highlights.is_empty());
+    }
+
+    fn counts(facets: &[FacetCount]) -> Vec<(&str, usize)> {
+        facets.iter().map(|facet| (facet.value.as_str(), facet.count)).collect()
+    }
+
+    fn attribute_counts<'a>(facets: &'a Facets, key: &str) -> Vec<(&'a str, usize)> {
+        facets.attributes.iter().find(|(k, _)| k == key).map(|(_, values)| counts(values)).unwrap_or_default()
+    }
+
+    fn filtered(tags: &[&str], attributes: &[(&str, &str)]) -> ProductQuery {
+        let mut query = ProductQuery { tags: tags.iter().map(|tag| tag.to_string()).collect(), ..ProductQuery::default() };
+        for (key, value) in attributes {
+            query.attributes.entry(key.to_string()).or_default().push(value.to_string());
+        }
+        query
+    }
+
+    async fn listed(repository: &SqliteProductRepository, query: ProductQuery) -> Vec<String> {
+        let page = repository.find_page(&ProductQuery { sort_by: ProductSortField::Name, direction: SortDirection::Asc, ..query }).await.unwrap();
+        page.products.iter().map(|p| p.name().value().to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn tags_and_attributes_round_trip_and_filter_listings() {
+        let repository = repository().await;
+        let created_at = Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap();
+        let keyboard = product(created_at, created_at).with_details(
+            vec![Tag::new("mechanical").unwrap(), Tag::new("wireless").unwrap()],
+            vec![ProductAttribute::new("brand", "Keychron").unwrap(), ProductAttribute::new("color", "White").unwrap()],
+        );
+        let saved = repository.save(keyboard, &[]).await.unwrap();
+        let loaded = repository.find_by_id(saved.id()).await.unwrap().unwrap();
+        let tags: Vec<&str> = loaded.tags().iter().map(Tag::value).collect();
+        assert_eq!(tags, vec!["mechanical", "wireless"]);
+        assert_eq!(loaded.attributes(), saved.attributes());
+
+        // Values of one filter are alternatives; separate filters must all hold
+        assert_eq!(listed(&repository, filtered(&["wireless"], &[])).await, vec!["AirPods Pro", "Apple Watch Ultra", "Mechanical Keyboard"]);
+        assert_eq!(listed(&repository, filtered(&["laptop", "tablet"], &[])).await, vec!["iPad Air", "MacBook Pro 16\""]);
+        assert_eq!(listed(&repository, filtered(&["wireless"], &[("color", "White")])).await, vec!["AirPods Pro", "Mechanical Keyboard"]);
+        assert_eq!(
+            listed(&repository, filtered(&["wireless"], &[("color", "White"), ("brand", "Apple")])).await,
+            vec!["AirPods Pro"]
+        );
+        assert!(listed(&repository, filtered(&["no-such-tag"], &[])).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn facets_count_each_value_with_every_other_filter_applied() {
+        let repository = repository().await;
+
+        let facets = repository.find_facets(&ProductQuery::default()).await.unwrap();
+        assert_eq!(counts(&facets.tags), vec![("wireless", 2), ("5g", 1), ("audio", 1), ("laptop", 1), ("phone", 1), ("tablet", 1), ("wearable", 1)]);
+        assert_eq!(attribute_counts(&facets, "brand"), vec![("Apple", 5)]);
+        assert_eq!(attribute_counts(&facets, "storage"), vec![("128GB", 1), ("512GB", 1), ("64GB", 1)]);
+
+        // Choosing a colour narrows the other facets but leaves the colours to pick from
+        let facets = repository.find_facets(&filtered(&[], &[("color", "Space Gray")])).await.unwrap();
+        assert_eq!(attribute_counts(&facets, "color"), vec![("Natural Titanium", 2), ("Space Gray", 2), ("White", 1)]);
+        assert_eq!(attribute_counts(&facets, "storage"), vec![("512GB", 1), ("64GB", 1)]);
+        assert_eq!(counts(&facets.tags), vec![("laptop", 1), ("tablet", 1)]);
+
+        let facets = repository.find_facets(&filtered(&["wireless"], &[("color", "White")])).await.unwrap();
+        assert_eq!(attribute_counts(&facets, "color"), vec![("Natural Titanium", 1), ("White", 1)]);
+        assert_eq!(counts(&facets.tags), vec![("audio", 1), ("wireless", 1)]);
+        assert_eq!(attribute_counts(&facets, "storage"), vec![]);
+
+        // Facets follow the text search too
+        let facets = repository.find_facets(&ProductQuery { search: Some("pro".to_string()), ..ProductQuery::default() }).await.unwrap();
+        assert_eq!(attribute_counts(&facets, "brand"), vec![("Apple", 3)]);
+    }
+}
@@ -0,0 +1,182 @@
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, SaltString};
+use argon2::{Argon2, PasswordHasher as _, PasswordVerifier as _};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::domain::{
+    DomainError, Email, PasswordHasher, RepositoryError, Role, SessionRepository, User, UserId,
+    UserRepository,
+};
+use crate::infrastructure::repositories::{format_timestamp, parse_timestamp};
+
+/// Argon2id with the crate's default cost parameters, stored in PHC string format
+#[derive(Default)]
+pub struct Argon2PasswordHasher {
+    argon2: Argon2<'static>,
+}
+
+impl Argon2PasswordHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PasswordHasher for Argon2PasswordHasher {
+    fn hash(&self, password: &str) -> Result<String, DomainError> {
+        let salt = SaltString::generate(&mut OsRng);
+        self.argon2
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| DomainError::InvalidUser(format!("Could not hash password: {}", e)))
+    }
+
+    fn verify(&self, password: &str, password_hash: &str) -> bool {
+        PasswordHash::new(password_hash)
+            .is_ok_and(|hash| self.argon2.verify_password(password.as_bytes(), &hash).is_ok())
+    }
+}
+
+pub struct SqliteUserRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteUserRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+fn row_to_user(row: &SqliteRow) -> Result<User, RepositoryError> {
+    let created_at = parse_timestamp(&row.get::<String, _>("created_at"))
+        .map_err(|e| RepositoryError::Internal(format!("Invalid created_at: {}", e)))?;
+    let updated_at = parse_timestamp(&row.get::<String, _>("updated_at"))
+        .map_err(|e| RepositoryError::Internal(format!("Invalid updated_at: {}", e)))?;
+
+    Ok(User::reconstitute(
+        UserId::new(row.get("id"))?,
+        Email::new(&row.get::<String, _>("email"))?,
+        row.get("name"),
+        row.get("password_hash"),
+        Role::try_from(row.get::<String, _>("role").as_str())?,
+        created_at,
+        updated_at,
+    ))
+}
+
+#[async_trait]
+impl UserRepository for SqliteUserRepository {
+    async fn find_by_id(&self, id: &UserId) -> Result<Option<User>, RepositoryError> {
+        let row = sqlx::query("SELECT id, email, name, password_hash, role, created_at, updated_at FROM users WHERE id = ?")
+            .bind(id.value())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(row_to_user).transpose()
+    }
+
+    async fn find_by_email(&self, email: &Email) -> Result<Option<User>, RepositoryError> {
+        let row = sqlx::query("SELECT id, email, name, password_hash, role, created_at, updated_at FROM users WHERE email = ?")
+            .bind(email.value())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(row_to_user).transpose()
+    }
+
+    async fn create(&self, user: User) -> Result<User, RepositoryError> {
+        // The transaction makes sure the insert is committed before the user is read back
+        let mut tx = self.pool.begin().await?;
+        let id: i64 = sqlx::query(
+            "INSERT INTO users (email, name, password_hash, role, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?)
+             RETURNING id"
+        )
+        .bind(user.email().value())
+        .bind(user.name())
+        .bind(user.password_hash())
+        .bind(user.role().as_str())
+        .bind(format_timestamp(&user.created_at()))
+        .bind(format_timestamp(&user.updated_at()))
+        .fetch_one(&mut *tx)
+        .await?
+        .get("id");
+        tx.commit().await?;
+
+        self.find_by_id(&UserId::new(id)?)
+            .await?
+            .ok_or(RepositoryError::Internal("Failed to retrieve created user".to_string()))
+    }
+
+    async fn update(&self, user: User) -> Result<User, RepositoryError> {
+        let result = sqlx::query("UPDATE users SET name = ?, role = ?, updated_at = ? WHERE id = ?")
+            .bind(user.name())
+            .bind(user.role().as_str())
+            .bind(format_timestamp(&user.updated_at()))
+            .bind(user.id().value())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+
+        self.find_by_id(user.id())
+            .await?
+            .ok_or(RepositoryError::Internal("Failed to retrieve updated user".to_string()))
+    }
+
+    async fn next_id(&self) -> Result<UserId, RepositoryError> {
+        // Placeholder; the real ID is assigned by auto-increment on insert
+        Ok(UserId::new(1)?)
+    }
+}
+
+#[async_trait]
+impl SessionRepository for SqliteUserRepository {
+    async fn create_session(
+        &self,
+        token_hash: &str,
+        user_id: &UserId,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query("INSERT INTO sessions (token_hash, user_id, created_at, expires_at) VALUES (?, ?, ?, ?)")
+            .bind(token_hash)
+            .bind(user_id.value())
+            .bind(format_timestamp(&Utc::now()))
+            .bind(format_timestamp(&expires_at))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn find_session_user(&self, token_hash: &str, now: DateTime<Utc>) -> Result<Option<User>, RepositoryError> {
+        let row = sqlx::query(
+            "SELECT u.id, u.email, u.name, u.password_hash, u.role, u.created_at, u.updated_at
+             FROM sessions s
+             JOIN users u ON u.id = s.user_id
+             WHERE s.token_hash = ? AND julianday(s.expires_at) > julianday(?)"
+        )
+        .bind(token_hash)
+        .bind(format_timestamp(&now))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(row_to_user).transpose()
+    }
+
+    async fn delete_session(&self, token_hash: &str) -> Result<(), RepositoryError> {
+        sqlx::query("DELETE FROM sessions WHERE token_hash = ?")
+            .bind(token_hash)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_expired_sessions(&self, now: DateTime<Utc>) -> Result<u64, RepositoryError> {
+        let result = sqlx::query("DELETE FROM sessions WHERE julianday(expires_at) <= julianday(?)")
+            .bind(format_timestamp(&now))
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
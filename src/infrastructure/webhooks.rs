@@ -0,0 +1,739 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{redirect, Url};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use tokio::sync::Semaphore;
+use tokio::task::{JoinHandle, JoinSet};
+
+use crate::application::{ApplicationError, EventHandler};
+use crate::domain::{
+    ProductEvent, RepositoryError, WebhookDelivery, WebhookEventType, WebhookId,
+    WebhookRepository, WebhookSubscription,
+};
+use crate::infrastructure::outbox::{queue_timestamp, ProductEventPayload, RelayConfig, RelayStats};
+use crate::infrastructure::repositories::{format_timestamp, parse_timestamp};
+
+/// How long a receiver gets to answer a delivery
+pub const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// How deliveries reach their receivers
+#[derive(Debug, Clone, Copy)]
+pub struct WebhookConfig {
+    /// Deliveries in flight at once
+    pub concurrency: usize,
+    /// Allow receivers on loopback, link-local and private addresses; for tests and local setups
+    pub allow_private_targets: bool,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self { concurrency: 8, allow_private_targets: false }
+    }
+}
+
+/// Body POSTed to webhook receivers
+#[derive(Debug, Serialize)]
+struct WebhookPayload {
+    #[serde(flatten)]
+    event: ProductEventPayload,
+    occurred_at: DateTime<Utc>,
+}
+
+/// Signature sent in `X-Webhook-Signature`: hex HMAC-SHA256 over `"{timestamp}.{body}"`
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// ============================================================================
+// Subscriptions
+// ============================================================================
+
+pub struct SqliteWebhookRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteWebhookRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Queue one delivery of `event` per active subscription whose filter matches it
+    pub async fn enqueue_deliveries(&self, event: &ProductEvent) -> Result<usize, RepositoryError> {
+        let targets: Vec<WebhookSubscription> = self
+            .find_all()
+            .await?
+            .into_iter()
+            .filter(|webhook| webhook.matches(event))
+            .collect();
+        if targets.is_empty() {
+            return Ok(0);
+        }
+
+        let payload = serde_json::to_string(&WebhookPayload {
+            event: ProductEventPayload::from(event),
+            occurred_at: Utc::now(),
+        })
+        .map_err(|e| RepositoryError::Internal(format!("Failed to serialize event: {}", e)))?;
+        let now = queue_timestamp(Utc::now());
+
+        let mut tx = self.pool.begin().await?;
+        for webhook in &targets {
+            sqlx::query(
+                "INSERT INTO webhook_deliveries (webhook_id, event_type, payload, status, attempts, next_attempt_at, created_at)
+                 VALUES (?, ?, ?, 'pending', 0, ?, ?)"
+            )
+            .bind(webhook.id().value())
+            .bind(event.event_type())
+            .bind(&payload)
+            .bind(&now)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(targets.len())
+    }
+
+    fn row_to_webhook(&self, row: &SqliteRow) -> Result<WebhookSubscription, RepositoryError> {
+        let events = row
+            .get::<String, _>("events")
+            .split(',')
+            .map(WebhookEventType::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let created_at = parse_timestamp(&row.get::<String, _>("created_at"))
+            .map_err(|e| RepositoryError::Internal(format!("Invalid created_at: {}", e)))?;
+        let updated_at = parse_timestamp(&row.get::<String, _>("updated_at"))
+            .map_err(|e| RepositoryError::Internal(format!("Invalid updated_at: {}", e)))?;
+
+        Ok(WebhookSubscription::reconstitute(
+            WebhookId::new(row.get("id"))?,
+            row.get("url"),
+            events,
+            row.get("secret"),
+            row.get("active"),
+            created_at,
+            updated_at,
+        ))
+    }
+}
+
+fn join_events(webhook: &WebhookSubscription) -> String {
+    webhook.events().iter().map(WebhookEventType::as_str).collect::<Vec<_>>().join(",")
+}
+
+#[async_trait]
+impl WebhookRepository for SqliteWebhookRepository {
+    async fn find_all(&self) -> Result<Vec<WebhookSubscription>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT id, url, events, secret, active, created_at, updated_at FROM webhooks ORDER BY id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(|row| self.row_to_webhook(row)).collect()
+    }
+
+    async fn find_by_id(&self, id: &WebhookId) -> Result<Option<WebhookSubscription>, RepositoryError> {
+        let row = sqlx::query(
+            "SELECT id, url, events, secret, active, created_at, updated_at FROM webhooks WHERE id = ?"
+        )
+        .bind(id.value())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| self.row_to_webhook(&row)).transpose()
+    }
+
+    async fn save(&self, webhook: WebhookSubscription) -> Result<WebhookSubscription, RepositoryError> {
+        let result = sqlx::query(
+            "INSERT INTO webhooks (url, events, secret, active, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?)
+             RETURNING id"
+        )
+        .bind(webhook.url())
+        .bind(join_events(&webhook))
+        .bind(webhook.secret())
+        .bind(webhook.active())
+        .bind(format_timestamp(&webhook.created_at()))
+        .bind(format_timestamp(&webhook.updated_at()))
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.find_by_id(&WebhookId::new(result.get("id"))?)
+            .await?
+            .ok_or(RepositoryError::Internal("Failed to retrieve saved webhook".to_string()))
+    }
+
+    async fn update(&self, webhook: WebhookSubscription) -> Result<WebhookSubscription, RepositoryError> {
+        let result = sqlx::query(
+            "UPDATE webhooks SET url = ?, events = ?, secret = ?, active = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(webhook.url())
+        .bind(join_events(&webhook))
+        .bind(webhook.secret())
+        .bind(webhook.active())
+        .bind(format_timestamp(&webhook.updated_at()))
+        .bind(webhook.id().value())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+
+        self.find_by_id(webhook.id())
+            .await?
+            .ok_or(RepositoryError::Internal("Failed to retrieve updated webhook".to_string()))
+    }
+
+    async fn delete(&self, id: &WebhookId) -> Result<bool, RepositoryError> {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = ?")
+            .bind(id.value())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn find_deliveries(&self, id: &WebhookId, limit: usize) -> Result<Vec<WebhookDelivery>, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT id, webhook_id, event_type, status, attempts, response_status, last_error, created_at, delivered_at
+             FROM webhook_deliveries
+             WHERE webhook_id = ?
+             ORDER BY id DESC
+             LIMIT ?"
+        )
+        .bind(id.value())
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let created_at = parse_timestamp(&row.get::<String, _>("created_at"))
+                    .map_err(|e| RepositoryError::Internal(format!("Invalid created_at: {}", e)))?;
+                let delivered_at = row
+                    .get::<Option<String>, _>("delivered_at")
+                    .map(|value| parse_timestamp(&value))
+                    .transpose()
+                    .map_err(|e| RepositoryError::Internal(format!("Invalid delivered_at: {}", e)))?;
+
+                Ok(WebhookDelivery {
+                    id: row.get("id"),
+                    webhook_id: WebhookId::new(row.get("webhook_id"))?,
+                    event_type: row.get("event_type"),
+                    status: row.get("status"),
+                    attempts: row.get("attempts"),
+                    response_status: row.get::<Option<i64>, _>("response_status").map(|code| code as u16),
+                    last_error: row.get("last_error"),
+                    created_at,
+                    delivered_at,
+                })
+            })
+            .collect()
+    }
+
+    async fn next_id(&self) -> Result<WebhookId, RepositoryError> {
+        // Placeholder; the real ID is assigned by auto-increment on insert
+        Ok(WebhookId::new(1)?)
+    }
+}
+
+/// Event subscriber that turns product events into queued webhook deliveries
+pub struct WebhookEventHandler {
+    repository: SqliteWebhookRepository,
+}
+
+impl WebhookEventHandler {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { repository: SqliteWebhookRepository::new(pool) }
+    }
+}
+
+#[async_trait]
+impl EventHandler for WebhookEventHandler {
+    fn name(&self) -> &'static str {
+        "webhooks"
+    }
+
+    async fn handle(&self, event: &ProductEvent) -> Result<(), ApplicationError> {
+        self.repository
+            .enqueue_deliveries(event)
+            .await
+            .map(|_| ())
+            .map_err(ApplicationError::RepositoryError)
+    }
+}
+
+// ============================================================================
+// Delivery
+// ============================================================================
+
+/// Background worker that POSTs queued deliveries to their receivers.
+///
+/// Any 2xx answer marks a delivery as delivered. Other answers and transport errors
+/// are retried with exponential backoff until `max_attempts`, after which the delivery
+/// is marked failed. Every attempt is logged with its response code. Up to
+/// `WebhookConfig::concurrency` deliveries are sent at once, and unless private targets
+/// are allowed, receivers whose host resolves to a loopback, link-local or private
+/// address are refused, as are redirects.
+pub struct WebhookDispatcher {
+    pool: SqlitePool,
+    client: reqwest::Client,
+    config: RelayConfig,
+    webhooks: WebhookConfig,
+}
+
+/// A delivery that is due, with what is needed to send it
+struct DueDelivery {
+    id: i64,
+    attempts: u32,
+    url: String,
+    event_type: String,
+    payload: String,
+    secret: String,
+}
+
+impl WebhookDispatcher {
+    pub fn new(pool: SqlitePool, config: RelayConfig, webhooks: WebhookConfig) -> Self {
+        let mut client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .redirect(redirect::Policy::none());
+        if !webhooks.allow_private_targets {
+            // A proxy would resolve the host itself, out of the resolver's sight
+            client = client.no_proxy().dns_resolver(Arc::new(PublicResolver));
+        }
+        let client = client.build().expect("HTTP client configuration is valid");
+        Self { pool, client, config, webhooks }
+    }
+
+    /// Run the dispatcher until the task is aborted
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match self.dispatch_once().await {
+                    Ok(stats) if stats.delivered + stats.retried + stats.dead_lettered
+                        >= self.config.batch_size as usize => continue,
+                    Ok(_) => {}
+                    Err(err) => tracing::error!("webhook dispatcher failed: {}", err),
+                }
+                tokio::time::sleep(self.config.poll_interval).await;
+            }
+        })
+    }
+
+    /// Attempt every pending delivery that is due, oldest first.
+    /// Deliveries for paused subscriptions wait until the subscription is re-activated.
+    pub async fn dispatch_once(&self) -> Result<RelayStats, RepositoryError> {
+        let rows = sqlx::query(
+            "SELECT d.id, d.event_type, d.payload, d.attempts, w.url, w.secret
+             FROM webhook_deliveries d
+             JOIN webhooks w ON w.id = d.webhook_id
+             WHERE d.status = 'pending' AND d.next_attempt_at <= ? AND w.active = 1
+             ORDER BY d.id
+             LIMIT ?"
+        )
+        .bind(queue_timestamp(Utc::now()))
+        .bind(self.config.batch_size)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let permits = Arc::new(Semaphore::new(self.webhooks.concurrency.max(1)));
+        let mut sending = JoinSet::new();
+        for row in rows {
+            let delivery = DueDelivery {
+                id: row.get("id"),
+                attempts: row.get::<i64, _>("attempts") as u32 + 1,
+                url: row.get("url"),
+                event_type: row.get("event_type"),
+                payload: row.get("payload"),
+                secret: row.get("secret"),
+            };
+            let client = self.client.clone();
+            let permits = permits.clone();
+            let allow_private = self.webhooks.allow_private_targets;
+            sending.spawn(async move {
+                let _permit = permits.acquire_owned().await.expect("delivery semaphore is never closed");
+                let started = Instant::now();
+                let (response_status, error) = send(&client, &delivery, allow_private).await;
+                (delivery, response_status, error, started.elapsed().as_millis() as i64)
+            });
+        }
+
+        let mut stats = RelayStats::default();
+        while let Some(sent) = sending.join_next().await {
+            let (delivery, response_status, error, duration_ms) = sent
+                .map_err(|err| RepositoryError::Internal(format!("Webhook delivery task failed: {}", err)))?;
+            self.record(&delivery, response_status, error, duration_ms, &mut stats).await?;
+        }
+
+        Ok(stats)
+    }
+
+    /// Log one attempt and move its delivery on: delivered, retried later or failed
+    async fn record(
+        &self,
+        delivery: &DueDelivery,
+        response_status: Option<u16>,
+        error: Option<String>,
+        duration_ms: i64,
+        stats: &mut RelayStats,
+    ) -> Result<(), RepositoryError> {
+        let (id, attempts, url) = (delivery.id, delivery.attempts, &delivery.url);
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO webhook_delivery_attempts (delivery_id, attempted_at, response_status, error, duration_ms)
+             VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(id)
+        .bind(queue_timestamp(Utc::now()))
+        .bind(response_status.map(i64::from))
+        .bind(&error)
+        .bind(duration_ms)
+        .execute(&mut *tx)
+        .await?;
+
+        let (status, next_attempt_at, delivered_at) = match &error {
+            None => ("delivered", Utc::now(), Some(queue_timestamp(Utc::now()))),
+            Some(_) if attempts >= self.config.max_attempts => ("failed", Utc::now(), None),
+            Some(_) => ("pending", Utc::now() + self.config.backoff(attempts), None),
+        };
+        sqlx::query(
+            "UPDATE webhook_deliveries
+             SET status = ?, attempts = ?, next_attempt_at = ?, response_status = ?, last_error = ?, delivered_at = ?
+             WHERE id = ?"
+        )
+        .bind(status)
+        .bind(attempts)
+        .bind(queue_timestamp(next_attempt_at))
+        .bind(response_status.map(i64::from))
+        .bind(&error)
+        .bind(delivered_at)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        match (status, &error) {
+            ("delivered", _) => {
+                tracing::info!(delivery_id = id, url = %url, status = ?response_status, "webhook delivered");
+                stats.delivered += 1;
+            }
+            ("failed", Some(err)) => {
+                tracing::error!(delivery_id = id, url = %url, attempts, "webhook delivery failed permanently: {}", err);
+                stats.dead_lettered += 1;
+            }
+            (_, err) => {
+                tracing::warn!(delivery_id = id, url = %url, attempts, "webhook delivery failed, will retry: {:?}", err);
+                stats.retried += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// POST one delivery; returns the response code, if any, and the failure reason
+async fn send(
+    client: &reqwest::Client,
+    delivery: &DueDelivery,
+    allow_private: bool,
+) -> (Option<u16>, Option<String>) {
+    if !allow_private {
+        if let Err(err) = check_literal_host(&delivery.url) {
+            return (None, Some(err));
+        }
+    }
+
+    let timestamp = Utc::now().timestamp();
+    let result = client
+        .post(&delivery.url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &delivery.event_type)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(SIGNATURE_HEADER, sign_payload(&delivery.secret, timestamp, &delivery.payload))
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+        Ok(response) => (
+            Some(response.status().as_u16()),
+            Some(format!("Receiver answered HTTP {}", response.status().as_u16())),
+        ),
+        Err(err) => (None, Some(error_chain(&err))),
+    }
+}
+
+/// Hosts written as an IP address are never resolved, so they are checked here
+fn check_literal_host(url: &str) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|err| format!("Invalid URL: {}", err))?;
+    let host = url.host_str().unwrap_or_default();
+    let Ok(ip) = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() else {
+        return Ok(());
+    };
+    if is_public_address(ip) {
+        Ok(())
+    } else {
+        Err(format!("Refusing to deliver to non-public address {}", ip))
+    }
+}
+
+/// An error with its sources, which carry the resolver's reason for refusing a host
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+/// Whether a receiver at `ip` is out on the internet rather than on this host or network
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_address(IpAddr::V4(mapped)),
+            None => !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()),
+        },
+    }
+}
+
+/// Resolves receiver hosts like the system resolver, keeping only public addresses.
+/// Checking at connection time, rather than when the URL is saved, also covers hosts
+/// whose DNS is changed to point inside after they were subscribed.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_address(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} does not resolve to a public address", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU16, AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use axum::{extract::State, http::{HeaderMap, StatusCode}, routing::post, Router};
+
+    use crate::application::{Actor, CreateWebhookRequest, WebhookService};
+    use crate::domain::{ProductId, StockQuantity};
+    use crate::infrastructure::migrated_pool;
+
+    const SECRET: &str = "test-secret-0123456789";
+    /// The stand-in receivers listen on loopback
+    const LOCAL: WebhookConfig = WebhookConfig { concurrency: 4, allow_private_targets: true };
+
+    /// Stand-in receiver that records requests and answers with a configurable status
+    #[derive(Clone, Default)]
+    struct Receiver {
+        requests: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+        status: Arc<AtomicU16>,
+        delay_ms: Arc<AtomicU64>,
+    }
+
+    async fn receive(State(receiver): State<Receiver>, headers: HeaderMap, body: String) -> StatusCode {
+        tokio::time::sleep(Duration::from_millis(receiver.delay_ms.load(Ordering::SeqCst))).await;
+        receiver.requests.lock().unwrap().push((headers, body));
+        StatusCode::from_u16(receiver.status.load(Ordering::SeqCst)).unwrap()
+    }
+
+    async fn start_receiver(status: u16) -> (Receiver, String) {
+        let receiver = Receiver::default();
+        receiver.status.store(status, Ordering::SeqCst);
+        let app = Router::new().route("/hook", post(receive)).with_state(receiver.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (receiver, url)
+    }
+
+    async fn subscribe(pool: &SqlitePool, url: &str, events: &[&str]) -> i64 {
+        let service = WebhookService::new(Arc::new(SqliteWebhookRepository::new(pool.clone())));
+        service
+            .create_webhook(CreateWebhookRequest {
+                url: url.to_string(),
+                events: events.iter().map(|event| event.to_string()).collect(),
+                secret: Some(SECRET.to_string()),
+            }, &Actor::system("test"))
+            .await
+            .unwrap()
+            .id
+    }
+
+    fn stock_changed() -> ProductEvent {
+        ProductEvent::StockChanged {
+            product_id: ProductId::from(7),
+            old_stock: StockQuantity::new(3).unwrap(),
+            new_stock: StockQuantity::new(1).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_payloads_for_subscribed_events_only() {
+        let pool = migrated_pool().await;
+        let (receiver, url) = start_receiver(204).await;
+        let webhook_id = subscribe(&pool, &url, &["product.stock_changed"]).await;
+
+        let handler = WebhookEventHandler::new(pool.clone());
+        handler.handle(&stock_changed()).await.unwrap();
+        handler.handle(&ProductEvent::ProductDeleted { product_id: ProductId::from(7) }).await.unwrap();
+
+        let stats = WebhookDispatcher::new(pool.clone(), RelayConfig::default(), LOCAL).dispatch_once().await.unwrap();
+        assert_eq!(stats.delivered, 1);
+
+        let requests = receiver.requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 1);
+        let (headers, body) = &requests[0];
+        let timestamp: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        assert_eq!(headers[SIGNATURE_HEADER].to_str().unwrap(), sign_payload(SECRET, timestamp, body));
+        assert_eq!(headers[EVENT_HEADER], "product.stock_changed");
+
+        let json: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(json["type"], "product.stock_changed");
+        assert_eq!(json["product_id"], 7);
+        assert_eq!(json["new_stock"], 1);
+
+        let log = SqliteWebhookRepository::new(pool)
+            .find_deliveries(&WebhookId::new(webhook_id).unwrap(), 10)
+            .await
+            .unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!((log[0].status.as_str(), log[0].response_status), ("delivered", Some(204)));
+    }
+
+    #[tokio::test]
+    async fn failing_receivers_are_retried_then_marked_failed() {
+        let pool = migrated_pool().await;
+        let (receiver, url) = start_receiver(500).await;
+        let webhook_id = subscribe(&pool, &url, &["product.stock_changed"]).await;
+        WebhookEventHandler::new(pool.clone()).handle(&stock_changed()).await.unwrap();
+
+        let config = RelayConfig { max_attempts: 2, base_backoff: Duration::ZERO, ..RelayConfig::default() };
+        let dispatcher = WebhookDispatcher::new(pool.clone(), config, LOCAL);
+        let repository = SqliteWebhookRepository::new(pool.clone());
+        let webhook_id = WebhookId::new(webhook_id).unwrap();
+
+        assert_eq!(dispatcher.dispatch_once().await.unwrap().retried, 1);
+        let log = repository.find_deliveries(&webhook_id, 10).await.unwrap();
+        assert_eq!((log[0].status.as_str(), log[0].attempts, log[0].response_status), ("pending", 1, Some(500)));
+
+        assert_eq!(dispatcher.dispatch_once().await.unwrap().dead_lettered, 1);
+        let log = repository.find_deliveries(&webhook_id, 10).await.unwrap();
+        assert_eq!((log[0].status.as_str(), log[0].attempts), ("failed", 2));
+
+        // Failed deliveries stay failed, and every attempt was logged with its code
+        assert_eq!(dispatcher.dispatch_once().await.unwrap(), RelayStats::default());
+        assert_eq!(receiver.requests.lock().unwrap().len(), 2);
+        let codes: Vec<Option<i64>> = sqlx::query_scalar("SELECT response_status FROM webhook_delivery_attempts")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(codes, vec![Some(500), Some(500)]);
+    }
+
+    #[tokio::test]
+    async fn slow_receivers_are_sent_to_concurrently() {
+        let pool = migrated_pool().await;
+        let (receiver, url) = start_receiver(204).await;
+        receiver.delay_ms.store(300, Ordering::SeqCst);
+        for _ in 0..4 {
+            subscribe(&pool, &url, &["product.stock_changed"]).await;
+        }
+        WebhookEventHandler::new(pool.clone()).handle(&stock_changed()).await.unwrap();
+
+        let started = Instant::now();
+        let stats = WebhookDispatcher::new(pool.clone(), RelayConfig::default(), LOCAL).dispatch_once().await.unwrap();
+        assert_eq!(stats.delivered, 4);
+        // One after another they would take 1.2s
+        assert!(started.elapsed() < Duration::from_millis(900), "{:?}", started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn receivers_on_private_addresses_are_refused() {
+        let pool = migrated_pool().await;
+        let (receiver, url) = start_receiver(204).await;
+        let webhook_id = subscribe(&pool, &url, &["product.stock_changed"]).await;
+        subscribe(&pool, "http://localhost:9/hook", &["product.stock_changed"]).await;
+        WebhookEventHandler::new(pool.clone()).handle(&stock_changed()).await.unwrap();
+
+        let dispatcher = WebhookDispatcher::new(pool.clone(), RelayConfig::default(), WebhookConfig::default());
+        assert_eq!(dispatcher.dispatch_once().await.unwrap().retried, 2);
+        assert!(receiver.requests.lock().unwrap().is_empty());
+
+        let log = SqliteWebhookRepository::new(pool.clone())
+            .find_deliveries(&WebhookId::new(webhook_id).unwrap(), 10)
+            .await
+            .unwrap();
+        assert!(log[0].last_error.as_deref().unwrap().contains("non-public address 127.0.0.1"));
+        let errors: Vec<String> = sqlx::query_scalar("SELECT error FROM webhook_delivery_attempts ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert!(errors.iter().any(|error| error.contains("localhost does not resolve to a public address")), "{:?}", errors);
+    }
+
+    #[test]
+    fn only_public_addresses_are_delivered_to() {
+        for private in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public_address(private.parse().unwrap()), "{}", private);
+        }
+        for public in ["93.184.216.34", "2606:2800:220:1::1"] {
+            assert!(is_public_address(public.parse().unwrap()), "{}", public);
+        }
+        assert!(check_literal_host("http://2130706433/hook").is_err());
+        assert!(check_literal_host("http://[::1]:8080/hook").is_err());
+        assert!(check_literal_host("https://example.com/hook").is_ok());
+    }
+}
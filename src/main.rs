@@ -1,50 +1,206 @@
+mod config;
 mod domain;
 mod application;
 mod infrastructure;
 mod presentation;
 
 use std::sync::Arc;
+use std::time::Duration;
 use anyhow::Result;
 
-use infrastructure::{create_connection_pool, SqliteProductRepository};
-use application::ProductService;
+use infrastructure::{
+    create_connection_pool, SqliteProductRepository, Migrator, MigrationState,
+    LoggingEventHandler, OutboxRelay, RelayConfig,
+    SqliteWebhookRepository, WebhookEventHandler, WebhookDispatcher, SqliteCartRepository,
+    SqliteOrderRepository, SqliteUserRepository, Argon2PasswordHasher, SqliteApiKeyRepository,
+    SqliteCategoryRepository,
+};
+use application::{AccountService, Actor, ApiKeyService, CartService, CategoryService, EventBus, InventoryService, OrderService, ProductService, SuggestionService, WebhookService};
+use config::{Config, ConfigArgs};
 use presentation::{create_router, AppState};
 
+/// What the process should do, selected by command-line flags
+enum StartupMode {
+    /// Apply pending migrations, then serve HTTP (default)
+    Serve,
+    /// `--migrate-only`: apply pending migrations and exit
+    MigrateOnly,
+    /// `--migration-status`: print the state of every migration and exit
+    MigrationStatus,
+    /// `--rollback [N]`: roll back the N most recent migrations (default 1) and exit
+    Rollback(usize),
+    /// `--grant-role <email> <role>`: give an account a role (e.g. the first admin) and exit
+    GrantRole { email: String, role: String },
+    /// `--print-config`: print the effective configuration and exit
+    PrintConfig,
+}
+
+impl StartupMode {
+    /// The mode from the arguments left once the configuration flags are taken out
+    fn from_args(args: &[String]) -> Result<Self> {
+        match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+            [] => Ok(Self::Serve),
+            ["--migrate-only"] => Ok(Self::MigrateOnly),
+            ["--migration-status"] => Ok(Self::MigrationStatus),
+            ["--print-config"] => Ok(Self::PrintConfig),
+            ["--rollback"] => Ok(Self::Rollback(1)),
+            ["--rollback", steps] => Ok(Self::Rollback(steps.parse()?)),
+            ["--grant-role", email, role] => Ok(Self::GrantRole {
+                email: email.to_string(),
+                role: role.to_string(),
+            }),
+            _ => anyhow::bail!(
+                "Unknown arguments {:?}. Usage: ecommerce-crud [--migrate-only | --migration-status | --rollback [N] | --grant-role <email> <role> | --print-config] [--config <file>] [--env <env>] [--database-url <url>] [--listen <addr>] [--static-dir <dir>] [--log-level <level>] [--set <key>=<value>]...",
+                args
+            ),
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let (config_args, args) = ConfigArgs::extract(std::env::args().skip(1).collect())?;
+    let mode = StartupMode::from_args(&args)?;
+    let config = Config::load(&config_args)?;
+    if let StartupMode::PrintConfig = mode {
+        print!("{}", config);
+        return Ok(());
+    }
+
     // Initialize logging
-    tracing_subscriber::fmt::init();
+    tracing_subscriber::fmt().with_max_level(config.log_level).init();
     
     println!("🚀 Starting E-commerce Application with Clean Architecture...");
+    println!("⚙️  Configuration:\n{}", config);
     
     // Infrastructure Layer - Database setup
-    let pool = create_connection_pool().await?;
+    let pool = create_connection_pool(&config.database_url, &config.pool).await?;
     println!("✅ Database connection established");
+
+    // Infrastructure Layer - Schema migrations
+    let migrator = Migrator::from_dir(&config.migrations_dir)?;
+    match mode {
+        StartupMode::MigrationStatus => {
+            for status in migrator.status(&pool).await? {
+                let state = match status.state {
+                    MigrationState::Applied { applied_at } => format!("applied {}", applied_at),
+                    MigrationState::Pending => "pending".to_string(),
+                    MigrationState::Modified => "MODIFIED after being applied".to_string(),
+                    MigrationState::Missing => "MISSING from migrations/".to_string(),
+                };
+                println!("  {:03} {:<30} {}", status.version, status.name, state);
+            }
+            return Ok(());
+        }
+        StartupMode::Rollback(steps) => {
+            let rolled_back = migrator.rollback(&pool, steps).await?;
+            println!("✅ Rolled back migrations: {:?}", rolled_back);
+            return Ok(());
+        }
+        StartupMode::MigrateOnly | StartupMode::GrantRole { .. } | StartupMode::Serve | StartupMode::PrintConfig => {
+            let applied = migrator.run(&pool).await?;
+            println!("✅ Migrations up to date ({} applied)", applied.len());
+        }
+    }
+    if let StartupMode::MigrateOnly = mode {
+        return Ok(());
+    }
+    if let StartupMode::GrantRole { email, role } = &mode {
+        let users = Arc::new(SqliteUserRepository::new(pool.clone()));
+        let accounts = AccountService::new(users.clone(), users, Arc::new(Argon2PasswordHasher::new()));
+        let user = accounts.grant_role(email, role, &Actor::system("cli")).await?;
+        println!("✅ {} is now {}", user.email, user.role);
+        return Ok(());
+    }
     
     // Infrastructure Layer - Repository implementation
-    let repository = SqliteProductRepository::new(pool);
-    repository.initialize().await?;
-    println!("✅ Database initialized with seed data");
+    let repository = Arc::new(SqliteProductRepository::new(pool.clone()));
+    if config.features.seed_data {
+        repository.initialize().await?;
+        println!("✅ Database initialized with seed data");
+    }
+    if repository.enable_full_text_search().await? {
+        println!("✅ Full-text search index ready");
+    } else {
+        println!("⚠️  SQLite was built without FTS5; product search falls back to substring matching");
+    }
     
+    // Application Layer - Domain event subscribers, fed from the outbox
+    let event_bus = Arc::new(EventBus::new());
+    event_bus.subscribe(Arc::new(LoggingEventHandler));
+    let suggestion_service = Arc::new(SuggestionService::new(repository.clone()));
+    suggestion_service.refresh().await?;
+    event_bus.subscribe(suggestion_service.clone());
+    if config.features.webhooks {
+        event_bus.subscribe(Arc::new(WebhookEventHandler::new(pool.clone())));
+        WebhookDispatcher::new(pool.clone(), RelayConfig::default(), config.webhooks).spawn();
+        println!("✅ Webhook dispatcher started");
+    }
+    OutboxRelay::new(pool.clone(), event_bus, RelayConfig::default()).spawn();
+    println!("✅ Outbox relay started");
+
     // Application Layer - Service with dependency injection
-    let product_service = Arc::new(ProductService::new(Arc::new(repository)));
+    let product_service = Arc::new(ProductService::new(repository.clone()));
+    let inventory_service = Arc::new(InventoryService::new(repository.clone(), repository.clone()));
+    if config.features.reservation_expiry {
+        inventory_service.spawn_expiry_sweeper(Duration::from_secs(30));
+    }
+    let discrepancies = inventory_service.reconcile_stock(&Actor::system("startup")).await?;
+    if discrepancies.is_empty() {
+        println!("✅ Stock ledger reconciled");
+    } else {
+        for discrepancy in &discrepancies {
+            tracing::warn!(
+                product_id = discrepancy.product_id,
+                stock = discrepancy.stock,
+                ledger_total = discrepancy.ledger_total,
+                "stock does not match the inventory ledger"
+            );
+        }
+        println!("⚠️  {} product(s) disagree with the stock ledger", discrepancies.len());
+    }
+    let carts = Arc::new(SqliteCartRepository::new(pool.clone()));
+    let cart_service = Arc::new(CartService::new(carts.clone(), repository.clone()));
+    let category_service = Arc::new(CategoryService::new(
+        Arc::new(SqliteCategoryRepository::new(pool.clone())),
+        repository.clone(),
+    ));
+    let order_service = Arc::new(OrderService::new(
+        Arc::new(SqliteOrderRepository::new(pool.clone())),
+        carts,
+        repository,
+    ));
+    let users = Arc::new(SqliteUserRepository::new(pool.clone()));
+    let account_service = Arc::new(AccountService::new(users.clone(), users, Arc::new(Argon2PasswordHasher::new())));
+    let api_key_service = Arc::new(ApiKeyService::new(Arc::new(SqliteApiKeyRepository::new(pool.clone()))));
+    let webhook_service = Arc::new(WebhookService::new(Arc::new(SqliteWebhookRepository::new(pool))));
     println!("✅ Application services configured");
     
     // Presentation Layer - Web framework setup
     let app_state = AppState {
         product_service,
+        webhook_service,
+        inventory_service,
+        cart_service,
+        order_service,
+        account_service,
+        api_key_service,
+        suggestion_service,
+        category_service,
+        environment: config.env,
     };
     
-    let app = create_router(app_state);
+    let app = create_router(app_state, &config.static_dir);
     println!("✅ Web routes configured");
     
     // Start server
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
-    println!("🌐 Server running on http://localhost:3000");
+    let listener = tokio::net::TcpListener::bind(config.listen).await?;
+    let address = listener.local_addr()?;
+    println!("🌐 Server running on http://{}", address);
     println!("📚 API Documentation:");
-    println!("  - Home Page: http://localhost:3000");
-    println!("  - REST API: http://localhost:3000/api/products");
-    println!("  - Health Check: http://localhost:3000/health");
+    println!("  - Home Page: http://{}", address);
+    println!("  - REST API: http://{}/api/products", address);
+    println!("  - Health Check: http://{}/health", address);
     
     // Serve the application
     axum::serve(listener, app).await?;
@@ -0,0 +1,514 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode, Uri},
+    middleware::Next,
+    response::{Html, IntoResponse, Json, Response},
+};
+use serde::Serialize;
+
+use crate::application::{describe, ApiResponse, ApplicationError, FieldError};
+use crate::domain::{DomainError, RepositoryError};
+use crate::presentation::handlers::Environment;
+use crate::presentation::middleware::RequestId;
+use crate::presentation::templates::error_page;
+
+// ============================================================================
+// REST API Errors
+// ============================================================================
+
+/// Media type of RFC 7807 error bodies
+pub const PROBLEM_JSON: &str = "application/problem+json";
+/// Request header with which older clients ask for errors in the `ApiResponse` envelope
+pub const ERROR_FORMAT_HEADER: &str = "x-error-format";
+/// Largest error body from outside the handlers that is kept as the problem's `detail`
+const MAX_DETAIL_BYTES: usize = 4 * 1024;
+
+/// An RFC 7807 problem details body.
+///
+/// `type` is always `about:blank`, so `title` is the status's reason phrase; `errors` lists the
+/// invalid fields of a 422.
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: &'static str,
+    pub title: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+impl Problem {
+    fn new(status: StatusCode, detail: Option<String>) -> Self {
+        Self {
+            problem_type: "about:blank",
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail,
+            instance: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// The same failure in the `ApiResponse` envelope
+    fn envelope(&self) -> ApiResponse<()> {
+        if self.errors.is_empty() {
+            ApiResponse::error(self.detail.clone().unwrap_or_else(|| self.title.clone()))
+        } else {
+            ApiResponse::invalid_fields(self.errors.clone())
+        }
+    }
+}
+
+/// A failed REST API request.
+///
+/// Built from the application's errors, which decide the status: missing resources are 404,
+/// malformed requests 400, invalid values 422, clashes with the current state 409. Anything
+/// unexpected is logged and answered with a 500 that does not repeat the internal message.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    detail: Option<String>,
+    errors: Vec<FieldError>,
+    headers: HeaderMap,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, detail: impl Into<String>) -> Self {
+        Self { status, detail: Some(detail.into()), errors: Vec::new(), headers: HeaderMap::new() }
+    }
+
+    fn internal(err: impl std::fmt::Display) -> Self {
+        tracing::error!(error = %err, "API request failed");
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "The server could not complete the request")
+    }
+
+    fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    fn problem(&self) -> Problem {
+        Problem { errors: self.errors.clone(), ..Problem::new(self.status, self.detail.clone()) }
+    }
+}
+
+impl From<ApplicationError> for ApiError {
+    fn from(err: ApplicationError) -> Self {
+        match err {
+            ApplicationError::ProductNotFound
+            | ApplicationError::WebhookNotFound
+            | ApplicationError::CategoryNotFound
+            | ApplicationError::ReservationNotFound
+            | ApplicationError::CartItemNotFound
+            | ApplicationError::OrderNotFound
+            | ApplicationError::UserNotFound
+            | ApplicationError::ApiKeyNotFound => Self::new(StatusCode::NOT_FOUND, err.to_string()),
+            ApplicationError::InvalidCredentials => Self::new(StatusCode::UNAUTHORIZED, err.to_string()),
+            ApplicationError::InvalidApiKey => Self::new(StatusCode::UNAUTHORIZED, err.to_string())
+                .with_header(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer")),
+            ApplicationError::RateLimited { retry_after_secs } => {
+                Self::new(StatusCode::TOO_MANY_REQUESTS, err.to_string())
+                    .with_header(header::RETRY_AFTER, HeaderValue::from(retry_after_secs))
+            }
+            ApplicationError::AuthorizationError(message) => Self::new(StatusCode::FORBIDDEN, message),
+            ApplicationError::ValidationError(message) => Self::new(StatusCode::BAD_REQUEST, message),
+            ApplicationError::InvalidFields(errors) => Self {
+                errors,
+                ..Self::new(StatusCode::UNPROCESSABLE_ENTITY, "One or more fields are invalid")
+            },
+            ApplicationError::DomainError(err) => err.into(),
+            ApplicationError::RepositoryError(err) => err.into(),
+            err @ ApplicationError::InternalError(_) => Self::internal(err),
+        }
+    }
+}
+
+impl From<DomainError> for ApiError {
+    fn from(err: DomainError) -> Self {
+        match err {
+            DomainError::ProductNotFound => Self::new(StatusCode::NOT_FOUND, err.to_string()),
+            // Well-formed requests the current stock, reservation, order, cart or category tree cannot take
+            DomainError::InsufficientStock
+            | DomainError::InvalidReservation(_)
+            | DomainError::InvalidOrder(_)
+            | DomainError::CurrencyMismatch(..)
+            | DomainError::CategoryNotEmpty { .. } => Self::new(StatusCode::CONFLICT, err.to_string()),
+            err => Self::new(StatusCode::UNPROCESSABLE_ENTITY, err.to_string()),
+        }
+    }
+}
+
+impl From<RepositoryError> for ApiError {
+    fn from(err: RepositoryError) -> Self {
+        match err {
+            RepositoryError::NotFound => Self::new(StatusCode::NOT_FOUND, "Not found"),
+            RepositoryError::ConcurrentModification => Self::new(
+                StatusCode::CONFLICT,
+                "The resource was changed by another request; fetch it again and retry",
+            ),
+            RepositoryError::ConstraintViolation(_) => {
+                tracing::warn!(error = %err, "API request violated a constraint");
+                Self::new(StatusCode::CONFLICT, "The request conflicts with existing data")
+            }
+            RepositoryError::DomainError(err) => err.into(),
+            err => Self::internal(err),
+        }
+    }
+}
+
+/// Failures decided by status alone, such as a missing precondition or session
+impl From<StatusCode> for ApiError {
+    fn from(status: StatusCode) -> Self {
+        Self { status, detail: None, errors: Vec::new(), headers: HeaderMap::new() }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let problem = self.problem();
+        let mut response = (
+            self.status,
+            [(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON))],
+            Json(&problem),
+        )
+            .into_response();
+        response.headers_mut().extend(self.headers);
+        // Left for `api_errors`, which may render it in another format
+        response.extensions_mut().insert(problem);
+        response
+    }
+}
+
+/// Gives every failed `/api` response a problem details body.
+///
+/// Errors from the handlers arrive as [`ApiError`]s. Rejections raised before a handler runs
+/// (an unparsable path or body, a missing session) are converted here, keeping their text as the
+/// `detail` unless it is a server error. Clients sending `X-Error-Format: envelope` get the
+/// `ApiResponse` envelope instead, with the same status code and headers.
+pub async fn api_errors(request: Request, next: Next) -> Response {
+    let envelope = wants_envelope(request.headers());
+    let instance = request.uri().path().to_string();
+    let response = next.run(request).await;
+    if !response.status().is_client_error() && !response.status().is_server_error() {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let mut problem = match parts.extensions.remove::<Problem>() {
+        Some(problem) => problem,
+        None if parts.status.is_server_error() => Problem::new(parts.status, None),
+        None => {
+            let text = to_bytes(body, MAX_DETAIL_BYTES).await.unwrap_or_default();
+            let detail = String::from_utf8_lossy(&text).trim().to_string();
+            Problem::new(parts.status, Some(detail).filter(|detail| !detail.is_empty()))
+        }
+    };
+    problem.instance = Some(instance);
+
+    let (content_type, body) = if envelope {
+        ("application/json", serde_json::to_vec(&problem.envelope()))
+    } else {
+        (PROBLEM_JSON, serde_json::to_vec(&problem))
+    };
+    parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body.expect("error bodies serialize")))
+}
+
+fn wants_envelope(headers: &HeaderMap) -> bool {
+    headers
+        .get(ERROR_FORMAT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("envelope"))
+}
+
+// ============================================================================
+// Browser Error Pages
+// ============================================================================
+
+/// A browser page that could not be shown. The body is left to `error_pages`, which renders
+/// the error template around it.
+#[derive(Debug, Clone)]
+pub struct PageError {
+    status: StatusCode,
+    /// Safe to show to anyone
+    message: Option<String>,
+    /// What actually failed; kept off the page in production
+    internal: Option<String>,
+}
+
+impl From<ApplicationError> for PageError {
+    fn from(err: ApplicationError) -> Self {
+        let status = match &err {
+            ApplicationError::ProductNotFound
+            | ApplicationError::OrderNotFound
+            | ApplicationError::ReservationNotFound
+            | ApplicationError::WebhookNotFound
+            | ApplicationError::CategoryNotFound
+            | ApplicationError::UserNotFound => StatusCode::NOT_FOUND,
+            ApplicationError::AuthorizationError(_) => StatusCode::FORBIDDEN,
+            ApplicationError::ValidationError(_)
+            | ApplicationError::InvalidFields(_)
+            | ApplicationError::DomainError(_) => StatusCode::BAD_REQUEST,
+            _ => {
+                tracing::error!(error = %err, "Page request failed");
+                return Self { internal: Some(err.to_string()), ..StatusCode::INTERNAL_SERVER_ERROR.into() };
+            }
+        };
+        let message = match err {
+            ApplicationError::AuthorizationError(message) | ApplicationError::ValidationError(message) => message,
+            ApplicationError::InvalidFields(errors) => describe(&errors),
+            err => err.to_string(),
+        };
+        Self { status, message: Some(message), internal: None }
+    }
+}
+
+impl From<StatusCode> for PageError {
+    fn from(status: StatusCode) -> Self {
+        Self { status, message: None, internal: None }
+    }
+}
+
+impl IntoResponse for PageError {
+    fn into_response(self) -> Response {
+        let mut response = self.status.into_response();
+        response.extensions_mut().insert(self);
+        response
+    }
+}
+
+/// Renders the error page for failed browser requests.
+///
+/// `PageError`s carry their own message; bare failures such as an unknown route, a wrong method
+/// or an unparsable path get one for their status. Pages that already explain the problem (the
+/// sign-in form after a wrong password), HTMX fragments and `/api` responses are left alone.
+pub async fn error_pages(State(environment): State<Environment>, request: Request, next: Next) -> Response {
+    let skip = is_api_path(request.uri()) || request.headers().contains_key("hx-request");
+    let request_id = request.extensions().get::<RequestId>().cloned().unwrap_or_default();
+    let response = next.run(request).await;
+    let status = response.status();
+    let explained = response.extensions().get::<PageError>().is_none() && is_html(response.headers());
+    if skip || explained || !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+
+    let (mut parts, _) = response.into_parts();
+    let error = parts.extensions.remove::<PageError>().unwrap_or_else(|| status.into());
+    let message = match (environment, error.internal) {
+        (Environment::Development, Some(internal)) => internal,
+        _ => error.message.unwrap_or_else(|| public_message(status).to_string()),
+    };
+
+    parts.headers.remove(header::CONTENT_TYPE);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let page = Html(error_page(status.as_u16(), &message, &request_id.0));
+    (parts, page).into_response()
+}
+
+/// Unknown routes: problem details under `/api`, the error page everywhere else
+pub async fn not_found(uri: Uri) -> Response {
+    if is_api_path(&uri) {
+        ApiError::new(StatusCode::NOT_FOUND, "There is no API endpoint at this path").into_response()
+    } else {
+        PageError::from(StatusCode::NOT_FOUND).into_response()
+    }
+}
+
+fn is_api_path(uri: &Uri) -> bool {
+    uri.path() == "/api" || uri.path().starts_with("/api/")
+}
+
+fn is_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"))
+}
+
+fn public_message(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "The request could not be understood.",
+        StatusCode::UNAUTHORIZED => "Please sign in to see this page.",
+        StatusCode::FORBIDDEN => "You do not have permission to see this page.",
+        StatusCode::NOT_FOUND => "The page you are looking for does not exist or has been moved.",
+        status if status.is_server_error() => "Something went wrong on our side. Please try again later.",
+        status => status.canonical_reason().unwrap_or("The request could not be completed."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::get, Router};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use crate::application::FieldErrors;
+
+    async fn call(router: Router, envelope: bool) -> (StatusCode, HeaderMap, Value) {
+        let mut request = Request::builder().uri("/api/things/7");
+        if envelope {
+            request = request.header(ERROR_FORMAT_HEADER, "envelope");
+        }
+        let response = router
+            .layer(middleware::from_fn(api_errors))
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let (parts, body) = response.into_parts();
+        let body = to_bytes(body, usize::MAX).await.unwrap();
+        (parts.status, parts.headers, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn failing(err: fn() -> ApplicationError) -> Router {
+        Router::new().route("/api/things/:id", get(move || async move { Err::<(), ApiError>(err().into()) }))
+    }
+
+    #[test]
+    fn application_errors_map_to_their_status() {
+        let status = |err: ApplicationError| ApiError::from(err).into_response().status();
+
+        assert_eq!(status(ApplicationError::OrderNotFound), StatusCode::NOT_FOUND);
+        assert_eq!(status(ApplicationError::validation("Page numbers start at 1")), StatusCode::BAD_REQUEST);
+        assert_eq!(status(FieldErrors::only("stock", "negative", "no")), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            status(DomainError::EmptyProductName.into()),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(status(DomainError::InsufficientStock.into()), StatusCode::CONFLICT);
+        assert_eq!(
+            status(DomainError::CategoryNotEmpty { products: 2, subcategories: 0 }.into()),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(status(ApplicationError::CategoryNotFound), StatusCode::NOT_FOUND);
+        assert_eq!(status(RepositoryError::ConcurrentModification.into()), StatusCode::CONFLICT);
+        assert_eq!(status(ApplicationError::authorization("Editors only")), StatusCode::FORBIDDEN);
+        assert_eq!(status(ApplicationError::InvalidApiKey), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status(RepositoryError::QueryFailed("no such table".to_string()).into()),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[tokio::test]
+    async fn errors_are_problem_documents_unless_the_envelope_is_asked_for() {
+        let invalid = || FieldErrors::only("stock", "negative", "Stock cannot be negative");
+
+        let (status, headers, body) = call(failing(invalid), false).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(headers[header::CONTENT_TYPE], PROBLEM_JSON);
+        assert_eq!(body["type"], "about:blank");
+        assert_eq!(body["title"], "Unprocessable Entity");
+        assert_eq!(body["status"], 422);
+        assert_eq!(body["instance"], "/api/things/7");
+        assert_eq!(body["errors"][0]["code"], "negative");
+
+        let (status, headers, body) = call(failing(invalid), true).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+        assert_eq!(body["success"], false);
+        assert_eq!(body["errors"][0], "stock: Stock cannot be negative");
+        assert_eq!(body["fields"][0]["field"], "stock");
+
+        let (status, headers, body) = call(failing(|| ApplicationError::RateLimited { retry_after_secs: 12 }), true).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(headers[header::RETRY_AFTER], "12");
+        assert_eq!(body["message"], "Rate limit exceeded; retry in 12 seconds");
+    }
+
+    #[tokio::test]
+    async fn internal_details_and_bare_rejections_are_handled() {
+        let (status, _, body) = call(failing(|| RepositoryError::QueryFailed("secret.db is locked".to_string()).into()), false).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(!body.to_string().contains("secret.db"));
+
+        // Path rejections are plain text from axum; they become problems with that text as detail
+        let typed = Router::new().route("/api/things/:id", get(|axum::extract::Path(_): axum::extract::Path<bool>| async {}));
+        let (status, headers, body) = call(typed, false).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(headers[header::CONTENT_TYPE], PROBLEM_JSON);
+        assert!(body["detail"].as_str().unwrap().contains("parse"));
+
+        let bare = Router::new().route("/api/things/:id", get(|| async { StatusCode::UNAUTHORIZED }));
+        let (status, _, body) = call(bare, false).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["title"], "Unauthorized");
+        assert!(body.get("detail").is_none());
+    }
+
+    /// The site's error handling around a few page routes, as `create_router` sets it up
+    fn pages(environment: Environment) -> Router {
+        Router::new()
+            .route("/products/:id", get(|| async { Err::<(), PageError>(ApplicationError::ProductNotFound.into()) }))
+            .route(
+                "/cart",
+                get(|| async {
+                    let err = RepositoryError::QueryFailed("<script>alert(\"x\")</script> in carts.db".to_string());
+                    Err::<(), PageError>(ApplicationError::RepositoryError(err).into())
+                }),
+            )
+            .route("/login", get(|| async { (StatusCode::UNAUTHORIZED, Html("<form>Try again</form>")) }))
+            .fallback(not_found)
+            .layer(middleware::from_fn_with_state(environment, error_pages))
+            .layer(middleware::from_fn(crate::presentation::middleware::request_id))
+    }
+
+    async fn visit(router: Router, uri: &str, headers: &[(&str, &str)]) -> (StatusCode, HeaderMap, String) {
+        let mut request = Request::builder().uri(uri);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let response = router.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let body = to_bytes(body, usize::MAX).await.unwrap();
+        (parts.status, parts.headers, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn failed_pages_render_the_error_template_with_the_request_id() {
+        let (status, headers, html) = visit(pages(Environment::Production), "/products/9", &[]).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(headers[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/html"));
+        assert!(html.contains("Page not found") && html.contains("Product not found"));
+        let request_id = headers["x-request-id"].to_str().unwrap();
+        assert!(html.contains(request_id));
+
+        let (status, _, html) = visit(pages(Environment::Production), "/no/such/page", &[("x-request-id", "edge-42")]).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(html.contains("does not exist") && html.contains("edge-42"));
+
+        let (status, headers, body) = visit(pages(Environment::Production), "/api/no/such/endpoint", &[]).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(headers[header::CONTENT_TYPE], PROBLEM_JSON);
+        assert!(!body.contains("<html"));
+    }
+
+    #[tokio::test]
+    async fn internal_errors_are_only_shown_outside_production() {
+        let (status, _, html) = visit(pages(Environment::Production), "/cart", &[]).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(html.contains("Something went wrong on our side"));
+        assert!(!html.contains("carts.db"));
+
+        let (_, _, html) = visit(pages(Environment::Development), "/cart", &[]).await;
+        assert!(html.contains("carts.db"));
+        assert!(html.contains("&lt;script&gt;") && !html.contains("<script>alert"));
+    }
+
+    #[tokio::test]
+    async fn pages_that_explain_themselves_and_htmx_fragments_are_left_alone() {
+        let (status, _, html) = visit(pages(Environment::Production), "/login", &[]).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(html, "<form>Try again</form>");
+
+        let (status, _, body) = visit(pages(Environment::Production), "/products/9", &[("hx-request", "true")]).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.is_empty());
+    }
+}
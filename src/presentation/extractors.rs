@@ -0,0 +1,96 @@
+use axum::{
+    async_trait,
+    extract::{rejection::QueryRejection, FromRequestParts, Query},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+};
+
+use crate::application::{Actor, SearchProductsQuery, UserResponse};
+use crate::presentation::handlers::{AppState, API_ACTOR};
+use crate::presentation::middleware::AuthenticatedKey;
+
+/// Cookie holding the sign-in session token
+pub const SESSION_COOKIE: &str = "session_id";
+
+/// The value of cookie `name` sent with the request
+pub fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(cookie, _)| *cookie == name)
+        .map(|(_, value)| value.to_string())
+}
+
+/// The signed-in customer. Requests without a valid session are rejected with 401.
+pub struct CurrentUser(pub UserResponse);
+
+/// The signed-in customer, if any
+pub struct MaybeUser(pub Option<UserResponse>);
+
+impl MaybeUser {
+    /// The actor for the policy checks; visitors who are not signed in are recorded as `anonymous`
+    pub fn actor(&self, anonymous: &str) -> Actor {
+        match &self.0 {
+            Some(user) => Actor::user(user),
+            None => Actor::anonymous(anonymous),
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for MaybeUser {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let Some(token) = cookie_value(&parts.headers, SESSION_COOKIE) else {
+            return Ok(MaybeUser(None));
+        };
+
+        let user = state.account_service.current_user(&token).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        Ok(MaybeUser(user))
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for CurrentUser {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let MaybeUser(user) = MaybeUser::from_request_parts(parts, state).await?;
+        user.map(CurrentUser).ok_or(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// Who calls a REST endpoint: the API key accepted by the middleware, else the signed-in
+/// customer, else an anonymous API client
+pub struct ApiActor(pub Actor);
+
+#[async_trait]
+impl FromRequestParts<AppState> for ApiActor {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if let Some(AuthenticatedKey(key)) = parts.extensions.get::<AuthenticatedKey>() {
+            return Ok(ApiActor(Actor::api_key(key)));
+        }
+        let user = MaybeUser::from_request_parts(parts, state).await?;
+        Ok(ApiActor(user.actor(API_ACTOR)))
+    }
+}
+
+/// The query string of a product listing, including the facet filters that may repeat
+pub struct ListingQuery(pub SearchProductsQuery);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ListingQuery {
+    type Rejection = QueryRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<SearchProductsQuery>::from_request_parts(parts, state).await?;
+        let Query(pairs) = Query::<Vec<(String, String)>>::from_request_parts(parts, state).await?;
+        Ok(ListingQuery(query.with_facet_filters(pairs)))
+    }
+}
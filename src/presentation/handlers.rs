@@ -1,108 +1,291 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::{Html, Json},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{Html, IntoResponse, Json, Redirect, Response},
     Form,
 };
-use serde::Deserialize;
 use std::sync::Arc;
 
 use crate::application::{
-    ProductService, CreateProductRequest, UpdateProductRequest, 
-    ProductResponse, SearchProductsQuery, ApiResponse, ApplicationError
+    ProductService, CreateProductRequest, UpdateProductRequest, DeleteProductRequest,
+    ProductResponse, ApiResponse, ApplicationError, PaginatedResponse,
+    WebhookService, CreateWebhookRequest, UpdateWebhookRequest, WebhookResponse,
+    WebhookDeliveryResponse, InventoryService, AdjustStockRequest, CreateReservationRequest,
+    ReservationResponse, StockHistoryQuery, StockMovementResponse, StockDiscrepancyResponse,
+    CartService, CartResponse, AddCartItemRequest, UpdateCartItemRequest, OrderService, OrderResponse,
+    AccountService, RegisterRequest, LoginRequest, SignedIn, UserResponse, SetRoleRequest, Actor,
+    ApiKeyService, CreateApiKeyRequest, ApiKeyResponse, SuggestionService, SuggestQuery, SuggestionsResponse,
+    CategoryService, CategoryResponse, CreateCategoryRequest, UpdateCategoryRequest, AssignCategoriesRequest,
 };
+use crate::domain::{OrderOwner, RepositoryError, UserId};
 use crate::presentation::templates::{
-    products_page, product_detail_page, product_list_partial, product_card
+    products_page, product_detail_page, product_list_partial, product_card, product_form,
+    product_edit_form, ProductForm,
+    cart_page, cart_contents, cart_summary, CartNotice, orders_page, order_row,
+    login_page, register_page, account_nav, CatalogControls, search_suggestions,
 };
+use crate::presentation::errors::{ApiError, PageError};
+use crate::presentation::extractors::{cookie_value, ApiActor, CurrentUser, ListingQuery, MaybeUser, SESSION_COOKIE};
 
 /// Shared application state
 #[derive(Clone)]
 pub struct AppState {
     pub product_service: Arc<ProductService>,
+    pub webhook_service: Arc<WebhookService>,
+    pub inventory_service: Arc<InventoryService>,
+    pub cart_service: Arc<CartService>,
+    pub order_service: Arc<OrderService>,
+    pub account_service: Arc<AccountService>,
+    pub api_key_service: Arc<ApiKeyService>,
+    pub suggestion_service: Arc<SuggestionService>,
+    pub category_service: Arc<CategoryService>,
+    pub environment: Environment,
 }
 
+/// Where the app is running. Production keeps internal error text off the error pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Environment {
+    #[default]
+    Development,
+    Production,
+}
+
+impl std::str::FromStr for Environment {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "development" | "dev" => Ok(Self::Development),
+            "production" | "prod" => Ok(Self::Production),
+            other => Err(format!("Unknown environment '{}'; expected development or production", other)),
+        }
+    }
+}
+
+/// Actors recorded in the stock ledger for changes made through the browser and the REST API
+pub(crate) const WEB_ACTOR: &str = "web";
+pub(crate) const API_ACTOR: &str = "api";
+
 // ============================================================================
 // HTML Handlers for Browser Interface
 // ============================================================================
 
-pub async fn home_page(State(state): State<AppState>) -> Result<Html<String>, StatusCode> {
-    match state.product_service.get_all_products().await {
-        Ok(products) => {
-            let html = products_page(&products);
-            Ok(Html(html))
-        }
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
+pub async fn home_page(
+    State(state): State<AppState>,
+    user: MaybeUser,
+    ListingQuery(query): ListingQuery,
+) -> Result<Html<String>, PageError> {
+    let selected = query.category.clone();
+    let page = state.product_service.search_products(query).await?;
+    let categories = state.category_service.list_categories().await?;
+    Ok(Html(products_page(&page, &categories, selected.as_deref(), catalog_controls(&user))))
 }
 
 pub async fn product_detail_page_handler(
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> Result<Html<String>, StatusCode> {
-    match state.product_service.get_product_by_id(id).await {
-        Ok(product) => {
-            let html = product_detail_page(&product);
-            Ok(Html(html))
+    user: MaybeUser,
+) -> Result<Html<String>, PageError> {
+    let product = state.product_service.get_product_by_id(id).await?;
+    let actor = user.actor(WEB_ACTOR);
+    let controls = CatalogControls::for_actor(&actor);
+    // The ledger names staff, so it is only shown to those who may see stock records
+    let history = if controls.can_view_stock {
+        state.inventory_service.stock_history(id, None, &actor).await?
+    } else {
+        Vec::new()
+    };
+    let breadcrumbs = state.category_service.breadcrumbs(id).await?;
+
+    Ok(Html(product_detail_page(&product, &history, &breadcrumbs, controls)))
+}
+
+pub async fn cart_page_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, PageError> {
+    let token = cart_token(&headers);
+    let cart = state.cart_service.get_cart(&token).await?;
+    Ok(with_cart_cookie(Html(cart_page(&cart)), &token, state.environment))
+}
+
+pub async fn orders_page_handler(
+    State(state): State<AppState>,
+    user: MaybeUser,
+    headers: HeaderMap,
+) -> Result<Response, PageError> {
+    let token = cart_token(&headers);
+    let orders = state.order_service.list_orders(&order_owner(&token, &user)).await?;
+    Ok(with_cart_cookie(Html(orders_page(&orders)), &token, state.environment))
+}
+
+pub async fn login_page_handler(MaybeUser(user): MaybeUser) -> Response {
+    match user {
+        Some(_) => Redirect::to("/").into_response(),
+        None => Html(login_page(None)).into_response(),
+    }
+}
+
+pub async fn register_page_handler(MaybeUser(user): MaybeUser) -> Response {
+    match user {
+        Some(_) => Redirect::to("/").into_response(),
+        None => Html(register_page(None)).into_response(),
+    }
+}
+
+/// Sign-in form target; goes back to the catalog once signed in
+pub async fn login_handler(
+    State(state): State<AppState>,
+    Form(form): Form<LoginRequest>,
+) -> Result<Response, PageError> {
+    match state.account_service.login(form).await {
+        Ok(signed_in) => Ok(with_session_cookie(Redirect::to("/"), &signed_in, state.environment)),
+        Err(err @ ApplicationError::InvalidCredentials) => {
+            Ok((StatusCode::UNAUTHORIZED, Html(login_page(Some(&err.to_string())))).into_response())
         }
-        Err(ApplicationError::ProductNotFound) => Err(StatusCode::NOT_FOUND),
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+        Err(err) => Err(err.into()),
     }
 }
 
+/// Registration form target; the new account is signed in straight away
+pub async fn register_handler(
+    State(state): State<AppState>,
+    Form(form): Form<RegisterRequest>,
+) -> Result<Response, PageError> {
+    match state.account_service.register(form).await {
+        Ok(signed_in) => Ok(with_session_cookie(Redirect::to("/"), &signed_in, state.environment)),
+        Err(ApplicationError::DomainError(err)) => Ok(Html(register_page(Some(&err.to_string()))).into_response()),
+        Err(ApplicationError::ValidationError(err)) => Ok(Html(register_page(Some(&err))).into_response()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub async fn logout_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, PageError> {
+    end_session(&state, &headers).await?;
+    Ok(without_session_cookie(Redirect::to("/"), state.environment))
+}
+
 // ============================================================================
 // HTMX Handlers for Dynamic Updates
 // ============================================================================
 
-#[derive(Deserialize)]
-pub struct HtmxSearchQuery {
-    search: Option<String>,
+pub async fn htmx_products_list(
+    State(state): State<AppState>,
+    user: MaybeUser,
+    ListingQuery(query): ListingQuery,
+) -> Result<Html<String>, StatusCode> {
+    match state.product_service.search_products(query).await {
+        Ok(page) => {
+            let html = product_list_partial(&page, catalog_controls(&user));
+            Ok(Html(html))
+        }
+        Err(ApplicationError::DomainError(_)) => Err(StatusCode::BAD_REQUEST),
+        Err(ApplicationError::ValidationError(_)) => Err(StatusCode::BAD_REQUEST),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// The dropdown of suggestions under the search box
+pub async fn htmx_product_suggestions(
+    State(state): State<AppState>,
+    Query(query): Query<SuggestQuery>,
+) -> Result<Html<String>, StatusCode> {
+    match state.suggestion_service.suggest(&query) {
+        Ok(suggestions) => Ok(Html(search_suggestions(&suggestions))),
+        Err(_) => Err(StatusCode::BAD_REQUEST),
+    }
 }
 
-pub async fn htmx_products_list(
+pub async fn htmx_product_card(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: MaybeUser,
+) -> Result<Html<String>, StatusCode> {
+    match state.product_service.get_product_by_id(id).await {
+        Ok(product) => Ok(Html(product_card(&product, catalog_controls(&user)))),
+        Err(ApplicationError::ProductNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// The edit form, swapped in place of the product's card
+pub async fn htmx_edit_product_form(
     State(state): State<AppState>,
-    Query(params): Query<HtmxSearchQuery>,
+    Path(id): Path<i64>,
+    user: MaybeUser,
 ) -> Result<Html<String>, StatusCode> {
-    let query = SearchProductsQuery {
-        query: params.search,
-        limit: None,
-        offset: None,
-    };
+    if !catalog_controls(&user).can_edit {
+        return Err(StatusCode::FORBIDDEN);
+    }
 
-    match state.product_service.search_products(query).await {
-        Ok(products) => {
-            let html = product_list_partial(&products);
+    match state.product_service.get_product_by_id(id).await {
+        Ok(product) => {
+            let html = product_edit_form(product.id, product.version, &ProductForm::for_product(&product));
             Ok(Html(html))
         }
+        Err(ApplicationError::ProductNotFound) => Err(StatusCode::NOT_FOUND),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
+/// Adds the new product's card to the list and resets the form; invalid input re-renders the
+/// form with an error against each field
 pub async fn htmx_create_product(
     State(state): State<AppState>,
+    user: MaybeUser,
     Form(form): Form<CreateProductRequest>,
-) -> Result<Html<String>, StatusCode> {
-    match state.product_service.create_product(form).await {
+) -> Result<Response, StatusCode> {
+    let actor = user.actor(WEB_ACTOR);
+    let submitted = ProductForm::submitted(&form);
+
+    match state.product_service.create_product(form, &actor).await {
         Ok(product) => {
-            let html = product_card(&product);
-            Ok(Html(html))
+            let card = product_card(&product, CatalogControls::for_actor(&actor));
+            let html = card + &product_form(&ProductForm::default(), true);
+            Ok(Html(html).into_response())
         }
+        Err(ApplicationError::InvalidFields(errors)) => {
+            let html = product_form(&submitted.with_errors(errors), false);
+            Ok(form_errors("#product-form", html))
+        }
+        Err(ApplicationError::AuthorizationError(_)) => Err(StatusCode::FORBIDDEN),
         Err(ApplicationError::DomainError(_)) => Err(StatusCode::BAD_REQUEST),
         Err(ApplicationError::ValidationError(_)) => Err(StatusCode::BAD_REQUEST),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
+/// Replaces the edit form with the updated card; invalid input re-renders the form with an
+/// error against each field
 pub async fn htmx_update_product(
     State(state): State<AppState>,
     Path(id): Path<i64>,
-    Form(form): Form<UpdateProductRequest>,
-) -> Result<Html<String>, StatusCode> {
-    match state.product_service.update_product(id, form).await {
+    user: MaybeUser,
+    headers: HeaderMap,
+    Form(mut form): Form<UpdateProductRequest>,
+) -> Result<Response, StatusCode> {
+    form.version = required_version(&headers, form.version)?;
+    let actor = user.actor(WEB_ACTOR);
+    let submitted = ProductForm::submitted_update(&form);
+    let version = form.version.unwrap_or_default();
+
+    match state.product_service.update_product(id, form, &actor).await {
         Ok(product) => {
-            let html = product_card(&product);
-            Ok(Html(html))
+            let html = product_card(&product, CatalogControls::for_actor(&actor));
+            Ok(Html(html).into_response())
+        }
+        Err(ApplicationError::InvalidFields(errors)) => {
+            let html = product_edit_form(id, version, &submitted.with_errors(errors));
+            Ok(form_errors(&format!("#product-{}", id), html))
         }
+        Err(ApplicationError::AuthorizationError(_)) => Err(StatusCode::FORBIDDEN),
         Err(ApplicationError::ProductNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(ApplicationError::RepositoryError(RepositoryError::ConcurrentModification)) => {
+            Err(conflict_status(&headers))
+        }
         Err(ApplicationError::DomainError(_)) => Err(StatusCode::BAD_REQUEST),
         Err(ApplicationError::ValidationError(_)) => Err(StatusCode::BAD_REQUEST),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
@@ -112,98 +295,908 @@ pub async fn htmx_update_product(
 pub async fn htmx_delete_product(
     State(state): State<AppState>,
     Path(id): Path<i64>,
+    user: MaybeUser,
+    headers: HeaderMap,
 ) -> Result<Html<String>, StatusCode> {
-    match state.product_service.delete_product(id).await {
+    let version = required_version(&headers, None)?;
+
+    match state.product_service.delete_product(id, version, &user.actor(WEB_ACTOR)).await {
         Ok(true) => Ok(Html(String::new())), // Empty response removes the element
         Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(ApplicationError::AuthorizationError(_)) => Err(StatusCode::FORBIDDEN),
         Err(ApplicationError::ProductNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(ApplicationError::RepositoryError(RepositoryError::ConcurrentModification)) => {
+            Err(conflict_status(&headers))
+        }
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Sign-in links or the signed-in customer, shown in page headers
+pub async fn htmx_account_nav(MaybeUser(user): MaybeUser) -> Html<String> {
+    Html(account_nav(user.as_ref()))
+}
+
+pub async fn htmx_cart_summary(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let token = cart_token(&headers);
+
+    match state.cart_service.get_cart(&token).await {
+        Ok(cart) => Ok(with_cart_cookie(Html(cart_summary(&cart, None)), &token, state.environment)),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
+/// Add to cart from a product card or detail page; answers with the refreshed cart badge.
+/// Stock and currency problems are shown next to the badge.
+pub async fn htmx_add_to_cart(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<AddCartItemRequest>,
+) -> Result<Response, StatusCode> {
+    let token = cart_token(&headers);
+
+    let html = match state.cart_service.add_item(&token, form).await {
+        Ok(cart) => cart_summary(&cart, Some(CartNotice::Success("Added to cart"))),
+        Err(ApplicationError::ProductNotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(ApplicationError::DomainError(err)) => {
+            let cart = state.cart_service.get_cart(&token).await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            cart_summary(&cart, Some(CartNotice::Error(&err.to_string())))
+        }
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    Ok(with_cart_cookie(Html(html), &token, state.environment))
+}
+
+pub async fn htmx_update_cart_item(
+    State(state): State<AppState>,
+    Path(product_id): Path<i64>,
+    headers: HeaderMap,
+    Form(form): Form<UpdateCartItemRequest>,
+) -> Result<Response, StatusCode> {
+    let token = cart_token(&headers);
+    let result = state.cart_service.update_item(&token, product_id, form).await;
+    cart_contents_response(&state, &token, result).await
+}
+
+pub async fn htmx_remove_cart_item(
+    State(state): State<AppState>,
+    Path(product_id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let token = cart_token(&headers);
+    let result = state.cart_service.remove_item(&token, product_id).await;
+    cart_contents_response(&state, &token, result).await
+}
+
+pub async fn htmx_clear_cart(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let token = cart_token(&headers);
+    let result = state.cart_service.clear_cart(&token).await;
+    cart_contents_response(&state, &token, result).await
+}
+
+/// Place an order from the cart page. On success the browser is sent to the order list;
+/// otherwise the cart is shown again with the reason.
+pub async fn htmx_place_order(
+    State(state): State<AppState>,
+    user: MaybeUser,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let token = cart_token(&headers);
+
+    let message = match state.order_service.place_order(&order_owner(&token, &user), WEB_ACTOR).await {
+        Ok(_) => {
+            let redirect = [("HX-Redirect", HeaderValue::from_static("/orders"))];
+            return Ok(with_cart_cookie((redirect, StatusCode::OK), &token, state.environment));
+        }
+        Err(err @ (ApplicationError::DomainError(_)
+        | ApplicationError::ValidationError(_)
+        | ApplicationError::ProductNotFound)) => err.to_string(),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let cart = state.cart_service.get_cart(&token).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(with_cart_cookie(Html(cart_contents(&cart, Some(CartNotice::Error(&message)))), &token, state.environment))
+}
+
+/// Cancel an order from the order list; answers with its refreshed row
+pub async fn htmx_cancel_order(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: MaybeUser,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let token = cart_token(&headers);
+    let owner = order_owner(&token, &user);
+
+    let html = match state.order_service.cancel_order(&owner, id, &user.actor(WEB_ACTOR)).await {
+        Ok(order) => order_row(&order, None),
+        Err(ApplicationError::OrderNotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(err @ (ApplicationError::DomainError(_) | ApplicationError::AuthorizationError(_))) => {
+            let order = state.order_service.get_order(&owner, id).await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            order_row(&order, Some(&err.to_string()))
+        }
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    Ok(with_cart_cookie(Html(html), &token, state.environment))
+}
+
+/// Render the cart table after an edit, with the reason if the edit was refused
+async fn cart_contents_response(
+    state: &AppState,
+    token: &str,
+    result: Result<CartResponse, ApplicationError>,
+) -> Result<Response, StatusCode> {
+    let html = match result {
+        Ok(cart) => cart_contents(&cart, None),
+        Err(ApplicationError::ProductNotFound | ApplicationError::CartItemNotFound) => {
+            return Err(StatusCode::NOT_FOUND)
+        }
+        Err(ApplicationError::DomainError(err)) => {
+            let cart = state.cart_service.get_cart(token).await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            cart_contents(&cart, Some(CartNotice::Error(&err.to_string())))
+        }
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    Ok(with_cart_cookie(Html(html), token, state.environment))
+}
+
 // ============================================================================
 // REST API Handlers for JSON Interface  
 // ============================================================================
+//
+// Failures are `ApiError`s, which pick the status and render RFC 7807 problem details.
 
 pub async fn api_get_products(
     State(state): State<AppState>,
-) -> Result<Json<ApiResponse<Vec<ProductResponse>>>, StatusCode> {
-    match state.product_service.get_all_products().await {
-        Ok(products) => Ok(Json(ApiResponse::success(products))),
-        Err(err) => {
-            let error_msg = format!("Failed to retrieve products: {}", err);
-            Ok(Json(ApiResponse::error(error_msg)))
-        }
-    }
+    ListingQuery(query): ListingQuery,
+) -> Result<Json<ApiResponse<PaginatedResponse<ProductResponse>>>, ApiError> {
+    let page = state.product_service.search_products(query).await?;
+    Ok(Json(ApiResponse::success(page)))
+}
+
+pub async fn api_suggest_products(
+    State(state): State<AppState>,
+    Query(query): Query<SuggestQuery>,
+) -> Result<Json<ApiResponse<SuggestionsResponse>>, ApiError> {
+    let suggestions = state.suggestion_service.suggest(&query)?;
+    Ok(Json(ApiResponse::success(suggestions)))
 }
 
 pub async fn api_get_product(
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse<ProductResponse>>, StatusCode> {
-    match state.product_service.get_product_by_id(id).await {
-        Ok(product) => Ok(Json(ApiResponse::success(product))),
-        Err(ApplicationError::ProductNotFound) => Err(StatusCode::NOT_FOUND),
-        Err(err) => {
-            let error_msg = format!("Failed to retrieve product: {}", err);
-            Ok(Json(ApiResponse::error(error_msg)))
-        }
-    }
+) -> Result<Response, ApiError> {
+    let product = state.product_service.get_product_by_id(id).await?;
+    Ok(with_etag(product))
 }
 
 pub async fn api_create_product(
     State(state): State<AppState>,
+    ApiActor(actor): ApiActor,
     Json(request): Json<CreateProductRequest>,
-) -> Result<Json<ApiResponse<ProductResponse>>, StatusCode> {
-    match state.product_service.create_product(request).await {
-        Ok(product) => Ok(Json(ApiResponse::success(product))),
-        Err(ApplicationError::DomainError(err)) => {
-            let error_msg = format!("Invalid product data: {}", err);
-            Ok(Json(ApiResponse::error(error_msg)))
-        }
-        Err(ApplicationError::ValidationError(err)) => {
-            Ok(Json(ApiResponse::validation_error(vec![err])))
-        }
-        Err(err) => {
-            let error_msg = format!("Failed to create product: {}", err);
-            Ok(Json(ApiResponse::error(error_msg)))
-        }
-    }
+) -> Result<Response, ApiError> {
+    let product = state.product_service.create_product(request, &actor).await?;
+    Ok((StatusCode::CREATED, with_etag(product)).into_response())
 }
 
 pub async fn api_update_product(
     State(state): State<AppState>,
     Path(id): Path<i64>,
-    Json(request): Json<UpdateProductRequest>,
-) -> Result<Json<ApiResponse<ProductResponse>>, StatusCode> {
-    match state.product_service.update_product(id, request).await {
-        Ok(product) => Ok(Json(ApiResponse::success(product))),
-        Err(ApplicationError::ProductNotFound) => Err(StatusCode::NOT_FOUND),
-        Err(ApplicationError::DomainError(err)) => {
-            let error_msg = format!("Invalid product data: {}", err);
-            Ok(Json(ApiResponse::error(error_msg)))
-        }
-        Err(ApplicationError::ValidationError(err)) => {
-            Ok(Json(ApiResponse::validation_error(vec![err])))
-        }
-        Err(err) => {
-            let error_msg = format!("Failed to update product: {}", err);
-            Ok(Json(ApiResponse::error(error_msg)))
-        }
-    }
+    ApiActor(actor): ApiActor,
+    headers: HeaderMap,
+    Json(mut request): Json<UpdateProductRequest>,
+) -> Result<Response, ApiError> {
+    request.version = required_version(&headers, request.version)?;
+
+    let product = state.product_service.update_product(id, request, &actor).await
+        .map_err(|err| versioned_error(err, &headers))?;
+    Ok(with_etag(product))
 }
 
 pub async fn api_delete_product(
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse<String>>, StatusCode> {
-    match state.product_service.delete_product(id).await {
+    ApiActor(actor): ApiActor,
+    headers: HeaderMap,
+    body: Option<Json<DeleteProductRequest>>,
+) -> Result<Json<ApiResponse<String>>, ApiError> {
+    let body_version = body.and_then(|Json(body)| body.version);
+    let version = required_version(&headers, body_version)?;
+
+    match state.product_service.delete_product(id, version, &actor).await {
         Ok(true) => Ok(Json(ApiResponse::success("Product deleted successfully".to_string()))),
-        Ok(false) => Err(StatusCode::NOT_FOUND),
-        Err(ApplicationError::ProductNotFound) => Err(StatusCode::NOT_FOUND),
-        Err(err) => {
-            let error_msg = format!("Failed to delete product: {}", err);
-            Ok(Json(ApiResponse::error(error_msg)))
+        Ok(false) => Err(ApplicationError::ProductNotFound.into()),
+        Err(err) => Err(versioned_error(err, &headers)),
+    }
+}
+
+// ============================================================================
+// Category API Handlers
+// ============================================================================
+
+pub async fn api_get_categories(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<CategoryResponse>>>, ApiError> {
+    let categories = state.category_service.list_categories().await?;
+    Ok(Json(ApiResponse::success(categories)))
+}
+
+pub async fn api_get_category(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse<CategoryResponse>>, ApiError> {
+    let category = state.category_service.get_category(id).await?;
+    Ok(Json(ApiResponse::success(category)))
+}
+
+pub async fn api_create_category(
+    State(state): State<AppState>,
+    ApiActor(actor): ApiActor,
+    Json(request): Json<CreateCategoryRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<CategoryResponse>>), ApiError> {
+    let category = state.category_service.create_category(request, &actor).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(category))))
+}
+
+pub async fn api_update_category(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+    Json(request): Json<UpdateCategoryRequest>,
+) -> Result<Json<ApiResponse<CategoryResponse>>, ApiError> {
+    let category = state.category_service.update_category(id, request, &actor).await?;
+    Ok(Json(ApiResponse::success(category)))
+}
+
+pub async fn api_delete_category(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<String>>, ApiError> {
+    state.category_service.delete_category(id, &actor).await?;
+    Ok(Json(ApiResponse::success("Category deleted successfully".to_string())))
+}
+
+pub async fn api_get_product_categories(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse<Vec<CategoryResponse>>>, ApiError> {
+    let categories = state.category_service.product_categories(id).await?;
+    Ok(Json(ApiResponse::success(categories)))
+}
+
+pub async fn api_assign_product_categories(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+    Json(request): Json<AssignCategoriesRequest>,
+) -> Result<Json<ApiResponse<Vec<CategoryResponse>>>, ApiError> {
+    let categories = state.category_service.assign_categories(id, request, &actor).await?;
+    Ok(Json(ApiResponse::success(categories)))
+}
+
+// ============================================================================
+// Stock API Handlers
+// ============================================================================
+
+pub async fn api_adjust_stock(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+    Json(request): Json<AdjustStockRequest>,
+) -> Result<Response, ApiError> {
+    let product = state.inventory_service.adjust_stock(id, request, &actor).await?;
+    Ok(with_etag(product))
+}
+
+pub async fn api_get_stock_history(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<StockHistoryQuery>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<Vec<StockMovementResponse>>>, ApiError> {
+    let history = state.inventory_service.stock_history(id, query.limit, &actor).await?;
+    Ok(Json(ApiResponse::success(history)))
+}
+
+pub async fn api_get_stock_reconciliation(
+    State(state): State<AppState>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<Vec<StockDiscrepancyResponse>>>, ApiError> {
+    let discrepancies = state.inventory_service.reconcile_stock(&actor).await?;
+    Ok(Json(ApiResponse::success(discrepancies)))
+}
+
+pub async fn api_create_reservation(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+    Json(request): Json<CreateReservationRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<ReservationResponse>>), ApiError> {
+    let reservation = state.inventory_service.reserve_stock(id, request, &actor).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(reservation))))
+}
+
+pub async fn api_get_reservation(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<ReservationResponse>>, ApiError> {
+    let reservation = state.inventory_service.get_reservation(id, &actor).await?;
+    Ok(Json(ApiResponse::success(reservation)))
+}
+
+pub async fn api_commit_reservation(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<ReservationResponse>>, ApiError> {
+    let reservation = state.inventory_service.commit_reservation(id, &actor).await?;
+    Ok(Json(ApiResponse::success(reservation)))
+}
+
+pub async fn api_release_reservation(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<ReservationResponse>>, ApiError> {
+    let reservation = state.inventory_service.release_reservation(id, &actor).await?;
+    Ok(Json(ApiResponse::success(reservation)))
+}
+
+// ============================================================================
+// Cart API Handlers
+// ============================================================================
+
+pub async fn api_get_cart(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let token = cart_token(&headers);
+    let cart = state.cart_service.get_cart(&token).await?;
+    Ok(cart_json(cart, &token, state.environment))
+}
+
+pub async fn api_add_cart_item(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<AddCartItemRequest>,
+) -> Result<Response, ApiError> {
+    let token = cart_token(&headers);
+    let cart = state.cart_service.add_item(&token, request).await?;
+    Ok(cart_json(cart, &token, state.environment))
+}
+
+pub async fn api_update_cart_item(
+    State(state): State<AppState>,
+    Path(product_id): Path<i64>,
+    headers: HeaderMap,
+    Json(request): Json<UpdateCartItemRequest>,
+) -> Result<Response, ApiError> {
+    let token = cart_token(&headers);
+    let cart = state.cart_service.update_item(&token, product_id, request).await?;
+    Ok(cart_json(cart, &token, state.environment))
+}
+
+pub async fn api_remove_cart_item(
+    State(state): State<AppState>,
+    Path(product_id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let token = cart_token(&headers);
+    let cart = state.cart_service.remove_item(&token, product_id).await?;
+    Ok(cart_json(cart, &token, state.environment))
+}
+
+pub async fn api_clear_cart(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let token = cart_token(&headers);
+    let cart = state.cart_service.clear_cart(&token).await?;
+    Ok(cart_json(cart, &token, state.environment))
+}
+
+fn cart_json(cart: CartResponse, token: &str, environment: Environment) -> Response {
+    with_cart_cookie(Json(ApiResponse::success(cart)), token, environment)
+}
+
+// ============================================================================
+// Order API Handlers
+// ============================================================================
+
+pub async fn api_get_orders(
+    State(state): State<AppState>,
+    user: MaybeUser,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let token = cart_token(&headers);
+    let orders = state.order_service.list_orders(&order_owner(&token, &user)).await?;
+    Ok(with_cart_cookie(Json(ApiResponse::success(orders)), &token, state.environment))
+}
+
+/// Place an order for everything in the session's cart
+pub async fn api_place_order(
+    State(state): State<AppState>,
+    user: MaybeUser,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let token = cart_token(&headers);
+    let order = state.order_service.place_order(&order_owner(&token, &user), API_ACTOR).await?;
+    Ok((StatusCode::CREATED, order_json(order, &token, state.environment)).into_response())
+}
+
+pub async fn api_get_order(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: MaybeUser,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let token = cart_token(&headers);
+    let order = state.order_service.get_order(&order_owner(&token, &user), id).await?;
+    Ok(order_json(order, &token, state.environment))
+}
+
+/// Record payment for any order; staff only, like shipping and delivery
+pub async fn api_pay_order(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<OrderResponse>>, ApiError> {
+    let order = state.order_service.pay_order(id, &actor).await?;
+    Ok(Json(ApiResponse::success(order)))
+}
+
+pub async fn api_ship_order(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<OrderResponse>>, ApiError> {
+    let order = state.order_service.ship_order(id, &actor).await?;
+    Ok(Json(ApiResponse::success(order)))
+}
+
+pub async fn api_deliver_order(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<OrderResponse>>, ApiError> {
+    let order = state.order_service.deliver_order(id, &actor).await?;
+    Ok(Json(ApiResponse::success(order)))
+}
+
+/// Cancel an order and return its items to stock; customers their own pending orders,
+/// staff any order that has not shipped
+pub async fn api_cancel_order(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: MaybeUser,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let token = cart_token(&headers);
+    let owner = order_owner(&token, &user);
+    let order = state.order_service.cancel_order(&owner, id, &user.actor(API_ACTOR)).await?;
+    Ok(order_json(order, &token, state.environment))
+}
+
+fn order_json(order: OrderResponse, token: &str, environment: Environment) -> Response {
+    with_cart_cookie(Json(ApiResponse::success(order)), token, environment)
+}
+
+// ============================================================================
+// Account API Handlers
+// ============================================================================
+
+pub async fn api_register(
+    State(state): State<AppState>,
+    Json(request): Json<RegisterRequest>,
+) -> Result<Response, ApiError> {
+    let signed_in = state.account_service.register(request).await?;
+    let body = (StatusCode::CREATED, Json(ApiResponse::success(signed_in.user.clone())));
+    Ok(with_session_cookie(body, &signed_in, state.environment))
+}
+
+pub async fn api_login(
+    State(state): State<AppState>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Response, ApiError> {
+    let signed_in = state.account_service.login(request).await?;
+    let body = Json(ApiResponse::success(signed_in.user.clone()));
+    Ok(with_session_cookie(body, &signed_in, state.environment))
+}
+
+pub async fn api_logout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    end_session(&state, &headers).await?;
+    let body = Json(ApiResponse::success("Signed out".to_string()));
+    Ok(without_session_cookie(body, state.environment))
+}
+
+pub async fn api_get_current_user(CurrentUser(user): CurrentUser) -> Json<ApiResponse<UserResponse>> {
+    Json(ApiResponse::success(user))
+}
+
+/// Change an account's role; admins only
+pub async fn api_set_user_role(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    CurrentUser(user): CurrentUser,
+    Json(request): Json<SetRoleRequest>,
+) -> Result<Json<ApiResponse<UserResponse>>, ApiError> {
+    let user = state.account_service.set_role(id, request, &Actor::user(&user)).await?;
+    Ok(Json(ApiResponse::success(user)))
+}
+
+// ============================================================================
+// API Key Admin Handlers
+// ============================================================================
+
+/// Issue an API key; the response holds the only copy of the secret
+pub async fn api_create_api_key(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<Response, ApiError> {
+    let key = state.api_key_service.issue_key(request, &Actor::user(&user)).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(key))).into_response())
+}
+
+pub async fn api_get_api_keys(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<ApiResponse<Vec<ApiKeyResponse>>>, ApiError> {
+    let keys = state.api_key_service.list_keys(&Actor::user(&user)).await?;
+    Ok(Json(ApiResponse::success(keys)))
+}
+
+pub async fn api_revoke_api_key(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    CurrentUser(user): CurrentUser,
+) -> Result<Json<ApiResponse<ApiKeyResponse>>, ApiError> {
+    let key = state.api_key_service.revoke_key(id, &Actor::user(&user)).await?;
+    Ok(Json(ApiResponse::success(key)))
+}
+
+// ============================================================================
+// Webhook API Handlers
+// ============================================================================
+
+pub async fn api_get_webhooks(
+    State(state): State<AppState>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<Vec<WebhookResponse>>>, ApiError> {
+    let webhooks = state.webhook_service.list_webhooks(&actor).await?;
+    Ok(Json(ApiResponse::success(webhooks)))
+}
+
+pub async fn api_get_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<WebhookResponse>>, ApiError> {
+    let webhook = state.webhook_service.get_webhook(id, &actor).await?;
+    Ok(Json(ApiResponse::success(webhook)))
+}
+
+pub async fn api_create_webhook(
+    State(state): State<AppState>,
+    ApiActor(actor): ApiActor,
+    Json(request): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<WebhookResponse>>), ApiError> {
+    let webhook = state.webhook_service.create_webhook(request, &actor).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(webhook))))
+}
+
+pub async fn api_update_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+    Json(request): Json<UpdateWebhookRequest>,
+) -> Result<Json<ApiResponse<WebhookResponse>>, ApiError> {
+    let webhook = state.webhook_service.update_webhook(id, request, &actor).await?;
+    Ok(Json(ApiResponse::success(webhook)))
+}
+
+pub async fn api_delete_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<String>>, ApiError> {
+    state.webhook_service.delete_webhook(id, &actor).await?;
+    Ok(Json(ApiResponse::success("Webhook deleted successfully".to_string())))
+}
+
+pub async fn api_get_webhook_deliveries(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiActor(actor): ApiActor,
+) -> Result<Json<ApiResponse<Vec<WebhookDeliveryResponse>>>, ApiError> {
+    let deliveries = state.webhook_service.list_deliveries(id, &actor).await?;
+    Ok(Json(ApiResponse::success(deliveries)))
+}
+
+// ============================================================================
+// Optimistic Concurrency Helpers
+// ============================================================================
+
+/// Resolve the version a write must match, from `If-Match` or else the request body.
+/// `If-Match: *` opts out of the check; no precondition at all is answered with 428.
+fn required_version(headers: &HeaderMap, body_version: Option<i64>) -> Result<Option<i64>, StatusCode> {
+    match headers.get(header::IF_MATCH) {
+        Some(value) => {
+            let value = value.to_str().map_err(|_| StatusCode::BAD_REQUEST)?.trim();
+            if value == "*" {
+                return Ok(None);
+            }
+            // If-Match uses strong comparison, so a weak tag never matches;
+            // neither can an entity tag we never issued
+            if value.starts_with("W/") {
+                return Err(StatusCode::PRECONDITION_FAILED);
+            }
+            let tag = value.trim_matches('"');
+            tag.parse::<i64>().map(Some).map_err(|_| StatusCode::PRECONDITION_FAILED)
+        }
+        None => body_version.map(Some).ok_or(StatusCode::PRECONDITION_REQUIRED),
+    }
+}
+
+/// 412 when the stale version came from `If-Match`, 409 when it came from the body
+fn conflict_status(headers: &HeaderMap) -> StatusCode {
+    if headers.contains_key(header::IF_MATCH) {
+        StatusCode::PRECONDITION_FAILED
+    } else {
+        StatusCode::CONFLICT
+    }
+}
+
+/// `ApiError::from`, except that a stale version is a 412 when it came from `If-Match`
+fn versioned_error(err: ApplicationError, headers: &HeaderMap) -> ApiError {
+    match err {
+        ApplicationError::RepositoryError(RepositoryError::ConcurrentModification) => ApiError::new(
+            conflict_status(headers),
+            "The product was changed since it was read; fetch it again and retry",
+        ),
+        err => err.into(),
+    }
+}
+
+/// Wrap a product in the API envelope and expose its version as a strong `ETag`
+fn with_etag(product: ProductResponse) -> Response {
+    let etag = HeaderValue::from_str(&format!("\"{}\"", product.version))
+        .expect("a quoted integer is a valid header value");
+    ([(header::ETAG, etag)], Json(ApiResponse::success(product))).into_response()
+}
+
+/// A form re-rendered with its validation errors. HTMX is told to put it in place of the form
+/// at `target`, whatever the request was aimed at.
+fn form_errors(target: &str, html: String) -> Response {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        [("HX-Retarget", target.to_string()), ("HX-Reswap", "outerHTML".to_string())],
+        Html(html),
+    )
+        .into_response()
+}
+
+// ============================================================================
+// Authorization Helpers
+// ============================================================================
+
+/// Which catalog controls to render for the visitor
+fn catalog_controls(user: &MaybeUser) -> CatalogControls {
+    CatalogControls::for_actor(&user.actor(WEB_ACTOR))
+}
+
+// ============================================================================
+// Session Helpers
+// ============================================================================
+
+/// Cookie holding the visitor's cart token, which also identifies their orders
+const CART_COOKIE: &str = "cart_id";
+/// Refreshed on every cart request, so carts last 30 days after the last visit
+const CART_COOKIE_MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// The cart token from the request's cookie, or a fresh one for a new visitor.
+/// Tokens are random v4 UUIDs; anything else is replaced.
+fn cart_token(headers: &HeaderMap) -> String {
+    cookie_value(headers, CART_COOKIE)
+        .filter(|value| value.len() == 32 && value.chars().all(|c| c.is_ascii_hexdigit()))
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string())
+}
+
+/// Whose orders the request may see: its cart session's, and the signed-in customer's
+fn order_owner(token: &str, user: &MaybeUser) -> OrderOwner {
+    let user_id = user.0.as_ref().and_then(|user| UserId::new(user.id).ok());
+    OrderOwner::new(token, user_id)
+}
+
+fn with_cart_cookie(response: impl IntoResponse, token: &str, environment: Environment) -> Response {
+    let cookie = format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+        CART_COOKIE, token, CART_COOKIE_MAX_AGE_SECS
+    );
+    with_cookie(response, &cookie, environment)
+}
+
+/// Hand the browser its sign-in session
+fn with_session_cookie(response: impl IntoResponse, signed_in: &SignedIn, environment: Environment) -> Response {
+    let cookie = format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+        SESSION_COOKIE,
+        signed_in.token,
+        (signed_in.expires_at - chrono::Utc::now()).num_seconds().max(0)
+    );
+    with_cookie(response, &cookie, environment)
+}
+
+fn without_session_cookie(response: impl IntoResponse, environment: Environment) -> Response {
+    let cookie = format!("{}=; Path=/; Max-Age=0; HttpOnly; SameSite=Lax", SESSION_COOKIE);
+    with_cookie(response, &cookie, environment)
+}
+
+/// Set `cookie` on the response. Production is served over HTTPS, so its cookies are marked
+/// `Secure` and never sent over plain HTTP.
+fn with_cookie(response: impl IntoResponse, cookie: &str, environment: Environment) -> Response {
+    let cookie = match environment {
+        Environment::Production => format!("{}; Secure", cookie),
+        Environment::Development => cookie.to_string(),
+    };
+    let mut response = response.into_response();
+    if let Ok(value) = HeaderValue::from_str(&cookie) {
+        response.headers_mut().append(header::SET_COOKIE, value);
+    }
+    response
+}
+
+/// Forget the session the request was signed in with, if any
+async fn end_session(state: &AppState, headers: &HeaderMap) -> Result<(), ApplicationError> {
+    if let Some(token) = cookie_value(headers, SESSION_COOKIE) {
+        state.account_service.logout(&token).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use crate::infrastructure::{
+        migrated_pool, Argon2PasswordHasher, SqliteApiKeyRepository, SqliteCartRepository, SqliteCategoryRepository,
+        SqliteOrderRepository, SqliteProductRepository, SqliteUserRepository, SqliteWebhookRepository,
+    };
+
+    /// The application state `main` wires up, over a fresh database
+    async fn state() -> AppState {
+        let pool = migrated_pool().await;
+        let products = Arc::new(SqliteProductRepository::new(pool.clone()));
+        let carts = Arc::new(SqliteCartRepository::new(pool.clone()));
+        let users = Arc::new(SqliteUserRepository::new(pool.clone()));
+
+        AppState {
+            product_service: Arc::new(ProductService::new(products.clone())),
+            webhook_service: Arc::new(WebhookService::new(Arc::new(SqliteWebhookRepository::new(pool.clone())))),
+            inventory_service: Arc::new(InventoryService::new(products.clone(), products.clone())),
+            cart_service: Arc::new(CartService::new(carts.clone(), products.clone())),
+            order_service: Arc::new(OrderService::new(
+                Arc::new(SqliteOrderRepository::new(pool.clone())),
+                carts,
+                products.clone(),
+            )),
+            account_service: Arc::new(AccountService::new(users.clone(), users, Arc::new(Argon2PasswordHasher::new()))),
+            api_key_service: Arc::new(ApiKeyService::new(Arc::new(SqliteApiKeyRepository::new(pool.clone())))),
+            suggestion_service: Arc::new(SuggestionService::new(products.clone())),
+            category_service: Arc::new(CategoryService::new(Arc::new(SqliteCategoryRepository::new(pool)), products)),
+            environment: Environment::Development,
         }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn created_resources_are_answered_with_201() {
+        let state = state().await;
+        let admin = || ApiActor(Actor::system("test"));
+        let product = CreateProductRequest {
+            name: "Desk Lamp".to_string(),
+            description: None,
+            price: 25.0,
+            currency: None,
+            stock: 4,
+            tags: Vec::new(),
+            attributes: BTreeMap::new(),
+        };
+
+        let response = api_create_product(State(state.clone()), admin(), Json(product)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[header::ETAG], "\"1\"");
+
+        let category = CreateCategoryRequest { name: "Lighting".to_string(), slug: None, parent_id: None };
+        let (status, _) = api_create_category(State(state.clone()), admin(), Json(category)).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+
+        let reservation = CreateReservationRequest { quantity: 1, ttl_seconds: None };
+        let (status, _) = api_create_reservation(State(state.clone()), Path(1), admin(), Json(reservation)).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+
+        let webhook = CreateWebhookRequest {
+            url: "https://erp.example.com/hook".to_string(),
+            events: vec!["product.created".to_string()],
+            secret: None,
+        };
+        let (status, _) = api_create_webhook(State(state), admin(), Json(webhook)).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn weak_entity_tags_never_satisfy_if_match() {
+        let state = state().await;
+        let admin = || ApiActor(Actor::system("test"));
+        let product = CreateProductRequest {
+            name: "Desk Lamp".to_string(),
+            description: None,
+            price: 25.0,
+            currency: None,
+            stock: 4,
+            tags: Vec::new(),
+            attributes: BTreeMap::new(),
+        };
+        api_create_product(State(state.clone()), admin(), Json(product)).await.unwrap();
+        let if_match = |tag: &'static str| HeaderMap::from_iter([(header::IF_MATCH, HeaderValue::from_static(tag))]);
+        let rename = || UpdateProductRequest {
+            name: Some("Floor Lamp".to_string()),
+            description: None,
+            price: None,
+            currency: None,
+            stock: None,
+            tags: None,
+            attributes: None,
+            version: None,
+        };
+
+        let err = api_update_product(State(state.clone()), Path(1), admin(), if_match("W/\"1\""), Json(rename()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::PRECONDITION_FAILED);
+        let err = api_delete_product(State(state.clone()), Path(1), admin(), if_match("W/\"1\""), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::PRECONDITION_FAILED);
+
+        let response = api_update_product(State(state), Path(1), admin(), if_match("\"1\""), Json(rename()))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::ETAG], "\"2\"");
+    }
+
+    #[tokio::test]
+    async fn production_cookies_are_only_sent_over_https() {
+        let register = |email: &str| RegisterRequest {
+            email: email.to_string(),
+            name: "Alice".to_string(),
+            password: "correct horse".to_string(),
+        };
+        let cookies = |response: &Response| -> Vec<String> {
+            response.headers().get_all(header::SET_COOKIE).iter().map(|v| v.to_str().unwrap().to_string()).collect()
+        };
+
+        let production = AppState { environment: Environment::Production, ..state().await };
+        let response = api_register(State(production.clone()), Json(register("alice@example.com"))).await.unwrap();
+        assert!(cookies(&response)[0].starts_with("session_id=") && cookies(&response)[0].ends_with("; Secure"));
+        let response = api_get_cart(State(production), HeaderMap::new()).await.unwrap();
+        assert!(cookies(&response)[0].starts_with("cart_id=") && cookies(&response)[0].ends_with("; Secure"));
+
+        let response = api_register(State(state().await), Json(register("bob@example.com"))).await.unwrap();
+        assert!(!cookies(&response)[0].contains("Secure"));
+    }
+}
@@ -0,0 +1,101 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::Instrument;
+
+use crate::application::ApiKeyResponse;
+use crate::presentation::errors::ApiError;
+use crate::presentation::handlers::AppState;
+
+/// Header machine clients may send their key in instead of `Authorization: Bearer`
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Header carrying the request ID, both ways
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Longest request ID accepted from a proxy in front of the app
+const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+/// Identifies one request in the logs, on error pages and in the `X-Request-Id` response header
+#[derive(Debug, Clone, Default)]
+pub struct RequestId(pub String);
+
+/// The API key a request was authenticated with, left in the request extensions for handlers
+#[derive(Debug, Clone)]
+pub struct AuthenticatedKey(pub ApiKeyResponse);
+
+/// Checks API keys on the `/api` routes.
+///
+/// Requests without a key pass through unchanged and are judged by their session, if any.
+/// A key must be valid and within its rate limit; what it may do is then decided by the
+/// services from its scopes, exactly as for a signed-in user's role, so a key never
+/// reaches further than an anonymous caller plus its scopes. Routes tied to a person
+/// (carts, orders, accounts, admin, webhooks) do not accept keys at all.
+pub async fn api_key_auth(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let Some(secret) = presented_key(request.headers()) else {
+        return next.run(request).await;
+    };
+    let accepted = request
+        .extensions()
+        .get::<MatchedPath>()
+        .is_some_and(|path| accepts_keys(path.as_str()));
+    if !accepted {
+        return ApiError::new(StatusCode::FORBIDDEN, "API keys cannot be used with this endpoint").into_response();
+    }
+
+    match state.api_key_service.authenticate(&secret).await {
+        Ok(key) => {
+            request.extensions_mut().insert(AuthenticatedKey(key));
+            next.run(request).await
+        }
+        Err(err) => ApiError::from(err).into_response(),
+    }
+}
+
+/// The key from `Authorization: Bearer <key>` or `X-API-Key: <key>`
+fn presented_key(headers: &HeaderMap) -> Option<String> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let header = headers.get(API_KEY_HEADER).and_then(|value| value.to_str().ok());
+
+    bearer.or(header).map(str::trim).filter(|key| !key.is_empty()).map(str::to_string)
+}
+
+/// Whether a route, by its path pattern, serves API keys: the catalog and stock routes do
+fn accepts_keys(path: &str) -> bool {
+    ["/api/products", "/api/categories", "/api/stock/", "/api/reservations/"]
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+}
+
+/// Gives every request an ID and logs everything it does under it.
+///
+/// An `X-Request-Id` set by a proxy in front of the app is kept if it is short and plain;
+/// otherwise a new one is made. Handlers find it in the request extensions.
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_plain_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+    request.extensions_mut().insert(RequestId(id.clone()));
+
+    let span = tracing::info_span!("request", request_id = %id, method = %request.method(), path = %request.uri().path());
+    let mut response = next.run(request).instrument(span).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+fn is_plain_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
@@ -1,4 +1,7 @@
+pub mod errors;
+pub mod extractors;
 pub mod handlers;
+pub mod middleware;
 pub mod routes;
 pub mod templates;
 
@@ -1,47 +1,139 @@
 use axum::{
+    middleware,
     routing::{delete, get, post, put},
     Router,
 };
+use std::path::Path;
 use tower_http::services::ServeDir;
 
 use crate::presentation::handlers::{
     AppState,
     // HTML routes
-    home_page, product_detail_page_handler,
+    home_page, product_detail_page_handler, cart_page_handler, orders_page_handler,
+    login_page_handler, register_page_handler, login_handler, register_handler, logout_handler,
     // HTMX routes
     htmx_products_list, htmx_create_product, htmx_update_product, htmx_delete_product,
+    htmx_product_card, htmx_edit_product_form, htmx_product_suggestions,
+    htmx_cart_summary, htmx_add_to_cart, htmx_update_cart_item, htmx_remove_cart_item, htmx_clear_cart,
+    htmx_place_order, htmx_cancel_order, htmx_account_nav,
     // API routes
-    api_get_products, api_get_product, api_create_product, api_update_product, api_delete_product,
+    api_get_products, api_get_product, api_suggest_products, api_create_product, api_update_product, api_delete_product,
+    api_get_categories, api_get_category, api_create_category, api_update_category, api_delete_category,
+    api_get_product_categories, api_assign_product_categories,
+    api_get_webhooks, api_get_webhook, api_create_webhook, api_update_webhook, api_delete_webhook,
+    api_get_webhook_deliveries,
+    api_adjust_stock, api_get_stock_history, api_get_stock_reconciliation,
+    api_create_reservation, api_get_reservation, api_commit_reservation, api_release_reservation,
+    api_get_cart, api_add_cart_item, api_update_cart_item, api_remove_cart_item, api_clear_cart,
+    api_get_orders, api_place_order, api_get_order, api_pay_order, api_ship_order, api_deliver_order,
+    api_cancel_order,
+    api_register, api_login, api_logout, api_get_current_user, api_set_user_role,
+    api_create_api_key, api_get_api_keys, api_revoke_api_key,
 };
+use crate::presentation::errors::{api_errors, error_pages, not_found};
+use crate::presentation::middleware::{api_key_auth, request_id};
 
-pub fn create_router(state: AppState) -> Router {
+pub fn create_router(state: AppState, static_dir: &Path) -> Router {
     Router::new()
         // Static files
-        .nest_service("/static", ServeDir::new("static"))
+        .nest_service("/static", ServeDir::new(static_dir))
         
         // HTML routes for browser interface
         .route("/", get(home_page))
         .route("/products/:id", get(product_detail_page_handler))
+        .route("/cart", get(cart_page_handler))
+        .route("/orders", get(orders_page_handler))
+        .route("/login", get(login_page_handler))
+        .route("/login", post(login_handler))
+        .route("/register", get(register_page_handler))
+        .route("/register", post(register_handler))
+        .route("/logout", post(logout_handler))
         
         // HTMX routes for dynamic interactions
         .route("/htmx/products", get(htmx_products_list))
         .route("/htmx/products", post(htmx_create_product))
+        .route("/htmx/products/suggest", get(htmx_product_suggestions))
+        .route("/htmx/products/:id", get(htmx_product_card))
         .route("/htmx/products/:id", put(htmx_update_product))
         .route("/htmx/products/:id", delete(htmx_delete_product))
+        .route("/htmx/products/:id/edit", get(htmx_edit_product_form))
+        .route("/htmx/account", get(htmx_account_nav))
+        .route("/htmx/cart", delete(htmx_clear_cart))
+        .route("/htmx/cart/summary", get(htmx_cart_summary))
+        .route("/htmx/cart/items", post(htmx_add_to_cart))
+        .route("/htmx/cart/items/:product_id", put(htmx_update_cart_item))
+        .route("/htmx/cart/items/:product_id", delete(htmx_remove_cart_item))
+        .route("/htmx/orders", post(htmx_place_order))
+        .route("/htmx/orders/:id/cancel", post(htmx_cancel_order))
         
         // REST API routes for JSON interface
+        .merge(api_routes(state.clone()))
+        
+        // Health check endpoint
+        .route("/health", get(health_check))
+
+        // Unknown routes, and error pages for failed browser requests
+        .fallback(not_found)
+        .layer(middleware::from_fn_with_state(state.environment, error_pages))
+        .layer(middleware::from_fn(request_id))
+
+        .with_state(state)
+}
+
+/// The `/api` routes; API keys are checked before any handler runs, and every failure is
+/// answered with problem details
+fn api_routes(state: AppState) -> Router<AppState> {
+    Router::new()
         .route("/api/products", get(api_get_products))
+        .route("/api/products/suggest", get(api_suggest_products))
         .route("/api/products/:id", get(api_get_product))
         .route("/api/products", post(api_create_product))
         .route("/api/products/:id", put(api_update_product))
         .route("/api/products/:id", delete(api_delete_product))
-        
-        // Health check endpoint
-        .route("/health", get(health_check))
-        
-        .with_state(state)
+        .route("/api/products/:id/categories", get(api_get_product_categories))
+        .route("/api/products/:id/categories", put(api_assign_product_categories))
+        .route("/api/categories", get(api_get_categories))
+        .route("/api/categories", post(api_create_category))
+        .route("/api/categories/:id", get(api_get_category))
+        .route("/api/categories/:id", put(api_update_category))
+        .route("/api/categories/:id", delete(api_delete_category))
+        .route("/api/products/:id/stock/adjust", post(api_adjust_stock))
+        .route("/api/products/:id/stock/history", get(api_get_stock_history))
+        .route("/api/stock/reconciliation", get(api_get_stock_reconciliation))
+        .route("/api/products/:id/reservations", post(api_create_reservation))
+        .route("/api/reservations/:id", get(api_get_reservation))
+        .route("/api/reservations/:id/commit", post(api_commit_reservation))
+        .route("/api/reservations/:id/release", post(api_release_reservation))
+        .route("/api/auth/register", post(api_register))
+        .route("/api/auth/login", post(api_login))
+        .route("/api/auth/logout", post(api_logout))
+        .route("/api/auth/me", get(api_get_current_user))
+        .route("/api/users/:id/role", put(api_set_user_role))
+        .route("/api/admin/api-keys", get(api_get_api_keys))
+        .route("/api/admin/api-keys", post(api_create_api_key))
+        .route("/api/admin/api-keys/:id", delete(api_revoke_api_key))
+        .route("/api/cart", get(api_get_cart))
+        .route("/api/cart", delete(api_clear_cart))
+        .route("/api/cart/items", post(api_add_cart_item))
+        .route("/api/cart/items/:product_id", put(api_update_cart_item))
+        .route("/api/cart/items/:product_id", delete(api_remove_cart_item))
+        .route("/api/orders", get(api_get_orders))
+        .route("/api/orders", post(api_place_order))
+        .route("/api/orders/:id", get(api_get_order))
+        .route("/api/orders/:id/pay", post(api_pay_order))
+        .route("/api/orders/:id/ship", post(api_ship_order))
+        .route("/api/orders/:id/deliver", post(api_deliver_order))
+        .route("/api/orders/:id/cancel", post(api_cancel_order))
+        .route("/api/webhooks", get(api_get_webhooks))
+        .route("/api/webhooks", post(api_create_webhook))
+        .route("/api/webhooks/:id", get(api_get_webhook))
+        .route("/api/webhooks/:id", put(api_update_webhook))
+        .route("/api/webhooks/:id", delete(api_delete_webhook))
+        .route("/api/webhooks/:id/deliveries", get(api_get_webhook_deliveries))
+        .route_layer(middleware::from_fn_with_state(state, api_key_auth))
+        .layer(middleware::from_fn(api_errors))
 }
 
 async fn health_check() -> &'static str {
     "OK"
-}
\ No newline at end of file
+}
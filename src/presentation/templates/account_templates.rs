@@ -0,0 +1,44 @@
+use askama::Template;
+
+use crate::application::UserResponse;
+use crate::domain::{MAX_USER_NAME_LENGTH, MIN_PASSWORD_LENGTH};
+use crate::presentation::templates::render;
+
+#[derive(Template)]
+#[template(path = "pages/login.html")]
+struct LoginPage<'a> {
+    error: Option<&'a str>,
+}
+
+#[derive(Template)]
+#[template(path = "pages/register.html")]
+struct RegisterPage<'a> {
+    error: Option<&'a str>,
+    max_name_length: usize,
+    min_password_length: usize,
+}
+
+#[derive(Template)]
+#[template(path = "partials/account_nav.html")]
+struct AccountNav<'a> {
+    user: Option<&'a UserResponse>,
+}
+
+/// Generate the sign-in page, with the reason the last attempt failed
+pub fn login_page(error: Option<&str>) -> String {
+    render(&LoginPage { error })
+}
+
+/// Generate the registration page, with the reason the last attempt failed
+pub fn register_page(error: Option<&str>) -> String {
+    render(&RegisterPage {
+        error,
+        max_name_length: MAX_USER_NAME_LENGTH,
+        min_password_length: MIN_PASSWORD_LENGTH,
+    })
+}
+
+/// Generate the account links shown in page headers
+pub fn account_nav(user: Option<&UserResponse>) -> String {
+    render(&AccountNav { user })
+}
@@ -0,0 +1,70 @@
+use askama::Template;
+
+use crate::application::CartResponse;
+use crate::domain::{Currency, Money};
+use crate::presentation::templates::{filters, render};
+
+/// Feedback shown next to the cart after an HTMX action
+pub enum CartNotice<'a> {
+    Success(&'a str),
+    Error(&'a str),
+}
+
+impl CartNotice<'_> {
+    fn color(&self) -> &'static str {
+        match self {
+            CartNotice::Success(_) => "text-green-600",
+            CartNotice::Error(_) => "text-red-600",
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            CartNotice::Success(message) | CartNotice::Error(message) => message,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "pages/cart.html")]
+struct CartPage<'a> {
+    cart: &'a CartResponse,
+    notice: Option<CartNotice<'a>>,
+}
+
+#[derive(Template)]
+#[template(path = "partials/cart_contents.html")]
+struct CartContents<'a> {
+    cart: &'a CartResponse,
+    notice: Option<CartNotice<'a>>,
+}
+
+#[derive(Template)]
+#[template(path = "partials/cart_summary.html")]
+struct CartSummary<'a> {
+    cart: &'a CartResponse,
+    notice: Option<CartNotice<'a>>,
+}
+
+/// Generate the cart page
+pub fn cart_page(cart: &CartResponse) -> String {
+    render(&CartPage { cart, notice: None })
+}
+
+/// Generate the cart table with its total, swapped in after cart edits
+pub fn cart_contents(cart: &CartResponse, notice: Option<CartNotice>) -> String {
+    render(&CartContents { cart, notice })
+}
+
+/// Generate the cart badge shown in page headers
+pub fn cart_summary(cart: &CartResponse, notice: Option<CartNotice>) -> String {
+    render(&CartSummary { cart, notice })
+}
+
+/// Format an amount with its currency, e.g. "2499.99 USD"
+pub(crate) fn format_amount(amount_minor: i64, currency: &str, decimal: f64) -> String {
+    Currency::new(currency)
+        .and_then(|currency| Money::new(amount_minor, currency))
+        .map(|money| money.to_string())
+        .unwrap_or_else(|_| format!("{:.2} {}", decimal, currency))
+}
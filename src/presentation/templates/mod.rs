@@ -1,5 +1,298 @@
+//! HTML views, compiled from the askama templates under `templates/` and escaped on output.
+
+pub mod account_templates;
+pub mod cart_templates;
+pub mod order_templates;
 pub mod product_templates;
 pub mod page_templates;
 
+pub use account_templates::*;
+pub use cart_templates::*;
+pub use order_templates::*;
 pub use product_templates::*;
-pub use page_templates::*;
\ No newline at end of file
+pub use page_templates::*;
+
+use askama::Template;
+
+/// Render a template; failures are logged and produce an empty fragment
+pub(crate) fn render(template: &impl Template) -> String {
+    template.render().unwrap_or_else(|err| {
+        tracing::error!(error = %err, template = std::any::type_name_of_val(template), "Failed to render template");
+        String::new()
+    })
+}
+
+/// Filters available to every template
+pub(crate) mod filters {
+    use crate::application::ProductResponse;
+    use crate::presentation::templates::{format_amount, format_price};
+
+    /// A product's price with its currency, e.g. "2499.99 USD"
+    pub fn price(product: &ProductResponse) -> askama::Result<String> {
+        Ok(format_price(product))
+    }
+
+    /// An amount in minor units with its currency, falling back to the decimal amount
+    pub fn money(amount_minor: &i64, currency: &str, decimal: &f64) -> askama::Result<String> {
+        Ok(format_amount(*amount_minor, currency, *decimal))
+    }
+
+    /// An attribute key as a heading, e.g. `storage_size` → "Storage size"
+    pub fn attribute_label(key: &str) -> askama::Result<String> {
+        let spaced = key.replace(['_', '-'], " ");
+        let mut chars = spaced.chars();
+        Ok(match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        })
+    }
+
+    /// Badge colours for an order status
+    pub fn status_class(status: &str) -> askama::Result<&'static str> {
+        Ok(match status {
+            "pending" => "bg-yellow-100 text-yellow-800",
+            "paid" => "bg-blue-100 text-blue-800",
+            "shipped" => "bg-indigo-100 text-indigo-800",
+            "delivered" => "bg-green-100 text-green-800",
+            _ => "bg-gray-100 text-gray-600",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+
+    use crate::application::{
+        CategoryResponse, FacetsResponse, FieldError, HighlightResponse, PaginatedResponse, ProductResponse, ProductSuggestion,
+        StockMovementResponse, SuggestionsResponse, TextSegment, UserResponse,
+    };
+    use crate::domain::{FacetCount, Facets};
+
+    const HOSTILE: &str = r#"<script>alert("x")</script>"#;
+    const ESCAPED: &str = "&lt;script&gt;alert(&quot;x&quot;)&lt;/script&gt;";
+
+    fn hostile_product() -> ProductResponse {
+        ProductResponse {
+            id: 7,
+            name: HOSTILE.to_string(),
+            description: Some(format!("<img src=x onerror=alert(1)> {}", HOSTILE)),
+            price: 9.99,
+            price_minor: 999,
+            currency: "USD".to_string(),
+            stock: 3,
+            reserved: 0,
+            available: 3,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            version: 1,
+            tags: vec![HOSTILE.to_string()],
+            attributes: BTreeMap::from([("brand".to_string(), HOSTILE.to_string())]),
+            highlight: None,
+        }
+    }
+
+    fn everything() -> CatalogControls {
+        CatalogControls { can_create: true, can_edit: true, can_delete: true, can_view_stock: true }
+    }
+
+    #[test]
+    fn hostile_product_fields_are_escaped_in_catalog_views() {
+        let page = PaginatedResponse::new(vec![hostile_product()], 1, 0, 12);
+
+        for html in [
+            products_page(&page, &[], None, everything()),
+            product_list_partial(&page, everything()),
+            product_card(&page.items[0], everything()),
+        ] {
+            assert!(html.contains("id=\"product-7\""));
+            assert!(html.contains(ESCAPED));
+            assert!(html.contains("&lt;img src=x onerror=alert(1)&gt;"));
+            assert!(!html.contains(HOSTILE));
+            assert!(!html.contains("<img"));
+        }
+    }
+
+    #[test]
+    fn search_highlights_are_marked_and_escaped() {
+        let segment = |text: &str, matched| TextSegment { text: text.to_string(), matched };
+        let product = ProductResponse {
+            highlight: Some(HighlightResponse {
+                name: vec![segment("<b>", false), segment("Mac", true), segment("Book", false)],
+                snippet: vec![segment(HOSTILE, true)],
+            }),
+            ..hostile_product()
+        };
+
+        let html = product_card(&product, everything());
+
+        assert!(html.contains(r#"&lt;b&gt;<mark class="bg-yellow-200 rounded-sm">Mac</mark>Book"#));
+        assert!(html.contains(&format!(">{}</mark>", ESCAPED)));
+        assert!(!html.contains(HOSTILE));
+        assert!(!html.contains("<b>"));
+    }
+
+    #[test]
+    fn search_suggestions_are_escaped_and_empty_when_there_are_none() {
+        let suggestions = SuggestionsResponse {
+            query: "scirpt".to_string(),
+            completions: Vec::new(),
+            did_you_mean: Some(HOSTILE.to_string()),
+            corrections: vec![ProductSuggestion { id: 7, name: HOSTILE.to_string() }],
+        };
+
+        let html = search_suggestions(&suggestions);
+        assert!(html.contains("href=\"/products/7\""));
+        assert!(html.contains(&format!("<strong>{}</strong>", ESCAPED)));
+        assert!(!html.contains(HOSTILE));
+
+        let nothing = SuggestionsResponse { did_you_mean: None, corrections: Vec::new(), ..suggestions };
+        assert!(search_suggestions(&nothing).trim().is_empty());
+    }
+
+    #[test]
+    fn hostile_product_and_history_fields_are_escaped_on_the_detail_page() {
+        let history = vec![StockMovementResponse {
+            delta: -2,
+            reason: "sale".to_string(),
+            actor: HOSTILE.to_string(),
+            note: Some("<b>bold</b>".to_string()),
+            stock_after: 3,
+            created_at: Utc::now(),
+        }];
+
+        let html = product_detail_page(&hostile_product(), &history, &[], everything());
+        assert!(html.contains(&format!("<title>{} - Product Details</title>", ESCAPED)));
+        assert!(html.contains("&lt;b&gt;bold&lt;/b&gt;"));
+        assert!(html.contains(">-2<"));
+        assert!(!html.contains(HOSTILE));
+        assert!(!html.contains("<b>"));
+    }
+
+    #[test]
+    fn category_names_are_escaped_in_the_sidebar_and_breadcrumbs() {
+        let category = |id: i64, name: &str, slug: &str, depth: usize| CategoryResponse {
+            id,
+            name: name.to_string(),
+            slug: slug.to_string(),
+            parent_id: None,
+            depth,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let categories = vec![category(1, "Electronics", "electronics", 0), category(2, HOSTILE, "phones", 1)];
+        let page = PaginatedResponse::new(vec![hostile_product()], 1, 0, 12);
+
+        let html = products_page(&page, &categories, Some("phones"), everything());
+        assert!(html.contains(r#"href="/?category=electronics""#));
+        assert!(html.contains(r#"<input type="hidden" name="category" value="phones">"#));
+        assert!(html.contains(&format!("bg-blue-100 text-blue-800 font-medium\">{}</a>", ESCAPED)));
+        assert!(!html.contains(HOSTILE));
+        assert!(!products_page(&page, &[], None, everything()).contains("Categories"));
+
+        let html = product_detail_page(&hostile_product(), &[], &[categories], everything());
+        assert!(html.contains(r#"<a href="/?category=phones" class="text-blue-600 hover:text-blue-800">"#));
+        assert!(html.contains(&format!(">{}</p>", ESCAPED)));
+        assert!(!html.contains(HOSTILE));
+        assert!(product_detail_page(&hostile_product(), &[], &[], everything()).contains(">Uncategorized</p>"));
+    }
+
+    /// The `<input>` tag of the facet checkbox for `name` = `value`
+    fn checkbox<'a>(html: &'a str, name: &str, value: &str) -> Option<&'a str> {
+        let start = html.find(&format!(r#"name="{}" value="{}""#, name, value))?;
+        let start = html[..start].rfind("<input")?;
+        Some(&html[start..start + html[start..].find('>')?])
+    }
+
+    #[test]
+    fn facet_checkboxes_show_counts_keep_choices_and_escape_values() {
+        let count = |value: &str, count| FacetCount { value: value.to_string(), count };
+        let facets = Facets {
+            tags: vec![count(HOSTILE, 2), count("sale", 1)],
+            attributes: vec![("storage_size".to_string(), vec![count("1TB", 3), count("512GB", 1)])],
+        };
+        let chosen = BTreeMap::from([("storage_size".to_string(), vec!["1TB".to_string()])]);
+        let page = PaginatedResponse::new(vec![hostile_product()], 1, 0, 12)
+            .with_facets(FacetsResponse::new(facets, &[], &chosen));
+
+        let html = product_list_partial(&page, everything());
+        assert!(html.contains(">Storage size</legend>"));
+        assert!(checkbox(&html, "attr.storage_size", "1TB").unwrap().contains("checked"));
+        assert!(!checkbox(&html, "attr.storage_size", "512GB").unwrap().contains("checked"));
+        assert!(checkbox(&html, "tag", "sale").unwrap().contains(r#"form="product-filters""#));
+        assert!(html.contains(&format!(r#"name="tag" value="{}""#, ESCAPED)));
+        assert!(!html.contains(HOSTILE));
+
+        // Listings without facets, such as the JSON API's, render no filters
+        let plain = PaginatedResponse::new(vec![hostile_product()], 1, 0, 12);
+        assert!(!product_list_partial(&plain, everything()).contains("facet-filters"));
+    }
+
+    #[test]
+    fn product_tags_and_attributes_link_to_filtered_listings() {
+        let product = ProductResponse {
+            tags: vec!["new arrival".to_string()],
+            attributes: BTreeMap::from([("color".to_string(), "Space Gray".to_string())]),
+            ..hostile_product()
+        };
+
+        assert!(product_card(&product, everything()).contains(r#"href="/?tag=new%20arrival""#));
+        let html = product_detail_page(&product, &[], &[], everything());
+        assert!(html.contains(r#"href="/?attr.color=Space%20Gray""#));
+        assert!(html.contains(">Color</th>"));
+        assert!(!product_detail_page(&hostile_product(), &[], &[], everything()).contains(HOSTILE));
+    }
+
+    #[test]
+    fn user_supplied_text_is_escaped_in_account_views() {
+        let user = UserResponse {
+            id: 1,
+            email: "mallory@example.com".to_string(),
+            name: HOSTILE.to_string(),
+            role: "viewer".to_string(),
+            created_at: Utc::now(),
+        };
+
+        let nav = account_nav(Some(&user));
+        assert!(nav.contains(ESCAPED) && !nav.contains(HOSTILE));
+        let login = login_page(Some(HOSTILE));
+        assert!(login.contains(ESCAPED) && !login.contains(HOSTILE));
+    }
+
+    #[test]
+    fn catalog_controls_follow_the_visitor() {
+        let page = PaginatedResponse::new(vec![hostile_product()], 1, 0, 12);
+
+        let visitor = products_page(&page, &[], None, CatalogControls::default());
+        assert!(!visitor.contains("Add New Product") && !visitor.contains("hx-delete"));
+        let editor = products_page(&page, &[], None, CatalogControls { can_delete: false, ..everything() });
+        assert!(editor.contains("Add New Product") && !editor.contains("hx-delete"));
+    }
+
+    #[test]
+    fn product_forms_show_each_field_error_next_to_its_submitted_value() {
+        let form = ProductForm {
+            name: HOSTILE.to_string(),
+            price: "-1".to_string(),
+            currency: "EUR".to_string(),
+            ..ProductForm::default()
+        }
+        .with_errors(vec![
+            FieldError { field: "name".to_string(), code: "too_long", message: "Product name too long".to_string() },
+            FieldError { field: "price".to_string(), code: "negative", message: "Price cannot be negative".to_string() },
+        ]);
+
+        for html in [product_form(&form, false), product_edit_form(7, 3, &form)] {
+            assert!(html.contains(&format!("value=\"{}\"", ESCAPED)));
+            assert!(html.contains("Product name too long") && html.contains("Price cannot be negative"));
+            assert!(html.contains("value=\"-1\""));
+            assert!(html.contains("<option value=\"EUR\" selected>"));
+            assert!(!html.contains(HOSTILE));
+        }
+        assert!(product_edit_form(7, 3, &form).contains("name=\"version\" value=\"3\""));
+        assert!(product_form(&ProductForm::default(), true).contains("hx-swap-oob=\"true\""));
+    }
+}
@@ -0,0 +1,28 @@
+use askama::Template;
+
+use crate::application::OrderResponse;
+use crate::presentation::templates::{filters, render};
+
+#[derive(Template)]
+#[template(path = "pages/orders.html")]
+struct OrdersPage<'a> {
+    orders: &'a [OrderResponse],
+    error: Option<&'a str>,
+}
+
+#[derive(Template)]
+#[template(path = "partials/order_row.html")]
+struct OrderRow<'a> {
+    order: &'a OrderResponse,
+    error: Option<&'a str>,
+}
+
+/// Generate the order list page
+pub fn orders_page(orders: &[OrderResponse]) -> String {
+    render(&OrdersPage { orders, error: None })
+}
+
+/// Generate one order row, swapped in after a cancellation, with an optional error
+pub fn order_row(order: &OrderResponse, error: Option<&str>) -> String {
+    render(&OrderRow { order, error })
+}
@@ -1,38 +1,37 @@
-use crate::application::ProductResponse;
-use std::fs;
+use askama::Template;
 
-/// Load template from file
-fn load_template(template_name: &str) -> String {
-    fs::read_to_string(format!("static/html/{}", template_name))
-        .unwrap_or_else(|_| {
-            eprintln!("Warning: Could not load template {}", template_name);
-            String::new()
-        })
+use crate::application::{CategoryResponse, ProductResponse, StockMovementResponse};
+use crate::presentation::templates::{filters, render, CatalogControls};
+
+#[derive(Template)]
+#[template(path = "pages/product_detail.html")]
+struct ProductDetailPage<'a> {
+    product: &'a ProductResponse,
+    history: &'a [StockMovementResponse],
+    breadcrumbs: &'a [Vec<CategoryResponse>],
+    controls: CatalogControls,
+}
+
+#[derive(Template)]
+#[template(path = "pages/error.html")]
+struct ErrorPage<'a> {
+    code: u16,
+    message: &'a str,
+    request_id: &'a str,
 }
 
-/// Generate product detail page
-pub fn product_detail_page(product: &ProductResponse) -> String {
-    let template = load_template("product_detail.html");
-    
-    template
-        .replace("{{PRODUCT_NAME}}", &product.name)
-        .replace("{{PRODUCT_ID}}", &product.id.to_string())
-        .replace("{{PRODUCT_DESCRIPTION}}", product.description.as_deref().unwrap_or("No description available for this product."))
-        .replace("{{PRODUCT_PRICE}}", &format!("{:.2}", product.price))
-        .replace("{{PRODUCT_STOCK}}", &product.stock.to_string())
-        .replace("{{STOCK_STATUS_CLASS}}", if product.stock > 0 { "text-green-600" } else { "text-red-600" })
-        .replace("{{STOCK_STATUS_TEXT}}", if product.stock > 0 { "In Stock" } else { "Out of Stock" })
-        .replace("{{CREATED_AT}}", &product.created_at.format("%B %d, %Y at %H:%M UTC").to_string())
-        .replace("{{UPDATED_AT}}", &product.updated_at.format("%B %d, %Y at %H:%M UTC").to_string())
-        .replace("{{PRODUCT_SKU}}", &format!("{:06}", product.id))
+/// Generate product detail page with the product's recent stock history and one
+/// breadcrumb trail per category it belongs to
+pub fn product_detail_page(
+    product: &ProductResponse,
+    history: &[StockMovementResponse],
+    breadcrumbs: &[Vec<CategoryResponse>],
+    controls: CatalogControls,
+) -> String {
+    render(&ProductDetailPage { product, history, breadcrumbs, controls })
 }
 
-/// Generate error page
-#[allow(dead_code)]
-pub fn error_page(error_code: u16, message: &str) -> String {
-    let template = load_template("error.html");
-    
-    template
-        .replace("{{ERROR_CODE}}", &error_code.to_string())
-        .replace("{{ERROR_MESSAGE}}", message)
-}
\ No newline at end of file
+/// Generate error page; `request_id` is shown so visitors can quote it to support
+pub fn error_page(error_code: u16, message: &str, request_id: &str) -> String {
+    render(&ErrorPage { code: error_code, message, request_id })
+}
@@ -1,44 +1,189 @@
-use crate::application::ProductResponse;
-use std::fs;
+use askama::Template;
 
-/// Load template from file
-fn load_template(template_name: &str) -> String {
-    fs::read_to_string(format!("static/html/{}", template_name))
-        .unwrap_or_else(|_| {
-            eprintln!("Warning: Could not load template {}", template_name);
-            String::new()
-        })
+use crate::application::{
+    Actor, CategoryResponse, CreateProductRequest, FieldError, PaginatedResponse, Permission, ProductResponse,
+    SuggestionsResponse, UpdateProductRequest,
+};
+use crate::domain::{Currency, Money};
+use crate::presentation::templates::{filters, render};
+
+/// Catalog controls the current visitor may see; the service enforces the same rules
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CatalogControls {
+    pub can_create: bool,
+    pub can_edit: bool,
+    pub can_delete: bool,
+    pub can_view_stock: bool,
+}
+
+impl CatalogControls {
+    pub fn for_actor(actor: &Actor) -> Self {
+        Self {
+            can_create: actor.can(Permission::CreateProduct),
+            can_edit: actor.can(Permission::EditProduct),
+            can_delete: actor.can(Permission::DeleteProduct),
+            can_view_stock: actor.can(Permission::ViewStock),
+        }
+    }
+}
+
+/// Currencies offered in the product forms
+const FORM_CURRENCIES: [&str; 4] = ["USD", "EUR", "GBP", "JPY"];
+
+/// What the add and edit product forms show: the values to fill in and any errors against them
+#[derive(Debug, Clone, Default)]
+pub struct ProductForm {
+    pub name: String,
+    pub description: String,
+    pub price: String,
+    pub currency: String,
+    pub stock: String,
+    pub errors: Vec<FieldError>,
+}
+
+impl ProductForm {
+    /// The values a failed add request submitted
+    pub fn submitted(request: &CreateProductRequest) -> Self {
+        Self {
+            name: request.name.clone(),
+            description: request.description.clone().unwrap_or_default(),
+            price: request.price.to_string(),
+            currency: request.currency.clone().unwrap_or_default(),
+            stock: request.stock.to_string(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// The values a failed edit request submitted
+    pub fn submitted_update(request: &UpdateProductRequest) -> Self {
+        Self {
+            name: request.name.clone().unwrap_or_default(),
+            description: request.description.clone().flatten().unwrap_or_default(),
+            price: request.price.map(|price| price.to_string()).unwrap_or_default(),
+            currency: request.currency.clone().unwrap_or_default(),
+            stock: request.stock.map(|stock| stock.to_string()).unwrap_or_default(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// A product's current values, to start editing from
+    pub fn for_product(product: &ProductResponse) -> Self {
+        Self {
+            name: product.name.clone(),
+            description: product.description.clone().unwrap_or_default(),
+            price: product.price.to_string(),
+            currency: product.currency.clone(),
+            stock: product.stock.to_string(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn with_errors(self, errors: Vec<FieldError>) -> Self {
+        Self { errors, ..self }
+    }
+
+    /// The first error against `field`
+    fn error(&self, field: &str) -> Option<&str> {
+        self.errors.iter().find(|error| error.field == field).map(|error| error.message.as_str())
+    }
+
+    fn currencies(&self) -> &'static [&'static str] {
+        &FORM_CURRENCIES
+    }
+
+    fn has_currency(&self, code: &str) -> bool {
+        let selected = if self.currency.is_empty() { "USD" } else { self.currency.as_str() };
+        selected.eq_ignore_ascii_case(code)
+    }
+}
+
+#[derive(Template)]
+#[template(path = "pages/products.html")]
+struct ProductsPage<'a> {
+    page: &'a PaginatedResponse<ProductResponse>,
+    categories: &'a [CategoryResponse],
+    /// Slug of the category being browsed
+    selected: Option<&'a str>,
+    controls: CatalogControls,
+    form: ProductForm,
+}
+
+#[derive(Template)]
+#[template(path = "partials/product_card.html")]
+struct ProductCard<'a> {
+    product: &'a ProductResponse,
+    controls: CatalogControls,
 }
 
-/// Generate the main products page with search and add product form
-pub fn products_page(products: &[ProductResponse]) -> String {
-    let product_cards = products.iter().map(product_card).collect::<Vec<_>>().join("");
-    let template = load_template("products.html");
-    
-    template.replace("{{PRODUCT_CARDS}}", &product_cards)
+#[derive(Template)]
+#[template(path = "partials/product_form.html")]
+struct ProductFormPartial<'a> {
+    form: &'a ProductForm,
+    swap_oob: bool,
+}
+
+#[derive(Template)]
+#[template(path = "partials/product_edit_form.html")]
+struct ProductEditForm<'a> {
+    id: i64,
+    version: i64,
+    form: &'a ProductForm,
+}
+
+#[derive(Template)]
+#[template(path = "partials/product_list.html")]
+struct ProductList<'a> {
+    page: &'a PaginatedResponse<ProductResponse>,
+    controls: CatalogControls,
+}
+
+#[derive(Template)]
+#[template(path = "partials/search_suggestions.html")]
+struct SearchSuggestions<'a> {
+    suggestions: &'a SuggestionsResponse,
+}
+
+/// Generate the main products page with search, the category sidebar and, for editors,
+/// the add product form. `selected` is the slug of the category being browsed.
+pub fn products_page(
+    page: &PaginatedResponse<ProductResponse>,
+    categories: &[CategoryResponse],
+    selected: Option<&str>,
+    controls: CatalogControls,
+) -> String {
+    render(&ProductsPage { page, categories, selected, controls, form: ProductForm::default() })
 }
 
 /// Generate a single product card
-pub fn product_card(product: &ProductResponse) -> String {
-    let template = load_template("product_card.html");
-    let stock_badge_class = if product.stock > 0 {
-        "bg-green-100 text-green-800"
-    } else {
-        "bg-red-100 text-red-800"
-    };
-
-    template
-        .replace("{{PRODUCT_ID}}", &product.id.to_string())
-        .replace("{{PRODUCT_NAME}}", &product.name)
-        .replace("{{STOCK_BADGE_CLASS}}", stock_badge_class)
-        .replace("{{PRODUCT_STOCK}}", &product.stock.to_string())
-        .replace("{{PRODUCT_DESCRIPTION}}", product.description.as_deref().unwrap_or("No description provided"))
-        .replace("{{PRODUCT_PRICE}}", &format!("{:.2}", product.price))
-        .replace("{{CREATED_AT}}", &product.created_at.format("%Y-%m-%d %H:%M").to_string())
-        .replace("{{UPDATED_AT}}", &product.updated_at.format("%Y-%m-%d %H:%M").to_string())
+pub fn product_card(product: &ProductResponse, controls: CatalogControls) -> String {
+    render(&ProductCard { product, controls })
+}
+
+/// Generate the add product form. `swap_oob` marks it for an out-of-band swap, to reset the
+/// form alongside another response.
+pub fn product_form(form: &ProductForm, swap_oob: bool) -> String {
+    render(&ProductFormPartial { form, swap_oob })
+}
+
+/// Generate the edit form shown in place of a product card
+pub fn product_edit_form(id: i64, version: i64, form: &ProductForm) -> String {
+    render(&ProductEditForm { id, version, form })
 }
 
 /// Generate product list partial for HTMX updates
-pub fn product_list_partial(products: &[ProductResponse]) -> String {
-    products.iter().map(product_card).collect::<Vec<_>>().join("")
-}
\ No newline at end of file
+pub fn product_list_partial(page: &PaginatedResponse<ProductResponse>, controls: CatalogControls) -> String {
+    render(&ProductList { page, controls })
+}
+
+/// Generate the dropdown under the search box; empty when there is nothing to suggest
+pub fn search_suggestions(suggestions: &SuggestionsResponse) -> String {
+    render(&SearchSuggestions { suggestions })
+}
+
+/// Format a product price with its currency, e.g. "2499.99 USD"
+pub fn format_price(product: &ProductResponse) -> String {
+    Currency::new(&product.currency)
+        .and_then(|currency| Money::new(product.price_minor, currency))
+        .map(|money| money.to_string())
+        .unwrap_or_else(|_| format!("{:.2} {}", product.price, product.currency))
+}